@@ -0,0 +1,83 @@
+//! Per-ROM achievements.
+//!
+//! An achievements file lists one achievement per line as a name and a condition (see
+//! `chip8::condition`) separated by a colon. Empty lines and lines starting with `#` are ignored:
+//!
+//! ``` text
+//! # Astro Dodge
+//! Survivor: mem[0x3A0] >= 100
+//! ```
+//!
+//! The conditions are evaluated every frame. The names of the unlocked achievements are saved, one
+//! per line, in a file next to the achievements file with the `unlocked` extension.
+
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use snafu::ResultExt;
+
+use chip8::{condition::Condition, Chip8};
+
+use crate::{InvalidAchievementSnafu, IoSnafu, Result};
+
+pub struct Achievements {
+    achievements: Vec<(String, Condition)>,
+    unlocked: HashSet<String>,
+    unlocked_path: PathBuf,
+}
+
+impl Achievements {
+    /// Loads achievements and the names of those unlocked in previous sessions.
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut achievements = Vec::new();
+        for (index, text) in fs::read_to_string(path).context(IoSnafu)?.lines().enumerate() {
+            let text = text.trim();
+            if text.is_empty() || text.starts_with('#') {
+                continue;
+            }
+            let line = index + 1;
+            let Some((name, condition)) = text.split_once(':') else {
+                let reason = "expected a name and a condition separated by `:`";
+                return InvalidAchievementSnafu { path, line, reason }.fail();
+            };
+            let condition = condition.parse().map_err(|err: chip8::Error| {
+                InvalidAchievementSnafu { path, line, reason: err.to_string() }.build()
+            })?;
+            achievements.push((name.trim().to_string(), condition));
+        }
+
+        let unlocked_path = path.with_extension("unlocked");
+        let unlocked = match fs::read_to_string(&unlocked_path) {
+            Ok(unlocked) => unlocked.lines().map(str::to_string).collect(),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => HashSet::new(),
+            Err(err) => return Err(err).context(IoSnafu),
+        };
+        Ok(Self { achievements, unlocked, unlocked_path })
+    }
+
+    /// Returns the names of the achievements unlocked for the first time, saving them.
+    pub fn update(&mut self, chip8: &Chip8) -> Result<Vec<String>> {
+        let mut newly_unlocked = Vec::new();
+        for (name, condition) in &self.achievements {
+            if !self.unlocked.contains(name) && condition.evaluate(chip8) {
+                self.unlocked.insert(name.clone());
+                newly_unlocked.push(name.clone());
+            }
+        }
+        if !newly_unlocked.is_empty() {
+            let mut file = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.unlocked_path)
+                .context(IoSnafu)?;
+            for name in &newly_unlocked {
+                writeln!(file, "{name}").context(IoSnafu)?;
+            }
+        }
+        Ok(newly_unlocked)
+    }
+}
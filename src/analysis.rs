@@ -0,0 +1,173 @@
+//! Static analysis of a program that is loaded at 0x200: which bytes are code and which are data,
+//! where the subroutines are, and which instructions refer to which addresses.
+//!
+//! A byte is code if it can be reached from the entry point by executing instructions, taking
+//! both ways of every skip and following jumps and calls. Bnnn jumps to an address that depends on
+//! a register, so its targets are not followed. Every other byte is data.
+//!
+//! Along each path, the value of I is known from the last Annn until an instruction changes it
+//! otherwise (Fx1E, Fx29, Fx55, and Fx65) or a subroutine is called, so Dxyn can be told which
+//! sprite it draws from there.
+
+use alloc::{collections::BTreeSet, vec, vec::Vec};
+use core::ops::Range;
+
+use crate::{disasm::Item, instruction::Instruction, PROGRAM_SPACE};
+
+/// What an analysis finds in a program.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Analysis {
+    /// The instructions and the bytes of data, in ascending order of address.
+    pub items: Vec<Item>,
+    /// The addresses in the program of the subroutines called by 2nnn, in ascending order.
+    pub subroutines: Vec<usize>,
+    /// The ranges of addresses of data, in ascending order.
+    pub data: Vec<Range<usize>>,
+    /// The references from instructions to addresses, in ascending order of target and then of
+    /// source.
+    pub references: Vec<Reference>,
+}
+
+/// A reference from the instruction at `from` to the address `to`, which need not be in the
+/// program.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Reference {
+    pub from: usize,
+    pub to: usize,
+    pub kind: ReferenceKind,
+}
+
+/// How an instruction refers to an address.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum ReferenceKind {
+    /// 1nnn jumps to it.
+    Jump,
+    /// 2nnn calls it.
+    Call,
+    /// Annn sets I to it.
+    LoadI,
+    /// Dxyn draws the sprite at it.
+    Draw,
+}
+
+impl Analysis {
+    /// Returns the references to `address`.
+    ///
+    /// ```
+    /// use chip8::analysis::{self, ReferenceKind};
+    ///
+    /// // CALL 0x206; JP 0x202; (data); LD I, 0x204; DRW V0, V0, 1; RET
+    /// let rom = [0x22, 0x06, 0x12, 0x02, 0x80, 0x00, 0xA2, 0x04, 0xD0, 0x01, 0x00, 0xEE];
+    /// let analysis = analysis::analyze(&rom);
+    /// assert_eq!(analysis.subroutines, [0x206]);
+    /// assert_eq!(analysis.data, [0x204..0x206]);
+    /// let kinds: Vec<_> = analysis.references_to(0x204).map(|reference| reference.kind).collect();
+    /// assert_eq!(kinds, [ReferenceKind::LoadI, ReferenceKind::Draw]);
+    /// ```
+    pub fn references_to(&self, address: usize) -> impl Iterator<Item = &Reference> {
+        let start = self.references.partition_point(|reference| reference.to < address);
+        self.references[start..].iter().take_while(move |reference| reference.to == address)
+    }
+
+    /// Returns the references from the instruction at `address`.
+    pub fn references_from(&self, address: usize) -> impl Iterator<Item = &Reference> {
+        self.references.iter().filter(move |reference| reference.from == address)
+    }
+}
+
+/// Analyzes a program that is loaded at 0x200.
+pub fn analyze(program: &[u8]) -> Analysis {
+    let start = PROGRAM_SPACE.start;
+    let end = start + program.len();
+    let opcode = |address: usize| {
+        let offset = address.checked_sub(start)?;
+        let bytes = program.get(offset..(offset + 2))?;
+        Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+    };
+
+    let mut is_code = vec![false; program.len()];
+    let mut subroutines = BTreeSet::new();
+    let mut references = BTreeSet::new();
+    // Each address is visited once for each value of I known on the paths to it.
+    let mut visited = BTreeSet::new();
+    let mut pending = vec![(start, None)];
+    while let Some((address, i)) = pending.pop() {
+        if !(start..end).contains(&address) || !visited.insert((address, i)) {
+            continue;
+        }
+        let Some(instruction) = opcode(address).and_then(Instruction::decode) else {
+            continue;
+        };
+        is_code[address - start] = true;
+        let next = address + 2;
+        let mut reference = |to: u16, kind| {
+            references.insert((usize::from(to), address, kind));
+        };
+        match instruction {
+            Instruction::Jump(nnn) => {
+                reference(nnn, ReferenceKind::Jump);
+                pending.push((usize::from(nnn), i));
+            }
+            Instruction::Call(nnn) => {
+                reference(nnn, ReferenceKind::Call);
+                subroutines.insert(usize::from(nnn));
+                pending.extend([(usize::from(nnn), i), (next, None)]);
+            }
+            Instruction::Return | Instruction::JumpOffset(_) => (),
+            Instruction::SkipIfEqual { .. }
+            | Instruction::SkipIfNotEqual { .. }
+            | Instruction::SkipIfRegEqual { .. }
+            | Instruction::SkipIfRegNotEqual { .. }
+            | Instruction::SkipIfKey { .. }
+            | Instruction::SkipIfNotKey { .. } => pending.extend([(next, i), (next + 2, i)]),
+            Instruction::LoadI(nnn) => {
+                reference(nnn, ReferenceKind::LoadI);
+                pending.push((next, Some(nnn)));
+            }
+            Instruction::Draw { .. } => {
+                if let Some(i) = i {
+                    reference(i, ReferenceKind::Draw);
+                }
+                pending.push((next, i));
+            }
+            Instruction::AddI { .. }
+            | Instruction::LoadDigit { .. }
+            | Instruction::Store { .. }
+            | Instruction::Restore { .. } => pending.push((next, None)),
+            _ => pending.push((next, i)),
+        }
+    }
+
+    let mut items = Vec::new();
+    let mut data: Vec<Range<usize>> = Vec::new();
+    let mut address = start;
+    while address < end {
+        match opcode(address).and_then(Instruction::decode) {
+            Some(instruction) if is_code[address - start] => {
+                let opcode = opcode(address).unwrap_or_default();
+                items.push(Item::Code { address, opcode, instruction });
+                address += 2;
+            }
+            _ => {
+                items.push(Item::Data { address, byte: program[address - start] });
+                match data.last_mut() {
+                    Some(range) if range.end == address => range.end += 1,
+                    _ => data.push(address..(address + 1)),
+                }
+                address += 1;
+            }
+        }
+    }
+    Analysis {
+        items,
+        subroutines: subroutines
+            .into_iter()
+            .filter(|address| (start..end).contains(address))
+            .collect(),
+        data,
+        references: references
+            .into_iter()
+            .map(|(to, from, kind)| Reference { from, to, kind })
+            .collect(),
+    }
+}
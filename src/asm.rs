@@ -0,0 +1,320 @@
+//! An assembler for the mnemonics of Cowgod's Chip-8 Technical Reference, which the disassembler
+//! prints.
+//!
+//! Each line holds an optional label followed by `:`, an optional instruction, and an optional
+//! comment starting with `;`. Mnemonics and register names are case-insensitive, labels are not.
+//! Numbers are decimal, hexadecimal (`0x2A`), or binary (`0b101010`), and a label can be used
+//! wherever a number is expected, standing for its address. `DB` emits bytes, and `SHR Vx` and
+//! `SHL Vx` shift Vx into itself. The program is assembled to be loaded at 0x200.
+//!
+//! ``` text
+//! start:  LD I, sprite    ; Draws a zero in the top left corner
+//!         DRW V0, V0, 5
+//! loop:   JP loop
+//! sprite: DB 0xF0, 0x90, 0x90, 0x90, 0xF0
+//! ```
+
+use std::{
+    collections::HashMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use snafu::ensure;
+
+use crate::{
+    instruction::Instruction, isa::Operand, InvalidAssemblySnafu, ProgramTooLargeSnafu, Result,
+    PROGRAM_SPACE,
+};
+
+/// Assembles a program, returning the ROM image.
+pub fn assemble(source: &str) -> Result<Vec<u8>> {
+    let mut program = Program::default();
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let mut text = line.split(';').next().unwrap_or_default().trim();
+        if let Some((label, rest)) = text.split_once(':') {
+            let label = label.trim();
+            ensure!(
+                is_label(label),
+                InvalidAssemblySnafu { line: line_number, reason: "invalid label" }
+            );
+            program.define(label, line_number)?;
+            text = rest.trim();
+        }
+        if text.is_empty() {
+            continue;
+        }
+        let (mnemonic, operands) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        let operands = if operands.trim().is_empty() {
+            Vec::new()
+        } else {
+            operands
+                .split(',')
+                .map(|operand| Argument::parse(operand.trim(), line_number))
+                .collect::<Result<_>>()?
+        };
+        statement(&mut program, &mnemonic.to_ascii_uppercase(), &operands, line_number)?;
+    }
+    program.finish()
+}
+
+/// An operand as written in the source.
+#[derive(Clone, Debug)]
+enum Argument {
+    Register(u8),
+    I,
+    IndirectI,
+    DelayTimer,
+    SoundTimer,
+    Key,
+    Font,
+    Bcd,
+    Value(Value),
+}
+
+impl Argument {
+    fn parse(text: &str, line: usize) -> Result<Self> {
+        let argument = match text.to_ascii_uppercase().as_str() {
+            "I" => Argument::I,
+            "[I]" => Argument::IndirectI,
+            "DT" => Argument::DelayTimer,
+            "ST" => Argument::SoundTimer,
+            "K" => Argument::Key,
+            "F" => Argument::Font,
+            "B" => Argument::Bcd,
+            upper => match upper.strip_prefix('V').map(|x| u8::from_str_radix(x, 16)) {
+                Some(Ok(x)) if x < 16 && upper.len() == 2 => Argument::Register(x),
+                _ => Argument::Value(Value::parse(text, line)?),
+            },
+        };
+        Ok(argument)
+    }
+}
+
+fn statement(
+    program: &mut Program,
+    mnemonic: &str,
+    arguments: &[Argument],
+    line: usize,
+) -> Result<()> {
+    use Argument::{Bcd, DelayTimer, Font, IndirectI, Key, Register, SoundTimer, Value, I};
+
+    if mnemonic == "DB" {
+        ensure!(!arguments.is_empty(), InvalidAssemblySnafu { line, reason: "expected bytes" });
+        for argument in arguments {
+            let Value(value) = argument else {
+                return InvalidAssemblySnafu { line, reason: "expected a byte" }.fail();
+            };
+            program.byte(value.clone(), line)?;
+        }
+        return Ok(());
+    }
+
+    let nnn = |value: &self::Value| Some((Operand::Nnn, value.clone()));
+    let kk = |value: &self::Value| Some((Operand::Kk, value.clone()));
+    let (instruction, operand) = match (mnemonic, arguments) {
+        ("CLS", []) => (Instruction::ClearScreen, None),
+        ("RET", []) => (Instruction::Return, None),
+        ("SYS", [Value(address)]) => (Instruction::Syscall(0), nnn(address)),
+        ("JP", [Value(address)]) => (Instruction::Jump(0), nnn(address)),
+        ("JP", [Register(0), Value(address)]) => (Instruction::JumpOffset(0), nnn(address)),
+        ("CALL", [Value(address)]) => (Instruction::Call(0), nnn(address)),
+        ("SE", [Register(x), Value(byte)]) => (Instruction::SkipIfEqual { x: *x, kk: 0 }, kk(byte)),
+        ("SNE", [Register(x), Value(byte)]) => {
+            (Instruction::SkipIfNotEqual { x: *x, kk: 0 }, kk(byte))
+        }
+        ("SE", [Register(x), Register(y)]) => (Instruction::SkipIfRegEqual { x: *x, y: *y }, None),
+        ("LD", [Register(x), Value(byte)]) => (Instruction::Load { x: *x, kk: 0 }, kk(byte)),
+        ("ADD", [Register(x), Value(byte)]) => (Instruction::Add { x: *x, kk: 0 }, kk(byte)),
+        ("LD", [Register(x), Register(y)]) => (Instruction::LoadReg { x: *x, y: *y }, None),
+        ("OR", [Register(x), Register(y)]) => (Instruction::Or { x: *x, y: *y }, None),
+        ("AND", [Register(x), Register(y)]) => (Instruction::And { x: *x, y: *y }, None),
+        ("XOR", [Register(x), Register(y)]) => (Instruction::Xor { x: *x, y: *y }, None),
+        ("ADD", [Register(x), Register(y)]) => (Instruction::AddReg { x: *x, y: *y }, None),
+        ("SUB", [Register(x), Register(y)]) => (Instruction::SubReg { x: *x, y: *y }, None),
+        ("SHR", [Register(x)]) => (Instruction::ShiftRight { x: *x, y: *x }, None),
+        ("SHR", [Register(x), Register(y)]) => (Instruction::ShiftRight { x: *x, y: *y }, None),
+        ("SUBN", [Register(x), Register(y)]) => (Instruction::SubN { x: *x, y: *y }, None),
+        ("SHL", [Register(x)]) => (Instruction::ShiftLeft { x: *x, y: *x }, None),
+        ("SHL", [Register(x), Register(y)]) => (Instruction::ShiftLeft { x: *x, y: *y }, None),
+        ("SNE", [Register(x), Register(y)]) => {
+            (Instruction::SkipIfRegNotEqual { x: *x, y: *y }, None)
+        }
+        ("LD", [I, Value(address)]) => (Instruction::LoadI(0), nnn(address)),
+        ("RND", [Register(x), Value(byte)]) => (Instruction::Random { x: *x, kk: 0 }, kk(byte)),
+        ("DRW", [Register(x), Register(y), Value(n)]) => {
+            (Instruction::Draw { x: *x, y: *y, n: 0 }, Some((Operand::N, n.clone())))
+        }
+        ("SKP", [Register(x)]) => (Instruction::SkipIfKey { x: *x }, None),
+        ("SKNP", [Register(x)]) => (Instruction::SkipIfNotKey { x: *x }, None),
+        ("LD", [Register(x), DelayTimer]) => (Instruction::LoadDelayTimer { x: *x }, None),
+        ("LD", [Register(x), Key]) => (Instruction::WaitKey { x: *x }, None),
+        ("LD", [DelayTimer, Register(x)]) => (Instruction::SetDelayTimer { x: *x }, None),
+        ("LD", [SoundTimer, Register(x)]) => (Instruction::SetSoundTimer { x: *x }, None),
+        ("ADD", [I, Register(x)]) => (Instruction::AddI { x: *x }, None),
+        ("LD", [Font, Register(x)]) => (Instruction::LoadDigit { x: *x }, None),
+        ("LD", [Bcd, Register(x)]) => (Instruction::StoreBcd { x: *x }, None),
+        ("LD", [IndirectI, Register(x)]) => (Instruction::Store { x: *x }, None),
+        ("LD", [Register(x), IndirectI]) => (Instruction::Restore { x: *x }, None),
+        _ => {
+            return InvalidAssemblySnafu { line, reason: "unknown instruction or invalid operands" }
+                .fail()
+        }
+    };
+    program.instruction(instruction, operand, line)
+}
+
+/// A number, or a label that stands for its address.
+#[derive(Clone, Debug)]
+pub(crate) enum Value {
+    Number(u16),
+    Label(String),
+}
+
+impl Value {
+    /// Parses a decimal, hexadecimal (`0x`), or binary (`0b`) number, or a label.
+    fn parse(text: &str, line: usize) -> Result<Self> {
+        let number = if let Some(hex) = text.strip_prefix("0x") {
+            u16::from_str_radix(hex, 16).ok()
+        } else if let Some(binary) = text.strip_prefix("0b") {
+            u16::from_str_radix(binary, 2).ok()
+        } else if text.starts_with(|c: char| c.is_ascii_digit()) {
+            text.parse().ok()
+        } else {
+            ensure!(is_label(text), InvalidAssemblySnafu { line, reason: "expected an operand" });
+            return Ok(Value::Label(text.to_string()));
+        };
+        match number {
+            Some(number) => Ok(Value::Number(number)),
+            None => InvalidAssemblySnafu { line, reason: "expected a 16-bit number" }.fail(),
+        }
+    }
+}
+
+/// Returns true if `name` can be a label: a letter or `_` followed by letters, digits, and `_`.
+fn is_label(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// A program being assembled, whose operands may refer to labels that are defined later.
+#[derive(Debug, Default)]
+pub(crate) struct Program {
+    bytes: Vec<u8>,
+    labels: HashMap<String, u16>,
+    fixups: Vec<Fixup>,
+}
+
+/// An operand that refers to a label, which is filled in once every label has an address.
+#[derive(Debug)]
+struct Fixup {
+    offset: usize,
+    /// The field of the instruction at `offset`, or `None` for a byte of data.
+    operand: Option<Operand>,
+    label: String,
+    line: usize,
+}
+
+impl Program {
+    /// Returns the address of the next instruction or byte.
+    pub(crate) fn address(&self) -> u16 {
+        (PROGRAM_SPACE.start + self.bytes.len()) as u16
+    }
+
+    /// Defines a label at the current address.
+    pub(crate) fn define(&mut self, label: &str, line: usize) -> Result<()> {
+        let address = self.address();
+        ensure!(
+            self.labels.insert(label.to_string(), address).is_none(),
+            InvalidAssemblySnafu { line, reason: "the label is already defined" }
+        );
+        Ok(())
+    }
+
+    /// Emits an instruction, filling in `operand` if given.
+    pub(crate) fn instruction(
+        &mut self,
+        instruction: Instruction,
+        operand: Option<(Operand, Value)>,
+        line: usize,
+    ) -> Result<()> {
+        let offset = self.bytes.len();
+        self.bytes.extend_from_slice(&instruction.encode().to_be_bytes());
+        match operand {
+            Some((operand, value)) => self.fill(offset, Some(operand), value, line),
+            None => Ok(()),
+        }
+    }
+
+    /// Emits a byte of data.
+    pub(crate) fn byte(&mut self, value: Value, line: usize) -> Result<()> {
+        let offset = self.bytes.len();
+        self.bytes.push(0);
+        self.fill(offset, None, value, line)
+    }
+
+    /// Points the jump at `address`, which was emitted without a target, to the current address.
+    pub(crate) fn resolve_jump(&mut self, address: u16, line: usize) -> Result<()> {
+        let target = self.address();
+        self.patch(usize::from(address) - PROGRAM_SPACE.start, Some(Operand::Nnn), target, line)
+    }
+
+    /// Resolves the labels and returns the ROM image.
+    pub(crate) fn finish(mut self) -> Result<Vec<u8>> {
+        for fixup in std::mem::take(&mut self.fixups) {
+            let Some(&address) = self.labels.get(&fixup.label) else {
+                let line = fixup.line;
+                return InvalidAssemblySnafu { line, reason: "undefined label" }.fail();
+            };
+            self.patch(fixup.offset, fixup.operand, address, fixup.line)?;
+        }
+        let size = self.bytes.len();
+        ensure!(size <= PROGRAM_SPACE.len(), ProgramTooLargeSnafu { size });
+        Ok(self.bytes)
+    }
+
+    fn fill(
+        &mut self,
+        offset: usize,
+        operand: Option<Operand>,
+        value: Value,
+        line: usize,
+    ) -> Result<()> {
+        match value {
+            Value::Number(number) => self.patch(offset, operand, number, line),
+            Value::Label(label) => {
+                self.fixups.push(Fixup { offset, operand, label, line });
+                Ok(())
+            }
+        }
+    }
+
+    fn patch(
+        &mut self,
+        offset: usize,
+        operand: Option<Operand>,
+        value: u16,
+        line: usize,
+    ) -> Result<()> {
+        let (mask, shift) = match operand {
+            Some(Operand::X) => (0xF, 8),
+            Some(Operand::Y) => (0xF, 4),
+            Some(Operand::N) => (0xF, 0),
+            Some(Operand::Kk) | None => (0xFF, 0),
+            Some(Operand::Nnn) => (0xFFF, 0),
+        };
+        ensure!(
+            value <= mask,
+            InvalidAssemblySnafu { line, reason: "the operand is out of range" }
+        );
+        if operand.is_some() {
+            let bytes = &mut self.bytes[offset..(offset + 2)];
+            let instruction = u16::from_be_bytes([bytes[0], bytes[1]]) | value << shift;
+            bytes.copy_from_slice(&instruction.to_be_bytes());
+        } else {
+            self.bytes[offset] = value as u8;
+        }
+        Ok(())
+    }
+}
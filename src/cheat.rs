@@ -0,0 +1,74 @@
+//! Cheats that poke values into memory, such as the number of lives found with `chip8::search`.
+//!
+//! A cheat file lists one cheat per line as an address, a byte, and whether to write the byte
+//! `once` or to `freeze` it by writing it every frame. Addresses and bytes can be written in
+//! decimal or, with the `0x` prefix, in hexadecimal. Empty lines and lines starting with `#` are
+//! ignored:
+//!
+//! ``` text
+//! # Astro Dodge
+//! 0x3A0 9 freeze
+//! 0x3A2 0x05 once
+//! ```
+
+use alloc::vec::Vec;
+
+use crate::{InvalidCheatSnafu, Result};
+
+/// A byte to write to memory.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Cheat {
+    pub address: u16,
+    pub value: u8,
+    pub mode: CheatMode,
+}
+
+/// When a cheat is applied.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CheatMode {
+    /// Once, when the program starts.
+    Once,
+    /// Every frame, so that the program cannot change the byte for long.
+    Freeze,
+}
+
+/// Parses the cheats of a cheat file.
+///
+/// ```
+/// use chip8::cheat::{self, Cheat, CheatMode};
+///
+/// let cheats = cheat::parse("# Infinite lives\n0x3A0 9 freeze\n")?;
+/// assert_eq!(cheats, [Cheat { address: 0x3A0, value: 9, mode: CheatMode::Freeze }]);
+/// # Ok::<(), chip8::Error>(())
+/// ```
+pub fn parse(text: &str) -> Result<Vec<Cheat>> {
+    let number = |word: &str| match word.strip_prefix("0x") {
+        Some(hex) => u16::from_str_radix(hex, 16).ok(),
+        None => word.parse().ok(),
+    };
+    let mut cheats = Vec::new();
+    for (index, text) in text.lines().enumerate() {
+        let text = text.trim();
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+        let fail = |reason| InvalidCheatSnafu { line: index + 1, reason }.fail();
+        let words: Vec<_> = text.split_whitespace().collect();
+        let [address, value, mode] = words.as_slice() else {
+            return fail("expected an address, a byte, and `once` or `freeze`");
+        };
+        let Some(address) = number(address) else {
+            return fail("invalid address");
+        };
+        let Some(value) = number(value).and_then(|value| u8::try_from(value).ok()) else {
+            return fail("invalid byte");
+        };
+        let mode = match *mode {
+            "once" => CheatMode::Once,
+            "freeze" => CheatMode::Freeze,
+            _ => return fail("expected `once` or `freeze`"),
+        };
+        cheats.push(Cheat { address, value, mode });
+    }
+    Ok(cheats)
+}
@@ -0,0 +1,233 @@
+//! Conditions over the state of a CHIP-8 machine, such as `mem[0x3A0] >= 100 && v3 == 0`.
+//!
+//! A condition is one or more comparisons joined with `&&`. Each side of a comparison is one of
+//! the following operands:
+//!
+//! | Operand            | Value                                   |
+//! | ------------------ | --------------------------------------- |
+//! | `42`, `0x2A`       | A decimal or hexadecimal number         |
+//! | `v0`..`vf`, `v[3]` | A register                              |
+//! | `i`                | The I register                          |
+//! | `pc`               | The program counter                     |
+//! | `dt`, `st`         | The delay timer and the sound timer     |
+//! | `mem[address]`     | The byte at `address` (an operand), or 0 if out of bounds |
+//!
+//! The comparison operators are `==`, `!=`, `<`, `<=`, `>`, and `>=`. `x in a..b` holds if `x` is
+//! in the range from `a` up to but not including `b`, such as `i in 0x300..0x320`.
+
+use alloc::{
+    boxed::Box,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{fmt, str::FromStr};
+
+use crate::{Chip8, InvalidConditionSnafu, Result};
+
+/// A parsed condition, which can be evaluated against a `Chip8` repeatedly.
+#[derive(Clone, Debug)]
+pub struct Condition {
+    source: String,
+    comparisons: Vec<Comparison>,
+}
+
+#[derive(Clone, Debug)]
+enum Comparison {
+    Compare { left: Operand, operator: Operator, right: Operand },
+    In { value: Operand, start: Operand, end: Operand },
+}
+
+#[derive(Clone, Debug)]
+enum Operand {
+    Number(u32),
+    Register(usize),
+    I,
+    Pc,
+    DelayTimer,
+    SoundTimer,
+    Memory(Box<Operand>),
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Operator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Condition {
+    /// Returns true if the condition holds for the current state of `chip8`.
+    pub fn evaluate(&self, chip8: &Chip8) -> bool {
+        self.comparisons.iter().all(|comparison| match comparison {
+            Comparison::Compare { left, operator, right } => {
+                let (left, right) = (left.evaluate(chip8), right.evaluate(chip8));
+                match operator {
+                    Operator::Eq => left == right,
+                    Operator::Ne => left != right,
+                    Operator::Lt => left < right,
+                    Operator::Le => left <= right,
+                    Operator::Gt => left > right,
+                    Operator::Ge => left >= right,
+                }
+            }
+            Comparison::In { value, start, end } => {
+                (start.evaluate(chip8)..end.evaluate(chip8)).contains(&value.evaluate(chip8))
+            }
+        })
+    }
+}
+
+impl Operand {
+    fn evaluate(&self, chip8: &Chip8) -> u32 {
+        match self {
+            Operand::Number(number) => *number,
+            Operand::Register(x) => u32::from(chip8.v[*x]),
+            Operand::I => u32::from(chip8.i),
+            Operand::Pc => chip8.pc as u32,
+            Operand::DelayTimer => u32::from(chip8.timers.delay_timer),
+            Operand::SoundTimer => u32::from(chip8.timers.sound_timer),
+            Operand::Memory(address) => {
+                let address = address.evaluate(chip8) as usize;
+                u32::from(chip8.ram.get(address).copied().unwrap_or(0))
+            }
+        }
+    }
+}
+
+impl FromStr for Condition {
+    type Err = crate::Error;
+
+    /// Parses a condition.
+    fn from_str(source: &str) -> Result<Self> {
+        let mut parser = Parser { source, rest: source };
+        let mut comparisons = vec![parser.comparison()?];
+        while parser.eat("&&") {
+            comparisons.push(parser.comparison()?);
+        }
+        if !parser.rest.trim_start().is_empty() {
+            return parser.fail("expected `&&` or the end of the condition");
+        }
+        Ok(Self { source: source.to_string(), comparisons })
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    rest: &'a str,
+}
+
+impl Parser<'_> {
+    fn comparison(&mut self) -> Result<Comparison> {
+        let left = self.operand()?;
+        if self.eat_word("in") {
+            let start = self.operand()?;
+            if !self.eat("..") {
+                return self.fail("expected `..`");
+            }
+            let end = self.operand()?;
+            return Ok(Comparison::In { value: left, start, end });
+        }
+        let operator = if self.eat("==") {
+            Operator::Eq
+        } else if self.eat("!=") {
+            Operator::Ne
+        } else if self.eat("<=") {
+            Operator::Le
+        } else if self.eat(">=") {
+            Operator::Ge
+        } else if self.eat("<") {
+            Operator::Lt
+        } else if self.eat(">") {
+            Operator::Gt
+        } else {
+            return self.fail("expected a comparison operator or `in`");
+        };
+        let right = self.operand()?;
+        Ok(Comparison::Compare { left, operator, right })
+    }
+
+    fn operand(&mut self) -> Result<Operand> {
+        self.rest = self.rest.trim_start();
+        let end = self.rest.find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or(self.rest.len());
+        let word = self.rest[..end].to_ascii_lowercase();
+        if word.is_empty() {
+            return self.fail("expected an operand");
+        }
+        self.rest = &self.rest[end..];
+        match word.as_str() {
+            "i" => Ok(Operand::I),
+            "pc" => Ok(Operand::Pc),
+            "dt" => Ok(Operand::DelayTimer),
+            "st" => Ok(Operand::SoundTimer),
+            "v" | "mem" => {
+                if !self.eat("[") {
+                    return self.fail("expected `[`");
+                }
+                let index = self.operand()?;
+                if !self.eat("]") {
+                    return self.fail("expected `]`");
+                }
+                if word == "mem" {
+                    return Ok(Operand::Memory(Box::new(index)));
+                }
+                match index {
+                    Operand::Number(x) if x < 16 => Ok(Operand::Register(x as usize)),
+                    _ => self.fail("expected a register number between 0 and 15"),
+                }
+            }
+            _ if word.len() == 2 && word.starts_with('v') => {
+                match usize::from_str_radix(&word[1..], 16) {
+                    Ok(x) => Ok(Operand::Register(x)),
+                    Err(_) => self.fail("expected a register between v0 and vf"),
+                }
+            }
+            _ => {
+                let number = match word.strip_prefix("0x") {
+                    Some(hex) => u32::from_str_radix(hex, 16),
+                    None => word.parse(),
+                };
+                match number {
+                    Ok(number) => Ok(Operand::Number(number)),
+                    Err(_) => self.fail("expected a number, a register, or `mem[...]`"),
+                }
+            }
+        }
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.rest = self.rest.trim_start();
+        if let Some(rest) = self.rest.strip_prefix(token) {
+            self.rest = rest;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Consumes `word` if it is the next word, not followed by more letters or digits.
+    fn eat_word(&mut self, word: &str) -> bool {
+        let rest = self.rest.trim_start();
+        match rest.strip_prefix(word) {
+            Some(after) if !after.starts_with(|c: char| c.is_ascii_alphanumeric()) => {
+                self.rest = after;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn fail<T>(&self, reason: &'static str) -> Result<T> {
+        let position = self.source.len() - self.rest.len();
+        InvalidConditionSnafu { condition: self.source, position, reason }.fail()
+    }
+}
@@ -0,0 +1,123 @@
+//! Game controllers, whose buttons type CHIP-8 keys.
+//!
+//! By default, the d-pad types 2, 4, 6, and 8, and the A button types 5, which suits most games
+//! that move in four directions and fire. Buttons can be mapped with `--buttons` or in a file with
+//! the same name as the ROM file and the `buttons` extension, which lists one `BUTTON=KEY` per line
+//! and is read before `--buttons` is applied:
+//!
+//! ``` text
+//! # Fire with B as well
+//! b=5
+//! ```
+//!
+//! Controllers can be plugged in and out while the ROM runs.
+
+use std::{collections::HashMap, fs, path::Path};
+
+use log::{info, warn};
+use sdl2::{
+    controller::{Button, GameController},
+    event::Event,
+    GameControllerSubsystem,
+};
+use snafu::ResultExt;
+
+use crate::{InvalidButtonProfileSnafu, IoSnafu, Result};
+
+const DEFAULT_BUTTONS: [(Button, u8); 5] = [
+    (Button::DPadUp, 0x2),
+    (Button::DPadLeft, 0x4),
+    (Button::DPadRight, 0x6),
+    (Button::DPadDown, 0x8),
+    (Button::A, 0x5),
+];
+
+/// A controller button and the CHIP-8 key that it types, given as `BUTTON=KEY`.
+#[derive(Clone, Copy, Debug)]
+pub struct ButtonBinding {
+    pub button: Button,
+    pub key: u8,
+}
+
+pub struct Controllers {
+    subsystem: GameControllerSubsystem,
+    open: Vec<GameController>,
+    keys: HashMap<Button, u8>,
+}
+
+impl Controllers {
+    /// Maps the buttons by default, then by the button profile of `rom_file` if it exists, then by
+    /// `bindings`.
+    pub fn new(
+        subsystem: GameControllerSubsystem,
+        rom_file: &Path,
+        bindings: &[ButtonBinding],
+    ) -> Result<Self> {
+        let mut keys: HashMap<_, _> = DEFAULT_BUTTONS.into_iter().collect();
+        let profile_file = rom_file.with_extension("buttons");
+        if profile_file.is_file() {
+            for binding in load_profile(&profile_file)? {
+                keys.insert(binding.button, binding.key);
+            }
+        }
+        for binding in bindings {
+            keys.insert(binding.button, binding.key);
+        }
+        Ok(Self { subsystem, open: Vec::new(), keys })
+    }
+
+    /// Opens and closes controllers as they are plugged in and out, and returns the CHIP-8 key
+    /// that a button event types and whether it is pressed, if any.
+    pub fn handle(&mut self, event: &Event) -> Option<(u8, bool)> {
+        match *event {
+            // SDL also sends this for the controllers plugged in before it starts.
+            Event::ControllerDeviceAdded { which, .. } => {
+                match self.subsystem.open(which) {
+                    Ok(controller) => {
+                        info!("Controller connected: {}", controller.name());
+                        self.open.push(controller);
+                    }
+                    Err(err) => warn!("Could not open controller {which}: {err}"),
+                }
+                None
+            }
+            Event::ControllerDeviceRemoved { which, .. } => {
+                self.open.retain(|controller| controller.instance_id() != which);
+                None
+            }
+            Event::ControllerButtonDown { button, .. } => Some((*self.keys.get(&button)?, true)),
+            Event::ControllerButtonUp { button, .. } => Some((*self.keys.get(&button)?, false)),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a binding written as `BUTTON=KEY`, where `BUTTON` is the SDL name of a controller button,
+/// such as `a`, `leftshoulder`, or `dpup`, and `KEY` is a hex digit.
+pub fn parse_button_binding(binding: &str) -> std::result::Result<ButtonBinding, String> {
+    let (name, key) =
+        binding.split_once('=').ok_or_else(|| format!("expected BUTTON=KEY, not {binding:?}"))?;
+    let button = Button::from_string(name.trim())
+        .ok_or_else(|| format!("{name:?} is not the name of a button"))?;
+    let key = u8::from_str_radix(key.trim(), 16)
+        .ok()
+        .filter(|&key| key < 16)
+        .ok_or_else(|| format!("{key:?} is not a hex key"))?;
+    Ok(ButtonBinding { button, key })
+}
+
+/// Reads a button profile, ignoring empty lines and lines starting with `#`.
+fn load_profile(path: &Path) -> Result<Vec<ButtonBinding>> {
+    let mut bindings = Vec::new();
+    for (index, text) in fs::read_to_string(path).context(IoSnafu)?.lines().enumerate() {
+        let text = text.trim();
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+        let binding = parse_button_binding(text).map_err(|reason| {
+            InvalidButtonProfileSnafu { path, line: index + 1, reason }.build()
+        })?;
+        bindings.push(binding);
+    }
+    Ok(bindings)
+}
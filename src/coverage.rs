@@ -0,0 +1,88 @@
+//! Which bytes of memory have been executed, for finding out which code paths a run exercised.
+
+use alloc::{vec, vec::Vec};
+use core::ops::RangeInclusive;
+#[cfg(feature = "std")]
+use std::{format, io::Write, string::String};
+
+#[cfg(feature = "std")]
+use snafu::ResultExt;
+
+#[cfg(feature = "std")]
+use crate::{IoSnafu, Result};
+use crate::{StepInfo, PROGRAM_SPACE};
+
+/// The bytes of the instructions executed so far, recorded from what
+/// `Chip8::fetch_execute_cycle` returns.
+#[derive(Clone, Debug)]
+pub struct Coverage {
+    executed: Vec<bool>,
+}
+
+impl Coverage {
+    /// Returns a coverage map in which nothing has been executed.
+    pub fn new() -> Self {
+        Self { executed: vec![false; PROGRAM_SPACE.end] }
+    }
+
+    /// Records the instruction executed by a step, if any.
+    pub fn record(&mut self, step: &StepInfo) {
+        if step.instruction.is_some() {
+            for address in step.pc..(step.pc + 2) {
+                if let Some(executed) = self.executed.get_mut(address) {
+                    *executed = true;
+                }
+            }
+        }
+    }
+
+    /// Returns true if the byte at `address` is part of an instruction that has been executed.
+    pub fn is_executed(&self, address: usize) -> bool {
+        self.executed.get(address).copied().unwrap_or(false)
+    }
+
+    /// Returns one bit per byte of memory from 0x000, set if the byte has been executed. The
+    /// first byte of memory is the most significant bit of the first byte.
+    pub fn bitmap(&self) -> Vec<u8> {
+        self.executed
+            .chunks(8)
+            .map(|bits| bits.iter().fold(0, |byte, &bit| byte << 1 | u8::from(bit)))
+            .collect()
+    }
+
+    /// Returns the ranges of consecutive bytes that have been executed, in ascending order.
+    pub fn ranges(&self) -> Vec<RangeInclusive<usize>> {
+        let mut ranges = Vec::new();
+        let mut start = None;
+        for (address, &executed) in self.executed.iter().enumerate() {
+            match (start, executed) {
+                (None, true) => start = Some(address),
+                (Some(first), false) => {
+                    ranges.push(first..=(address - 1));
+                    start = None;
+                }
+                _ => (),
+            }
+        }
+        if let Some(first) = start {
+            ranges.push(first..=(self.executed.len() - 1));
+        }
+        ranges
+    }
+
+    /// Writes the ranges of executed bytes as text, one range such as `0x200-0x21F` per line.
+    #[cfg(feature = "std")]
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let mut text = String::new();
+        for range in self.ranges() {
+            text += &format!("{:#05X}-{:#05X}\n", range.start(), range.end());
+        }
+        writer.write_all(text.as_bytes()).context(IoSnafu)
+    }
+}
+
+impl Default for Coverage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,56 @@
+//! Crash bundles, so that bug reports about a ROM that failed carry more than the error message.
+//!
+//! A bundle is a directory in the current directory named `chip8-crash-SECONDS`, after the Unix
+//! time of the failure. It holds `report.txt`, which gives the error, the ROM and its hash, the
+//! quirks, the registers, the last instructions executed, and the screen, and `state.c8s`, a save
+//! state (see `chip8::save_state`) from which the failure can be reproduced.
+
+use std::{
+    fs::{self, File},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use snafu::ResultExt;
+
+use chip8::{Chip8, Quirks};
+
+use crate::{format_trace, Chip8Snafu, Error, IoSnafu, Result};
+
+/// Writes a bundle for `error`, which `chip8` failed with while running `rom_file` with `quirks`,
+/// returning the path of its directory.
+pub fn write_bundle(
+    rom_file: &Path,
+    quirks: &Quirks,
+    chip8: &Chip8,
+    error: &Error,
+) -> Result<PathBuf> {
+    let seconds = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let directory = PathBuf::from(format!("chip8-crash-{seconds}"));
+    fs::create_dir_all(&directory).context(IoSnafu)?;
+
+    let mut report = String::new();
+    report += &format!("Error: {error}\n");
+    report += &format!("ROM: {}\n", rom_file.display());
+    report += &format!("ROM hash: {:#018X}\n", chip8.rom_hash());
+    report += &format!("Quirks: {quirks:#?}\n");
+    report += &format!("Registers: PC={:#05X} I={:#05X}", chip8.pc(), chip8.i());
+    for (x, value) in chip8.v().iter().enumerate() {
+        report += &format!(" V{x:X}={value:02X}");
+    }
+    report +=
+        &format!(" DT={:02X} ST={:02X}\n", chip8.timers.delay_timer(), chip8.timers.sound_timer);
+    let stack: Vec<_> =
+        chip8.call_stack().iter().map(|address| format!("{address:#05X}")).collect();
+    report += &format!("Call stack: [{}]\n", stack.join(", "));
+    report += &format!("Cycles executed: {}\n", chip8.cycles_executed());
+    report += &format!("Last instructions executed:{}\n", format_trace(chip8));
+    report += &format!("Screen:\n{}", chip8.screen.to_ascii());
+    fs::write(directory.join("report.txt"), report).context(IoSnafu)?;
+
+    let mut state_file = File::create(directory.join("state.c8s")).context(IoSnafu)?;
+    chip8.save_state(&mut state_file).context(Chip8Snafu)?;
+    Ok(directory)
+}
@@ -0,0 +1,186 @@
+//! Finds likely duplicate or variant ROMs in a collection.
+//!
+//! Every ROM is run without a window for a fixed number of frames, during which the sequence of
+//! distinct screens it shows (its screen trajectory) is recorded as a list of hashes. Two ROMs are
+//! considered to be related if they have the same screen trajectory or if their bytes are similar
+//! enough. The random number generator is seeded with the same value for every ROM.
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashSet},
+    fs,
+    hash::{Hash, Hasher},
+    io,
+    path::{Path, PathBuf},
+    thread,
+};
+
+use clap::Args;
+
+use log::{info, warn};
+
+use snafu::ResultExt;
+
+use crate::{IoSnafu, QuirksOpt, Result};
+
+#[derive(Debug, Args)]
+pub struct Opt {
+    /// Sets how many CHIP-8 instructions will be executed per second
+    #[arg(long = "cpu-speed", value_name = "CPU-SPEED", default_value = "700")]
+    cpu_speed: u32,
+
+    /// Sets how many frames (1/60 seconds each) each ROM will be run for
+    #[arg(long, value_name = "FRAMES", default_value = "600")]
+    frames: u32,
+
+    #[command(flatten)]
+    quirks: QuirksOpt,
+
+    /// Sets a directory containing ROM files
+    #[arg(name = "ROM-DIR")]
+    rom_dir: PathBuf,
+
+    /// Sets the minimum byte similarity (between 0 and 1) of ROMs reported as variants
+    #[arg(long, value_name = "SIMILARITY", default_value = "0.8")]
+    similarity: f64,
+}
+
+struct Rom {
+    path: PathBuf,
+    shingles: HashSet<[u8; 4]>,
+    trajectory: Vec<u64>,
+}
+
+pub fn run(opt: &Opt) -> Result<()> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(&opt.rom_dir).context(IoSnafu)? {
+        let path = entry.context(IoSnafu)?.path();
+        if path.is_file() {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    let threads = thread::available_parallelism().map_or(1, usize::from);
+    let chunk_size = paths.len().div_ceil(threads).max(1);
+    let roms = thread::scope(|scope| -> Result<Vec<Rom>> {
+        let handles: Vec<_> = paths
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk.iter().map(|path| load_and_run(path, opt)).collect::<io::Result<Vec<_>>>()
+                })
+            })
+            .collect();
+        let mut roms = Vec::with_capacity(paths.len());
+        for handle in handles {
+            roms.extend(handle.join().expect("a ROM runner thread panicked").context(IoSnafu)?);
+        }
+        Ok(roms)
+    })?;
+
+    // Group related ROMs using a union-find structure.
+    let mut parents: Vec<usize> = (0..roms.len()).collect();
+    let mut relations = Vec::new();
+    for a in 0..roms.len() {
+        for b in (a + 1)..roms.len() {
+            let same_screens =
+                !roms[a].trajectory.is_empty() && roms[a].trajectory == roms[b].trajectory;
+            let similarity = similarity(&roms[a].shingles, &roms[b].shingles);
+            if same_screens || similarity >= opt.similarity {
+                let (root_a, root_b) = (find(&mut parents, a), find(&mut parents, b));
+                parents[root_b] = root_a;
+                relations.push((a, b, same_screens, similarity));
+            }
+        }
+    }
+
+    let mut reported = HashSet::new();
+    for &(a, _, _, _) in &relations {
+        let root = find(&mut parents, a);
+        if !reported.insert(root) {
+            continue;
+        }
+        println!("Likely duplicates or variants:");
+        for (index, rom) in roms.iter().enumerate() {
+            if find(&mut parents, index) == root {
+                println!("  {}", rom.path.display());
+            }
+        }
+        for &(a, b, same_screens, similarity) in &relations {
+            if find(&mut parents, a) == root {
+                println!(
+                    "    {} ~ {}: {}{:.0}% similar bytes",
+                    file_name(&roms[a].path),
+                    file_name(&roms[b].path),
+                    if same_screens { "same screens, " } else { "" },
+                    similarity * 100.0,
+                );
+            }
+        }
+    }
+    if reported.is_empty() {
+        println!("No duplicates or variants found among {} ROMs.", roms.len());
+    }
+    Ok(())
+}
+
+fn load_and_run(path: &Path, opt: &Opt) -> io::Result<Rom> {
+    let bytes = fs::read(path)?;
+    let shingles = bytes.windows(4).map(|window| [window[0], window[1], window[2], window[3]]);
+    let trajectory = match chip8::Chip8::new(path, (&opt.quirks).into()) {
+        Ok(mut chip8) => {
+            chip8.set_rng(chip8::rng::SeededRng::new(0));
+            run_headless(&mut chip8, path, opt)
+        }
+        Err(err) => {
+            warn!("{}: {err}", path.display());
+            Vec::new()
+        }
+    };
+    info!("{}: {} distinct screens", path.display(), trajectory.len());
+    Ok(Rom { path: path.to_path_buf(), shingles: shingles.collect(), trajectory })
+}
+
+fn run_headless(chip8: &mut chip8::Chip8, path: &Path, opt: &Opt) -> Vec<u64> {
+    let instructions_per_frame = (f64::from(opt.cpu_speed) / 60.0).round() as u32;
+    let mut trajectory = Vec::new();
+    for _ in 0..opt.frames {
+        chip8.timers.count_down();
+        chip8.vblank();
+        for _ in 0..instructions_per_frame {
+            if let Err(err) = chip8.fetch_execute_cycle() {
+                info!("{}: {err}", path.display());
+                return trajectory;
+            }
+        }
+        let mut hasher = DefaultHasher::new();
+        chip8.screen.as_ref().hash(&mut hasher);
+        let hash = hasher.finish();
+        if trajectory.last() != Some(&hash) {
+            trajectory.push(hash);
+        }
+    }
+    trajectory
+}
+
+/// Returns the Jaccard similarity of two sets of byte shingles.
+fn similarity(a: &HashSet<[u8; 4]>, b: &HashSet<[u8; 4]>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+fn find(parents: &mut [usize], mut index: usize) -> usize {
+    while parents[index] != index {
+        parents[index] = parents[parents[index]];
+        index = parents[index];
+    }
+    index
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into())
+}
@@ -0,0 +1,108 @@
+//! Reports the host environment, to triage platform-specific stutter and audio issues.
+//!
+//! Every section is printed even if an earlier one fails, with failures reported in place.
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use spin_sleep_util::MissedTickBehavior;
+
+use crate::{open_audio, open_canvas, Result, Waveform};
+
+/// How many times each kind of sleep is measured.
+const SLEEP_SAMPLES: u32 = 60;
+
+pub fn run() -> Result<()> {
+    println!("Platform: {}", sdl2::get_platform());
+    println!("SDL version: {}", sdl2::version::version());
+    let sdl_context = sdl2::init()?;
+
+    println!("Video:");
+    match sdl_context.video() {
+        Ok(video_subsystem) => {
+            println!("  Driver: {}", video_subsystem.current_video_driver());
+            for display in 0..video_subsystem.num_video_displays()? {
+                println!("  Display {display}:");
+                report("Name", video_subsystem.display_name(display));
+                report(
+                    "DPI (diagonal, horizontal, vertical)",
+                    video_subsystem.display_dpi(display),
+                );
+                report("Desktop mode", video_subsystem.desktop_display_mode(display));
+                for mode in 0..video_subsystem.num_display_modes(display)? {
+                    report(&format!("Mode {mode}"), video_subsystem.display_mode(display, mode));
+                }
+            }
+        }
+        Err(err) => println!("  Error: {err}"),
+    }
+
+    println!("Renderers:");
+    for info in sdl2::render::drivers() {
+        println!("  {} (flags {:#x})", info.name, info.flags);
+    }
+    match open_canvas(&sdl_context, false) {
+        Ok((canvas, _)) => {
+            let info = canvas.info();
+            println!("  Used: {} (flags {:#x})", info.name, info.flags);
+            println!("  Texture formats: {:?}", info.texture_formats);
+            println!(
+                "  Maximum texture size: {}x{}",
+                info.max_texture_width, info.max_texture_height
+            );
+            report("Window mode", canvas.window().display_mode());
+        }
+        Err(err) => println!("  Error: {err}"),
+    }
+    println!("Touch devices: {}", sdl2::touch::num_touch_devices());
+
+    println!("Audio:");
+    match open_audio(&sdl_context, &Waveform::default()) {
+        Ok(audio_device) => {
+            let spec = audio_device.spec();
+            println!("  Driver: {}", audio_device.subsystem().current_audio_driver());
+            println!("  Frequency: {} Hz", spec.freq);
+            println!("  Format: {:?}", spec.format);
+            println!("  Channels: {}", spec.channels);
+            println!("  Buffer: {} samples ({} bytes)", spec.samples, spec.size);
+        }
+        Err(err) => println!("  Error: {err}"),
+    }
+
+    println!("Timing:");
+    let timer_subsystem = sdl_context.timer()?;
+    println!("  Performance counter: {} Hz", timer_subsystem.performance_frequency());
+    let (mean, max) = measure(|| thread::sleep(Duration::from_millis(1)));
+    println!("  Sleeping for 1 ms: {mean:?} on average, {max:?} at most");
+    let mut interval = spin_sleep_util::interval(Duration::from_secs(1) / 60)
+        .with_missed_tick_behavior(MissedTickBehavior::Delay);
+    interval.tick();
+    let (mean, max) = measure(|| {
+        interval.tick();
+    });
+    println!("  Frame interval (16.667 ms): {mean:?} on average, {max:?} at most");
+    Ok(())
+}
+
+fn report<T: std::fmt::Debug>(label: &str, value: std::result::Result<T, String>) {
+    match value {
+        Ok(value) => println!("    {label}: {value:?}"),
+        Err(err) => println!("    {label}: Error: {err}"),
+    }
+}
+
+/// Returns the mean and the maximum time that `sleep` takes.
+fn measure(mut sleep: impl FnMut()) -> (Duration, Duration) {
+    let mut total = Duration::ZERO;
+    let mut max = Duration::ZERO;
+    for _ in 0..SLEEP_SAMPLES {
+        let start = Instant::now();
+        sleep();
+        let elapsed = start.elapsed();
+        total += elapsed;
+        max = max.max(elapsed);
+    }
+    (total / SLEEP_SAMPLES, max)
+}
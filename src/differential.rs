@@ -0,0 +1,197 @@
+//! Differential testing: running two interpreters in lockstep, such as this one with two sets of
+//! quirks or this one and another emulator, and finding the first instruction after which their
+//! states differ.
+//!
+//! ```
+//! use chip8::{differential, Chip8, Quirks};
+//!
+//! let rom = [0x60, 0x81, 0x81, 0x06, 0x12, 0x04]; // LD V0, 0x81; SHR V1, V0; (halt)
+//! let mut modern = Chip8::builder().rom(&rom).build()?;
+//! let mut vip = Chip8::builder().rom(&rom).quirks(Quirks::COSMAC_VIP).build()?;
+//! let divergence = differential::find_divergence(&mut modern, &mut vip, 10, 60).unwrap();
+//! assert_eq!((divergence.pc, divergence.differences.as_slice()), (Some(0x202), ["v"].as_slice()));
+//! # Ok::<(), chip8::Error>(())
+//! ```
+//!
+//! Both interpreters must draw the same random numbers for Cxkk, such as by seeding them alike.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::{self, Display, Formatter};
+
+use crate::{Chip8, Screen};
+
+/// An interpreter that can be run in lockstep with another.
+pub trait Interpreter {
+    type Error: Display;
+
+    /// Executes the next instruction, unless the interpreter is waiting for the vertical blank.
+    fn step(&mut self) -> Result<(), Self::Error>;
+
+    /// Counts the timers down and signals the vertical blank.
+    fn end_frame(&mut self) -> Result<(), Self::Error>;
+
+    /// Returns the state to compare.
+    fn state(&self) -> State;
+}
+
+impl Interpreter for Chip8 {
+    type Error = crate::Error;
+
+    fn step(&mut self) -> Result<(), Self::Error> {
+        self.fetch_execute_cycle().map(|_| ())
+    }
+
+    fn end_frame(&mut self) -> Result<(), Self::Error> {
+        self.run_frame(0).map(|_| ())
+    }
+
+    fn state(&self) -> State {
+        State {
+            pc: self.pc(),
+            i: self.i(),
+            v: *self.v(),
+            stack: self.call_stack().to_vec(),
+            delay_timer: self.timers.delay_timer(),
+            sound_timer: self.timers.sound_timer,
+            screen: self.screen,
+        }
+    }
+}
+
+/// The state of an interpreter that is compared.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct State {
+    pub pc: usize,
+    pub i: u16,
+    pub v: [u8; 16],
+    /// The return addresses of the subroutine calls, the innermost last.
+    pub stack: Vec<usize>,
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+    pub screen: Screen,
+}
+
+/// Where two interpreters first differed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Divergence {
+    /// The number of frames completed before.
+    pub frame: u32,
+    /// The address of the instruction after which the interpreters differed, according to the
+    /// first one, or `None` if they differed after counting the timers down at the end of a frame.
+    pub pc: Option<usize>,
+    /// The names of the parts of the states that differ, such as `v` and `screen`, and `error` if
+    /// only one interpreter failed.
+    pub differences: Vec<&'static str>,
+    /// The states afterwards of the first and the second interpreter.
+    pub states: (State, State),
+    /// The errors of the first and the second interpreter, if they failed.
+    pub errors: (Option<String>, Option<String>),
+}
+
+/// Runs `first` and `second` for up to `frames` frames of `instructions_per_frame` instructions,
+/// comparing their states after every instruction and every frame. Returns where they first
+/// differed or both failed, or `None` if neither happened.
+pub fn find_divergence<A, B>(
+    first: &mut A,
+    second: &mut B,
+    instructions_per_frame: u32,
+    frames: u32,
+) -> Option<Divergence>
+where
+    A: Interpreter,
+    B: Interpreter,
+{
+    for frame in 0..frames {
+        for _ in 0..instructions_per_frame {
+            let pc = first.state().pc;
+            let results = (first.step(), second.step());
+            if let Some(divergence) = compare(first, second, frame, Some(pc), results) {
+                return Some(divergence);
+            }
+        }
+        let results = (first.end_frame(), second.end_frame());
+        if let Some(divergence) = compare(first, second, frame, None, results) {
+            return Some(divergence);
+        }
+    }
+    None
+}
+
+/// Returns where `first` and `second` differ after a step with `results`, or `None` if they do
+/// not. Both failing counts as a divergence with no differences, which stops execution.
+fn compare<A: Interpreter, B: Interpreter>(
+    first: &A,
+    second: &B,
+    frame: u32,
+    pc: Option<usize>,
+    results: (Result<(), A::Error>, Result<(), B::Error>),
+) -> Option<Divergence> {
+    let states = (first.state(), second.state());
+    let errors =
+        (results.0.err().map(|err| err.to_string()), results.1.err().map(|err| err.to_string()));
+    let (left, right) = &states;
+    let mut differences = Vec::new();
+    for (name, differs) in [
+        ("error", errors.0.is_some() != errors.1.is_some()),
+        ("pc", left.pc != right.pc),
+        ("i", left.i != right.i),
+        ("v", left.v != right.v),
+        ("stack", left.stack != right.stack),
+        ("delay_timer", left.delay_timer != right.delay_timer),
+        ("sound_timer", left.sound_timer != right.sound_timer),
+        ("screen", left.screen != right.screen),
+    ] {
+        if differs {
+            differences.push(name);
+        }
+    }
+    let failed = errors.0.is_some() && errors.1.is_some();
+    (!differences.is_empty() || failed).then_some(Divergence {
+        frame,
+        pc,
+        differences,
+        states,
+        errors,
+    })
+}
+
+impl Display for Divergence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.pc {
+            Some(pc) => write!(f, "After the instruction at {pc:#05X} in frame {}", self.frame)?,
+            None => write!(f, "At the end of frame {}", self.frame)?,
+        }
+        if self.differences.is_empty() {
+            writeln!(f, ", both interpreters failed:")?;
+        } else {
+            writeln!(f, ", {} differ:", self.differences.join(", "))?;
+        }
+        for (name, state, error) in
+            [("first", &self.states.0, &self.errors.0), ("second", &self.states.1, &self.errors.1)]
+        {
+            write!(f, "{name:>7}: PC={:#05X} I={:#05X}", state.pc, state.i)?;
+            for (x, value) in state.v.iter().enumerate() {
+                write!(f, " V{x:X}={value:02X}")?;
+            }
+            write!(f, " DT={:02X} ST={:02X}", state.delay_timer, state.sound_timer)?;
+            let stack: Vec<_> =
+                state.stack.iter().map(|address| format!("{address:#05X}")).collect();
+            writeln!(f, " stack=[{}]", stack.join(", "))?;
+            if let Some(error) = error {
+                writeln!(f, "         {error}")?;
+            }
+        }
+        if self.differences.contains(&"screen") {
+            for (left, right) in
+                self.states.0.screen.to_ascii().lines().zip(self.states.1.screen.to_ascii().lines())
+            {
+                writeln!(f, "{left}  {right}")?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,34 @@
+//! A disassembler that tells code from data by following the control flow from 0x200 (see
+//! `analysis`).
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{analysis, instruction::Instruction};
+
+/// An instruction or a byte of data at an address.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Item {
+    Code { address: usize, opcode: u16, instruction: Instruction },
+    Data { address: usize, byte: u8 },
+}
+
+impl fmt::Display for Item {
+    /// Formats the item as its address, its raw bytes, and its mnemonic, such as
+    /// `0x200  6A02  LD VA, 0x02` or `0x2F0  3C    DB 0x3C`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Item::Code { address, opcode, instruction } => {
+                write!(f, "{address:#05X}  {opcode:04X}  {instruction}")
+            }
+            Item::Data { address, byte } => {
+                write!(f, "{address:#05X}  {byte:02X}    DB {byte:#04X}")
+            }
+        }
+    }
+}
+
+/// Disassembles a program that is loaded at 0x200 (see `analysis::analyze`).
+pub fn disassemble(program: &[u8]) -> Vec<Item> {
+    analysis::analyze(program).items
+}
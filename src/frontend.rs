@@ -0,0 +1,76 @@
+//! The parts of a frontend that depend on the platform, and a driver that runs an interpreter
+//! with them.
+//!
+//! A frontend implements `DisplaySink`, `InputSource`, and `AudioSink` for its platform and calls
+//! `run`, which takes care of pacing frames and emulating the ghosting of phosphor displays, while
+//! `Chip8::run_for` paces instructions and counts down the timers.
+
+use std::{thread, time::Instant};
+
+use crate::{post::Phosphor, Chip8, Error, Screen};
+
+/// Where frames are displayed.
+pub trait DisplaySink {
+    type Error;
+
+    /// Displays a frame, once every vertical blank.
+    fn present(&mut self, frame: &Screen) -> Result<(), Self::Error>;
+}
+
+/// Where key presses come from.
+pub trait InputSource {
+    type Error;
+
+    /// Presses and releases the keys of `chip8` as the user did since the last call, once every
+    /// vertical blank. Returns false if the user has requested to quit.
+    fn poll(&mut self, chip8: &mut Chip8) -> Result<bool, Self::Error>;
+}
+
+/// Where the beep is played.
+pub trait AudioSink {
+    /// Starts or stops the beep, once every vertical blank.
+    fn set_beeping(&mut self, beeping: bool);
+}
+
+/// Plays nothing, for frontends without audio.
+impl AudioSink for () {
+    fn set_beeping(&mut self, _beeping: bool) {}
+}
+
+/// Runs `chip8` until `input` requests to quit or an instruction fails.
+///
+/// Every vertical blank (see `Chip8::timer_cycle`), the keys are polled, the interpreter is run
+/// for the frame (see `Chip8::run_for`), and the screen is displayed, blended with the previous
+/// one to reduce flicker. The driver sleeps between vertical blanks, and skips the time it falls
+/// behind.
+pub fn run<D, I, A, E>(
+    chip8: &mut Chip8,
+    display: &mut D,
+    input: &mut I,
+    audio: &mut A,
+) -> Result<(), E>
+where
+    D: DisplaySink<Error = E>,
+    I: InputSource<Error = E>,
+    A: AudioSink,
+    E: From<Error>,
+{
+    let mut phosphor = Phosphor::new();
+    let mut deadline = Instant::now();
+    while input.poll(chip8)? {
+        let steps = chip8.run_for(chip8.timer_cycle())?;
+        display.present(&phosphor.process(&chip8.screen))?;
+        // A beep that started and stopped within the frame still plays for the frame.
+        let sound_started = steps.iter().any(|step| step.sound_started);
+        audio.set_beeping(sound_started || chip8.timers.sound_timer > 0);
+
+        deadline += chip8.timer_cycle();
+        let now = Instant::now();
+        if deadline > now {
+            thread::sleep(deadline - now);
+        } else {
+            deadline = now;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,61 @@
+//! Running arbitrary ROMs with bounded resources, for fuzzers such as cargo-fuzz:
+//!
+//! ```ignore
+//! fuzz_target!(|rom: &[u8]| {
+//!     let _ = chip8::fuzz::run_bounded(rom, 10_000);
+//! });
+//! ```
+
+use crate::{Chip8, Result};
+
+/// How many instructions are executed between two vertical blanks, as at the default CPU speed.
+const INSTRUCTIONS_PER_FRAME: usize = 12;
+
+/// How a bounded run ended without failing.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Outcome {
+    /// The number of instructions executed.
+    pub steps: usize,
+    /// True if the program halted (see `Chip8::is_halted`) before `max_steps`.
+    pub halted: bool,
+    /// The hash of the final state (see `Chip8::state_hash`).
+    pub state_hash: u64,
+}
+
+/// Runs `rom` with the default quirks for at most `max_steps` instructions, failing if the ROM
+/// does not fit in memory or an instruction fails. The run is deterministic, whatever `rom` is:
+/// Cxkk draws from a `rng::SeededRng` seeded with 0, and Fx0A gets key 0 at once.
+///
+/// Whatever `rom` is, this never panics, allocates more than a few kilobytes at a time, or runs
+/// for more than `max_steps` instructions and as many vertical blanks.
+///
+/// ```
+/// # fn main() -> Result<(), chip8::Error> {
+/// let outcome = chip8::fuzz::run_bounded(&[0xF0, 0x0A, 0x12, 0x02], 100)?;
+/// assert!(outcome.halted);
+/// assert!(chip8::fuzz::run_bounded(&[0x00, 0xEE], 100).is_err());
+/// # Ok(())
+/// # }
+/// ```
+pub fn run_bounded(rom: &[u8], max_steps: usize) -> Result<Outcome> {
+    let mut chip8 = Chip8::builder().rom(rom).seed(0).build()?;
+    let mut steps = 0;
+    // Each frame executes at least one instruction unless it only waits for the vertical blank,
+    // which the next frame no longer does, so this bounds the frames too.
+    for _ in 0..max_steps {
+        let frame_len = INSTRUCTIONS_PER_FRAME.min(max_steps - steps);
+        if frame_len == 0 {
+            break;
+        }
+        let frame = chip8.run_frame(frame_len as u32)?;
+        steps += frame.len();
+        if frame.last().is_some_and(|step| step.waiting_for_key) {
+            chip8.press_key(0)?;
+            chip8.release_key(0)?;
+        }
+        if chip8.is_halted() {
+            break;
+        }
+    }
+    Ok(Outcome { steps, halted: chip8.is_halted(), state_hash: chip8.state_hash() })
+}
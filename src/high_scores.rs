@@ -0,0 +1,99 @@
+//! Per-ROM high-score tables.
+//!
+//! The location of the score in memory (see `chip8::score::ScoreLocation`) is given with
+//! `--score-location` or read from a file with the same name as the ROM file and the `score`
+//! extension. The best score of each session is recorded in a file with the `highscores` extension,
+//! which keeps the top `TABLE_SIZE` scores, one per line, and which is shown in the title bar of
+//! the window when the interpreter is paused.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use log::warn;
+use snafu::ResultExt;
+
+use chip8::{score::ScoreLocation, Chip8};
+
+use crate::{Chip8Snafu, IoSnafu, Result};
+
+const TABLE_SIZE: usize = 10;
+
+pub struct HighScores {
+    location: ScoreLocation,
+    table_path: PathBuf,
+    best_score: u32,
+}
+
+impl HighScores {
+    /// Reads the location of the score from `location` or, if it is `None`, from the score file
+    /// of `rom_file`. Returns `None` if neither exists.
+    pub fn new(rom_file: &Path, location: Option<&str>) -> Result<Option<Self>> {
+        let location = match location {
+            Some(location) => location.to_string(),
+            None => match fs::read_to_string(rom_file.with_extension("score")) {
+                Ok(location) => location,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+                Err(err) => return Err(err).context(IoSnafu),
+            },
+        };
+        Ok(Some(Self {
+            location: location.trim().parse().context(Chip8Snafu)?,
+            table_path: rom_file.with_extension("highscores"),
+            best_score: 0,
+        }))
+    }
+
+    /// Keeps track of the best score of the current session.
+    pub fn update(&mut self, chip8: &Chip8) {
+        self.best_score = self.best_score.max(self.location.read(chip8));
+    }
+
+    /// Returns the table with the best score of the current session, if not recorded yet.
+    pub fn table(&self) -> Result<Vec<u32>> {
+        let mut table = match fs::read_to_string(&self.table_path) {
+            Ok(table) => table.lines().filter_map(|line| line.trim().parse().ok()).collect(),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err).context(IoSnafu),
+        };
+        if self.best_score > 0 {
+            table.push(self.best_score);
+        }
+        table.sort_unstable_by(|a, b| b.cmp(a));
+        table.truncate(TABLE_SIZE);
+        Ok(table)
+    }
+
+    /// Records the best score of the current session in the table and returns the table.
+    pub fn save(&mut self) -> Result<Vec<u32>> {
+        let table = self.table()?;
+        let contents: String = table.iter().map(|score| format!("{score}\n")).collect();
+        fs::write(&self.table_path, contents).context(IoSnafu)?;
+        // The score is in the table now, and a new session starts.
+        self.best_score = 0;
+        Ok(table)
+    }
+}
+
+impl Drop for HighScores {
+    /// Records the best score of the current session if `save` has not, as when the interpreter
+    /// stops on an error.
+    fn drop(&mut self) {
+        if self.best_score > 0 {
+            if let Err(err) = self.save() {
+                warn!("Could not save the high scores: {err}");
+            }
+        }
+    }
+}
+
+/// Formats `table` on one line, for the title bar of the window.
+pub fn format_table(table: &[u32]) -> String {
+    let scores: Vec<_> =
+        table.iter().enumerate().map(|(rank, score)| format!("{}. {score}", rank + 1)).collect();
+    format!(
+        "High scores: {}",
+        if scores.is_empty() { "none yet".into() } else { scores.join(", ") }
+    )
+}
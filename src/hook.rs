@@ -0,0 +1,62 @@
+//! Callbacks that are called around each instruction, for debuggers, tracers, and other tools that
+//! watch or stop execution, and when the screen or the beep changes, for frontends.
+
+use alloc::{boxed::Box, vec::Vec};
+use core::fmt::{self, Debug, Formatter};
+
+use crate::{condition::Condition, instruction::Instruction, Chip8};
+
+/// What `Chip8::fetch_execute_cycle` does after a hook returns.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HookAction {
+    /// Goes on executing.
+    Continue,
+    /// Returns with `StepInfo::paused` set.
+    Pause,
+    /// Fails with `Error::HookAborted`.
+    Abort,
+}
+
+/// A change that frontends may want to react to as soon as it happens, rather than by polling the
+/// interpreter every frame.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Event {
+    /// The screen was cleared, or a sprite was drawn that changed at least one pixel.
+    ScreenChanged,
+    /// The sound timer was set to a nonzero value while it was zero, which starts the beep.
+    SoundStarted,
+    /// The sound timer reached zero, by counting down or by being set, which stops the beep.
+    SoundStopped,
+}
+
+pub(crate) type Hook = Box<dyn FnMut(&Chip8, Instruction) -> HookAction + Send>;
+
+pub(crate) type Listener = Box<dyn FnMut(Event) + Send>;
+
+/// The hooks of an interpreter.
+#[derive(Default)]
+pub(crate) struct Hooks {
+    /// Called before each instruction is executed.
+    pub(crate) before: Option<Hook>,
+    /// Called after each instruction is executed.
+    pub(crate) after: Option<Hook>,
+    /// Called on each event.
+    pub(crate) listener: Option<Listener>,
+    /// The conditions that pause execution before an instruction.
+    pub(crate) breakpoints: Vec<Condition>,
+    /// The address at which a breakpoint paused execution, where the next step resumes without
+    /// checking the breakpoints.
+    pub(crate) resume_pc: Option<usize>,
+}
+
+impl Debug for Hooks {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Hooks")
+            .field("before", &self.before.is_some())
+            .field("after", &self.after.is_some())
+            .field("listener", &self.listener.is_some())
+            .field("breakpoints", &self.breakpoints)
+            .field("resume_pc", &self.resume_pc)
+            .finish()
+    }
+}
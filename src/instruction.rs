@@ -0,0 +1,230 @@
+//! Decoded CHIP-8 instructions.
+
+use core::fmt;
+
+use crate::isa;
+
+/// A CHIP-8 instruction with its operands extracted. Register indexes (`x` and `y`) are between 0
+/// and 15, `n` is between 0 and 15, and addresses (`nnn`) are below 0x1000.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Instruction {
+    /// 00E0: clears the screen.
+    ClearScreen,
+    /// 00EE: returns from a subroutine.
+    Return,
+    /// 0nnn: calls the machine code routine at nnn.
+    Syscall(u16),
+    /// 1nnn: jumps to nnn.
+    Jump(u16),
+    /// 2nnn: calls the subroutine at nnn.
+    Call(u16),
+    /// 3xkk: skips the next instruction if Vx == kk.
+    SkipIfEqual { x: u8, kk: u8 },
+    /// 4xkk: skips the next instruction if Vx != kk.
+    SkipIfNotEqual { x: u8, kk: u8 },
+    /// 5xy0: skips the next instruction if Vx == Vy.
+    SkipIfRegEqual { x: u8, y: u8 },
+    /// 6xkk: Vx = kk.
+    Load { x: u8, kk: u8 },
+    /// 7xkk: Vx = Vx + kk.
+    Add { x: u8, kk: u8 },
+    /// 8xy0: Vx = Vy.
+    LoadReg { x: u8, y: u8 },
+    /// 8xy1: Vx = Vx | Vy.
+    Or { x: u8, y: u8 },
+    /// 8xy2: Vx = Vx & Vy.
+    And { x: u8, y: u8 },
+    /// 8xy3: Vx = Vx ^ Vy.
+    Xor { x: u8, y: u8 },
+    /// 8xy4: Vx = Vx + Vy, VF = carry.
+    AddReg { x: u8, y: u8 },
+    /// 8xy5: Vx = Vx - Vy, VF = no borrow.
+    SubReg { x: u8, y: u8 },
+    /// 8xy6: shifts Vx (or Vy) right into Vx, VF = the bit shifted out. VF is set last, so that
+    /// 8Fy6 leaves the bit shifted out in VF, as 8xy4, 8xy5, and 8xy7 leave their flags:
+    ///
+    /// ```
+    /// # fn main() -> Result<(), chip8::Error> {
+    /// let mut chip8 = chip8::Chip8::from_bytes(&[], Default::default())?;
+    /// chip8.v_mut()[0xF] = 0x03;
+    /// chip8.execute(chip8::instruction::Instruction::ShiftRight { x: 0xF, y: 0xF })?;
+    /// assert_eq!(chip8.v()[0xF], 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ShiftRight { x: u8, y: u8 },
+    /// 8xy7: Vx = Vy - Vx, VF = no borrow.
+    SubN { x: u8, y: u8 },
+    /// 8xyE: shifts Vx (or Vy) left into Vx, VF = the bit shifted out, which VF is left holding
+    /// for 8FyE too:
+    ///
+    /// ```
+    /// # fn main() -> Result<(), chip8::Error> {
+    /// let mut chip8 = chip8::Chip8::from_bytes(&[], Default::default())?;
+    /// chip8.v_mut()[0xF] = 0x81;
+    /// chip8.execute(chip8::instruction::Instruction::ShiftLeft { x: 0xF, y: 0xF })?;
+    /// assert_eq!(chip8.v()[0xF], 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ShiftLeft { x: u8, y: u8 },
+    /// 9xy0: skips the next instruction if Vx != Vy.
+    SkipIfRegNotEqual { x: u8, y: u8 },
+    /// Annn: I = nnn.
+    LoadI(u16),
+    /// Bnnn: jumps to nnn + V0 (or, as Bxnn, to xnn + Vx).
+    JumpOffset(u16),
+    /// Cxkk: Vx = a random byte & kk.
+    Random { x: u8, kk: u8 },
+    /// Dxyn: draws the n-byte sprite at I at (Vx, Vy), VF = collision.
+    Draw { x: u8, y: u8, n: u8 },
+    /// Ex9E: skips the next instruction if the key Vx is pressed.
+    SkipIfKey { x: u8 },
+    /// ExA1: skips the next instruction if the key Vx is not pressed.
+    SkipIfNotKey { x: u8 },
+    /// Fx07: Vx = the delay timer.
+    LoadDelayTimer { x: u8 },
+    /// Fx0A: Vx = a key pressed and then released.
+    WaitKey { x: u8 },
+    /// Fx15: the delay timer = Vx.
+    SetDelayTimer { x: u8 },
+    /// Fx18: the sound timer = Vx.
+    SetSoundTimer { x: u8 },
+    /// Fx1E: I = I + Vx.
+    AddI { x: u8 },
+    /// Fx29: I = the address of the sprite for the hexadecimal digit Vx.
+    LoadDigit { x: u8 },
+    /// Fx33: stores the BCD of Vx at I..=(I + 2).
+    StoreBcd { x: u8 },
+    /// Fx55: stores V0..=Vx at I..=(I + x), or nothing if that runs past the end of memory:
+    ///
+    /// ```
+    /// # fn main() -> Result<(), chip8::Error> {
+    /// let mut chip8 = chip8::Chip8::from_bytes(&[], Default::default())?;
+    /// let end = chip8.memory().len();
+    /// chip8.set_i(end as u16 - 2);
+    /// chip8.v_mut()[..3].copy_from_slice(&[1, 2, 3]);
+    /// assert!(chip8.execute(chip8::instruction::Instruction::Store { x: 2 }).is_err());
+    /// assert_eq!(chip8.memory()[end - 2..], [0, 0]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    Store { x: u8 },
+    /// Fx65: loads V0..=Vx from I..=(I + x).
+    Restore { x: u8 },
+}
+
+impl Instruction {
+    /// Decodes a 2-bytes instruction, returning `None` if it is not well-formed.
+    pub fn decode(instruction: u16) -> Option<Self> {
+        let nnn = instruction & 0x0FFF;
+        let x = ((instruction & 0x0F00) >> 8) as u8;
+        let y = ((instruction & 0x00F0) >> 4) as u8;
+        let n = (instruction & 0x000F) as u8;
+        let kk = (instruction & 0x00FF) as u8;
+        let decoded = match instruction & 0xF000 {
+            0x0000 => match nnn {
+                0x00E0 => Instruction::ClearScreen,
+                0x00EE => Instruction::Return,
+                _ => Instruction::Syscall(nnn),
+            },
+            0x1000 => Instruction::Jump(nnn),
+            0x2000 => Instruction::Call(nnn),
+            0x3000 => Instruction::SkipIfEqual { x, kk },
+            0x4000 => Instruction::SkipIfNotEqual { x, kk },
+            0x5000 if n == 0 => Instruction::SkipIfRegEqual { x, y },
+            0x6000 => Instruction::Load { x, kk },
+            0x7000 => Instruction::Add { x, kk },
+            0x8000 => match n {
+                0x0 => Instruction::LoadReg { x, y },
+                0x1 => Instruction::Or { x, y },
+                0x2 => Instruction::And { x, y },
+                0x3 => Instruction::Xor { x, y },
+                0x4 => Instruction::AddReg { x, y },
+                0x5 => Instruction::SubReg { x, y },
+                0x6 => Instruction::ShiftRight { x, y },
+                0x7 => Instruction::SubN { x, y },
+                0xE => Instruction::ShiftLeft { x, y },
+                _ => return None,
+            },
+            0x9000 if n == 0 => Instruction::SkipIfRegNotEqual { x, y },
+            0xA000 => Instruction::LoadI(nnn),
+            0xB000 => Instruction::JumpOffset(nnn),
+            0xC000 => Instruction::Random { x, kk },
+            0xD000 => Instruction::Draw { x, y, n },
+            0xE000 => match kk {
+                0x9E => Instruction::SkipIfKey { x },
+                0xA1 => Instruction::SkipIfNotKey { x },
+                _ => return None,
+            },
+            0xF000 => match kk {
+                0x07 => Instruction::LoadDelayTimer { x },
+                0x0A => Instruction::WaitKey { x },
+                0x15 => Instruction::SetDelayTimer { x },
+                0x18 => Instruction::SetSoundTimer { x },
+                0x1E => Instruction::AddI { x },
+                0x29 => Instruction::LoadDigit { x },
+                0x33 => Instruction::StoreBcd { x },
+                0x55 => Instruction::Store { x },
+                0x65 => Instruction::Restore { x },
+                _ => return None,
+            },
+            _ => return None,
+        };
+        Some(decoded)
+    }
+
+    /// Encodes this instruction into 2 bytes. Operands out of range are truncated.
+    pub fn encode(self) -> u16 {
+        let nnn = |nnn: u16| nnn & 0x0FFF;
+        let xkk = |x: u8, kk: u8| u16::from(x & 0xF) << 8 | u16::from(kk);
+        let xyn = |x: u8, y: u8, n: u8| xkk(x, (y & 0xF) << 4 | n & 0xF);
+        match self {
+            Instruction::ClearScreen => 0x00E0,
+            Instruction::Return => 0x00EE,
+            Instruction::Syscall(address) => nnn(address),
+            Instruction::Jump(address) => 0x1000 | nnn(address),
+            Instruction::Call(address) => 0x2000 | nnn(address),
+            Instruction::SkipIfEqual { x, kk } => 0x3000 | xkk(x, kk),
+            Instruction::SkipIfNotEqual { x, kk } => 0x4000 | xkk(x, kk),
+            Instruction::SkipIfRegEqual { x, y } => 0x5000 | xyn(x, y, 0x0),
+            Instruction::Load { x, kk } => 0x6000 | xkk(x, kk),
+            Instruction::Add { x, kk } => 0x7000 | xkk(x, kk),
+            Instruction::LoadReg { x, y } => 0x8000 | xyn(x, y, 0x0),
+            Instruction::Or { x, y } => 0x8000 | xyn(x, y, 0x1),
+            Instruction::And { x, y } => 0x8000 | xyn(x, y, 0x2),
+            Instruction::Xor { x, y } => 0x8000 | xyn(x, y, 0x3),
+            Instruction::AddReg { x, y } => 0x8000 | xyn(x, y, 0x4),
+            Instruction::SubReg { x, y } => 0x8000 | xyn(x, y, 0x5),
+            Instruction::ShiftRight { x, y } => 0x8000 | xyn(x, y, 0x6),
+            Instruction::SubN { x, y } => 0x8000 | xyn(x, y, 0x7),
+            Instruction::ShiftLeft { x, y } => 0x8000 | xyn(x, y, 0xE),
+            Instruction::SkipIfRegNotEqual { x, y } => 0x9000 | xyn(x, y, 0x0),
+            Instruction::LoadI(address) => 0xA000 | nnn(address),
+            Instruction::JumpOffset(address) => 0xB000 | nnn(address),
+            Instruction::Random { x, kk } => 0xC000 | xkk(x, kk),
+            Instruction::Draw { x, y, n } => 0xD000 | xyn(x, y, n),
+            Instruction::SkipIfKey { x } => 0xE000 | xkk(x, 0x9E),
+            Instruction::SkipIfNotKey { x } => 0xE000 | xkk(x, 0xA1),
+            Instruction::LoadDelayTimer { x } => 0xF000 | xkk(x, 0x07),
+            Instruction::WaitKey { x } => 0xF000 | xkk(x, 0x0A),
+            Instruction::SetDelayTimer { x } => 0xF000 | xkk(x, 0x15),
+            Instruction::SetSoundTimer { x } => 0xF000 | xkk(x, 0x18),
+            Instruction::AddI { x } => 0xF000 | xkk(x, 0x1E),
+            Instruction::LoadDigit { x } => 0xF000 | xkk(x, 0x29),
+            Instruction::StoreBcd { x } => 0xF000 | xkk(x, 0x33),
+            Instruction::Store { x } => 0xF000 | xkk(x, 0x55),
+            Instruction::Restore { x } => 0xF000 | xkk(x, 0x65),
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    /// Formats the instruction as an assembly mnemonic from Cowgod's Chip-8 Technical Reference,
+    /// such as `DRW V0, V1, 5`, using the mnemonics in `isa::OPCODES`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let instruction = self.encode();
+        let opcode = isa::lookup(instruction).expect("every instruction has an opcode");
+        opcode.write(instruction, f)
+    }
+}
@@ -0,0 +1,301 @@
+//! The CHIP-8 instruction set as data, for tools that describe instructions to users.
+//!
+//! Mnemonics follow Cowgod's Chip-8 Technical Reference. `Instruction`'s `Display`, and so the
+//! disassembler, formats instructions from these mnemonics.
+
+use core::fmt;
+
+/// A field of an instruction that holds an operand.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Operand {
+    /// The register index in the second nibble.
+    X,
+    /// The register index in the third nibble.
+    Y,
+    /// The 4-bit number in the fourth nibble.
+    N,
+    /// The 8-bit number in the lower byte.
+    Kk,
+    /// The 12-bit address in the lower three nibbles.
+    Nnn,
+}
+
+impl Operand {
+    /// Returns the operand that `word` stands for in a mnemonic, such as `X` for `Vx`.
+    pub fn from_placeholder(word: &str) -> Option<Self> {
+        match word {
+            "Vx" => Some(Operand::X),
+            "Vy" => Some(Operand::Y),
+            "n" => Some(Operand::N),
+            "kk" => Some(Operand::Kk),
+            "nnn" => Some(Operand::Nnn),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this operand in `instruction`.
+    pub fn extract(self, instruction: u16) -> u16 {
+        match self {
+            Operand::X => (instruction & 0x0F00) >> 8,
+            Operand::Y => (instruction & 0x00F0) >> 4,
+            Operand::N => instruction & 0x000F,
+            Operand::Kk => instruction & 0x00FF,
+            Operand::Nnn => instruction & 0x0FFF,
+        }
+    }
+
+    /// Writes the value of this operand in `instruction` the way disassemblies show it.
+    fn write(self, instruction: u16, f: &mut impl fmt::Write) -> fmt::Result {
+        let value = self.extract(instruction);
+        match self {
+            Operand::X | Operand::Y => write!(f, "V{value:X}"),
+            Operand::N => write!(f, "{value}"),
+            Operand::Kk => write!(f, "{value:#04X}"),
+            Operand::Nnn => write!(f, "{value:#05X}"),
+        }
+    }
+}
+
+/// A field of `Quirks` that changes what an instruction does.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Quirk {
+    /// `Quirks::shift`.
+    Shift,
+    /// `Quirks::load_store`.
+    LoadStore,
+    /// `Quirks::vf_reset`.
+    VfReset,
+    /// `Quirks::clip_horizontally`.
+    ClipHorizontally,
+    /// `Quirks::clip_vertically`.
+    ClipVertically,
+    /// `Quirks::display_wait`.
+    DisplayWait,
+    /// `Quirks::jump`.
+    Jump,
+    /// `Quirks::index_overflow`.
+    IndexOverflow,
+    /// `Quirks::index_mask`.
+    IndexMask,
+    /// `Quirks::max_call_stack_depth`.
+    MaxCallStackDepth,
+    /// `Quirks::key_latch`.
+    KeyLatch,
+    /// `Quirks::reserved_memory_writes`.
+    ReservedMemoryWrites,
+    /// `Quirks::odd_jumps`.
+    OddJumps,
+}
+
+/// A platform that ran CHIP-8 programs.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Platform {
+    /// The original CHIP-8 interpreter on the COSMAC VIP.
+    CosmacVip,
+    /// CHIP-48 on the HP 48 calculators.
+    Chip48,
+    /// SCHIP (SUPER-CHIP) on the HP 48 calculators.
+    Schip,
+}
+
+const ALL_PLATFORMS: &[Platform] = &[Platform::CosmacVip, Platform::Chip48, Platform::Schip];
+
+/// The description of an opcode.
+#[derive(Clone, Copy, Debug)]
+pub struct Opcode {
+    /// The opcode as written in references, such as `8xy6`.
+    pub pattern: &'static str,
+    /// The bits of an instruction that identify this opcode.
+    pub mask: u16,
+    /// The value of the bits in `mask`.
+    pub value: u16,
+    /// The assembly mnemonic, such as `DRW Vx, Vy, n`, where `Vx`, `Vy`, `n`, `kk`, and `nnn` stand
+    /// for the operands.
+    pub mnemonic: &'static str,
+    /// The operands, in the order in which the mnemonic lists them.
+    pub operands: &'static [Operand],
+    /// What the instruction does.
+    pub description: &'static str,
+    /// The fields of `Quirks` that change what the instruction does.
+    pub quirks: &'static [Quirk],
+    /// The platforms that supported the instruction.
+    pub platforms: &'static [Platform],
+}
+
+impl Opcode {
+    /// Returns true if `instruction` is an instance of this opcode.
+    pub fn matches(&self, instruction: u16) -> bool {
+        instruction & self.mask == self.value
+    }
+
+    /// Writes the mnemonic of this opcode with the operands of `instruction` filled in, such as
+    /// `DRW V0, V1, 5`.
+    pub fn write(&self, instruction: u16, f: &mut impl fmt::Write) -> fmt::Result {
+        let mut rest = self.mnemonic;
+        while !rest.is_empty() {
+            let end = rest.find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or(rest.len());
+            let (word, tail) = rest.split_at(end.max(1));
+            match Operand::from_placeholder(word) {
+                Some(operand) => operand.write(instruction, f)?,
+                None => f.write_str(word)?,
+            }
+            rest = tail;
+        }
+        Ok(())
+    }
+}
+
+macro_rules! opcode {
+    (
+        $pattern:literal, $mask:literal, $value:literal, $mnemonic:literal, [$($operand:ident),*],
+        $description:literal, [$($quirk:ident),*]
+        $(, $platforms:expr)?
+    ) => {
+        Opcode {
+            pattern: $pattern,
+            mask: $mask,
+            value: $value,
+            mnemonic: $mnemonic,
+            operands: &[$(Operand::$operand),*],
+            description: $description,
+            quirks: &[$(Quirk::$quirk),*],
+            platforms: opcode!(@platforms $($platforms)?),
+        }
+    };
+    (@platforms) => { ALL_PLATFORMS };
+    (@platforms $platforms:expr) => { $platforms };
+}
+
+/// Every opcode that this interpreter executes. More specific opcodes come first, so the first
+/// match for an instruction is its opcode.
+pub const OPCODES: &[Opcode] = &[
+    opcode!("00E0", 0xFFFF, 0x00E0, "CLS", [], "Clears the screen", []),
+    opcode!("00EE", 0xFFFF, 0x00EE, "RET", [], "Returns from a subroutine", []),
+    opcode!(
+        "0nnn",
+        0xF000,
+        0x0000,
+        "SYS nnn",
+        [Nnn],
+        "Calls the machine code routine at nnn (see UnknownSyscallPolicy)",
+        [],
+        &[Platform::CosmacVip]
+    ),
+    opcode!("1nnn", 0xF000, 0x1000, "JP nnn", [Nnn], "Jumps to nnn", [OddJumps]),
+    opcode!(
+        "2nnn",
+        0xF000,
+        0x2000,
+        "CALL nnn",
+        [Nnn],
+        "Calls the subroutine at nnn",
+        [MaxCallStackDepth, OddJumps]
+    ),
+    opcode!("3xkk", 0xF000, 0x3000, "SE Vx, kk", [X, Kk], "Skips if Vx == kk", []),
+    opcode!("4xkk", 0xF000, 0x4000, "SNE Vx, kk", [X, Kk], "Skips if Vx != kk", []),
+    opcode!("5xy0", 0xF00F, 0x5000, "SE Vx, Vy", [X, Y], "Skips if Vx == Vy", []),
+    opcode!("6xkk", 0xF000, 0x6000, "LD Vx, kk", [X, Kk], "Vx = kk", []),
+    opcode!("7xkk", 0xF000, 0x7000, "ADD Vx, kk", [X, Kk], "Vx += kk, leaving VF untouched", []),
+    opcode!("8xy0", 0xF00F, 0x8000, "LD Vx, Vy", [X, Y], "Vx = Vy", []),
+    opcode!("8xy1", 0xF00F, 0x8001, "OR Vx, Vy", [X, Y], "Vx |= Vy", [VfReset]),
+    opcode!("8xy2", 0xF00F, 0x8002, "AND Vx, Vy", [X, Y], "Vx &= Vy", [VfReset]),
+    opcode!("8xy3", 0xF00F, 0x8003, "XOR Vx, Vy", [X, Y], "Vx ^= Vy", [VfReset]),
+    opcode!("8xy4", 0xF00F, 0x8004, "ADD Vx, Vy", [X, Y], "Vx += Vy, VF = carry", []),
+    opcode!("8xy5", 0xF00F, 0x8005, "SUB Vx, Vy", [X, Y], "Vx -= Vy, VF = not borrow", []),
+    opcode!(
+        "8xy6",
+        0xF00F,
+        0x8006,
+        "SHR Vx, Vy",
+        [X, Y],
+        "Vx = Vy >> 1 (or Vx >> 1), VF = the bit shifted out",
+        [Shift]
+    ),
+    opcode!("8xy7", 0xF00F, 0x8007, "SUBN Vx, Vy", [X, Y], "Vx = Vy - Vx, VF = not borrow", []),
+    opcode!(
+        "8xyE",
+        0xF00F,
+        0x800E,
+        "SHL Vx, Vy",
+        [X, Y],
+        "Vx = Vy << 1 (or Vx << 1), VF = the bit shifted out",
+        [Shift]
+    ),
+    opcode!("9xy0", 0xF00F, 0x9000, "SNE Vx, Vy", [X, Y], "Skips if Vx != Vy", []),
+    opcode!("Annn", 0xF000, 0xA000, "LD I, nnn", [Nnn], "I = nnn", []),
+    opcode!(
+        "Bnnn",
+        0xF000,
+        0xB000,
+        "JP V0, nnn",
+        [Nnn],
+        "Jumps to nnn + V0 (or, as Bxnn, to xnn + Vx)",
+        [Jump, OddJumps]
+    ),
+    opcode!("Cxkk", 0xF000, 0xC000, "RND Vx, kk", [X, Kk], "Vx = a random byte & kk", []),
+    opcode!(
+        "Dxyn",
+        0xF000,
+        0xD000,
+        "DRW Vx, Vy, n",
+        [X, Y, N],
+        "XORs the n-byte sprite at I onto the screen at (Vx, Vy), VF = collision",
+        [ClipHorizontally, ClipVertically, DisplayWait]
+    ),
+    opcode!("Ex9E", 0xF0FF, 0xE09E, "SKP Vx", [X], "Skips if the key Vx is pressed", [KeyLatch]),
+    opcode!(
+        "ExA1",
+        0xF0FF,
+        0xE0A1,
+        "SKNP Vx",
+        [X],
+        "Skips if the key Vx is not pressed",
+        [KeyLatch]
+    ),
+    opcode!("Fx07", 0xF0FF, 0xF007, "LD Vx, DT", [X], "Vx = the delay timer", []),
+    opcode!(
+        "Fx0A",
+        0xF0FF,
+        0xF00A,
+        "LD Vx, K",
+        [X],
+        "Waits for a key to be pressed and released, Vx = the key",
+        [KeyLatch]
+    ),
+    opcode!("Fx15", 0xF0FF, 0xF015, "LD DT, Vx", [X], "The delay timer = Vx", []),
+    opcode!("Fx18", 0xF0FF, 0xF018, "LD ST, Vx", [X], "The sound timer = Vx", []),
+    opcode!("Fx1E", 0xF0FF, 0xF01E, "ADD I, Vx", [X], "I += Vx", [IndexOverflow, IndexMask]),
+    opcode!("Fx29", 0xF0FF, 0xF029, "LD F, Vx", [X], "I = the address of the digit Vx", []),
+    opcode!(
+        "Fx33",
+        0xF0FF,
+        0xF033,
+        "LD B, Vx",
+        [X],
+        "Stores the decimal digits of Vx at I, I + 1, and I + 2",
+        [ReservedMemoryWrites]
+    ),
+    opcode!(
+        "Fx55",
+        0xF0FF,
+        0xF055,
+        "LD [I], Vx",
+        [X],
+        "Stores V0, ..., Vx at I, ..., I + x",
+        [LoadStore, IndexMask, ReservedMemoryWrites]
+    ),
+    opcode!(
+        "Fx65",
+        0xF0FF,
+        0xF065,
+        "LD Vx, [I]",
+        [X],
+        "Loads V0, ..., Vx from I, ..., I + x",
+        [LoadStore, IndexMask]
+    ),
+];
+
+/// Returns the opcode of `instruction`, or `None` if it is not a CHIP-8 instruction.
+pub fn lookup(instruction: u16) -> Option<&'static Opcode> {
+    OPCODES.iter().find(|opcode| opcode.matches(instruction))
+}
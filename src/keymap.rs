@@ -0,0 +1,85 @@
+//! Which PC keys type which CHIP-8 keys.
+//!
+//! PC keys are identified by their positions (SDL scancodes), named after the keys at those
+//! positions on a US QWERTY keyboard, or, with `--keycodes`, by the characters they type in the
+//! keyboard layout in use (SDL keycodes), so that on an AZERTY keyboard the key labelled A types
+//! what A types on a QWERTY one.
+
+use std::collections::HashMap;
+
+use sdl2::keyboard::{Keycode, Scancode};
+
+// The PC keys (by position or, with `--keycodes`, by character) on the left are mapped to the
+// CHIP-8 keys on the right:
+//
+//   1 2 3 4   1 2 3 C
+//   Q W E R   4 5 6 D
+//   A S D F   7 8 9 E
+//   Z X C V   A 0 B F
+const DEFAULT_LAYOUT: [(Scancode, u8); 16] = [
+    (Scancode::Num1, 0x1),
+    (Scancode::Num2, 0x2),
+    (Scancode::Num3, 0x3),
+    (Scancode::Num4, 0xC),
+    (Scancode::Q, 0x4),
+    (Scancode::W, 0x5),
+    (Scancode::E, 0x6),
+    (Scancode::R, 0xD),
+    (Scancode::A, 0x7),
+    (Scancode::S, 0x8),
+    (Scancode::D, 0x9),
+    (Scancode::F, 0xE),
+    (Scancode::Z, 0xA),
+    (Scancode::X, 0x0),
+    (Scancode::C, 0xB),
+    (Scancode::V, 0xF),
+];
+
+/// A CHIP-8 key and a PC key to type it with, given as `KEY=NAME` on the command line.
+#[derive(Clone, Copy, Debug)]
+pub struct KeyBinding {
+    pub key: u8,
+    pub scancode: Scancode,
+}
+
+pub struct KeyMap {
+    keys: HashMap<Scancode, u8>,
+    by_keycode: bool,
+}
+
+impl KeyMap {
+    /// Returns the default layout with `bindings` added, each replacing what its PC key typed. If
+    /// `by_keycode` is true, PC keys are identified by the characters they type.
+    pub fn new(bindings: &[KeyBinding], by_keycode: bool) -> Self {
+        let mut keys: HashMap<_, _> = DEFAULT_LAYOUT.into_iter().collect();
+        for binding in bindings {
+            keys.insert(binding.scancode, binding.key);
+        }
+        Self { keys, by_keycode }
+    }
+
+    /// Returns the CHIP-8 key that the PC key of a keyboard event types, if any.
+    pub fn key(&self, scancode: Option<Scancode>, keycode: Option<Keycode>) -> Option<u8> {
+        let scancode = if self.by_keycode {
+            // A keycode stands for the scancode of the same name, such as `Q` or `Up`.
+            Scancode::from_name(&keycode?.name())?
+        } else {
+            scancode?
+        };
+        self.keys.get(&scancode).copied()
+    }
+}
+
+/// Parses a binding written as `KEY=NAME`, where `KEY` is a hex digit and `NAME` is the SDL name of
+/// a PC key, such as `5=Up` or `0=Space`.
+pub fn parse_key_binding(binding: &str) -> Result<KeyBinding, String> {
+    let (key, name) =
+        binding.split_once('=').ok_or_else(|| format!("expected KEY=NAME, not {binding:?}"))?;
+    let key = u8::from_str_radix(key, 16)
+        .ok()
+        .filter(|&key| key < 16)
+        .ok_or_else(|| format!("{key:?} is not a hex key"))?;
+    let scancode =
+        Scancode::from_name(name).ok_or_else(|| format!("{name:?} is not the name of a key"))?;
+    Ok(KeyBinding { key, scancode })
+}
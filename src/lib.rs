@@ -1,128 +1,1355 @@
+#![no_std]
+#![forbid(unsafe_code)]
 #![warn(rust_2018_idioms)]
 
-use std::{
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+use alloc::{
+    boxed::Box,
+    collections::VecDeque,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{
     fmt::{self, Debug, Formatter},
-    fs::File,
-    io::{self, Read},
+    mem,
     ops::{BitOrAssign, BitXorAssign, Index, IndexMut, Range},
-    path::Path,
     time::Duration,
 };
+#[cfg(feature = "std")]
+use std::{fs, io, path::Path};
+
+use snafu::{ensure, OptionExt, Snafu};
+#[cfg(feature = "std")]
+use snafu::{Backtrace, ResultExt};
+
+use crate::{
+    cheat::Cheat,
+    condition::Condition,
+    hook::{Event, HookAction, Hooks},
+    instruction::Instruction,
+    rng::RngSource,
+    timing::{CostTable, Pacing},
+};
+
+/// Emits a `tracing` event, or does nothing without the `tracing` feature.
+macro_rules! trace_event {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::event!($($arg)*);
+    };
+}
 
-use snafu::{Backtrace, ResultExt, Snafu};
+pub mod analysis;
+#[cfg(feature = "std")]
+pub mod asm;
+pub mod cheat;
+pub mod condition;
+pub mod coverage;
+pub mod differential;
+pub mod disasm;
+#[cfg(feature = "std")]
+pub mod frontend;
+pub mod fuzz;
+pub mod hook;
+pub mod instruction;
+pub mod isa;
+#[cfg(feature = "std")]
+pub mod movie;
+#[cfg(feature = "std")]
+pub mod octo;
+pub mod post;
+#[cfg(feature = "std")]
+pub mod profiler;
+pub mod rewind;
+pub mod rng;
+pub mod rom_db;
+pub mod save_state;
+pub mod score;
+pub mod search;
+pub mod state;
+#[cfg(feature = "test-vectors")]
+pub mod test_vector;
+pub mod timing;
+#[cfg(feature = "std")]
+pub mod trace_export;
+pub mod verify;
 
 #[derive(Debug, Snafu)]
 pub enum Error {
+    #[snafu(display("The buffer of {len} bytes is shorter than the {required} bytes required"))]
+    BufferTooSmall { len: usize, required: usize },
+
+    #[snafu(display("Called at address {address:#06X} when the call stack was full"))]
+    CallStackOverflow { address: usize },
+
     #[snafu(display("Returned at adress {address:#06X} when the call stack was empty"))]
     CallStackUnderflow { address: usize },
 
+    #[snafu(display("A hook aborted execution at {pc:#06X}"))]
+    HookAborted { pc: usize },
+
+    #[snafu(display("The assembly is invalid at line {line}: {reason}"))]
+    InvalidAssembly { line: usize, reason: &'static str },
+
+    #[snafu(display("The cheat file is invalid at line {line}: {reason}"))]
+    InvalidCheat { line: usize, reason: &'static str },
+
+    #[snafu(display("Invalid condition {condition:?} at column {position}: {reason}"))]
+    InvalidCondition { condition: String, position: usize, reason: &'static str },
+
+    #[snafu(display("Invalid score location {location:?}"))]
+    InvalidScoreLocation { location: String },
+
+    #[snafu(display("The key {key:#04X} is not a hex key"))]
+    InvalidKey { key: u8 },
+
     #[snafu(display("The program counter {pc:#06X} is invalid"))]
     InvalidProgramCounter { pc: usize },
 
+    #[snafu(display("The movie is invalid at line {line}: {reason}"))]
+    InvalidMovie { line: usize, reason: &'static str },
+
+    #[snafu(display("The save state is invalid: {reason}"))]
+    InvalidSaveState { reason: &'static str },
+
+    #[cfg(feature = "test-vectors")]
+    #[snafu(display("The test vectors are invalid: {source}"))]
+    InvalidTestVectors { source: serde_json::Error },
+
+    #[snafu(display(
+        "Invariant violated at {location} ({invariant}): expected {expected:#04X}, got \
+         {actual:#04X}"
+    ))]
+    InvariantViolated { invariant: &'static str, location: String, expected: u16, actual: u16 },
+
+    #[cfg(feature = "std")]
     #[snafu(display("{source}"))]
     Io { source: io::Error, backtrace: Backtrace },
 
-    #[snafu(display("The instruction {instruction:#06X} at {pc:#06X} is not well-formed"))]
-    NotWellFormedInstruction { instruction: u16, pc: usize },
+    #[snafu(display(
+        "The instruction at {pc:#06X} accessed memory at {address:#06X} out of bounds"
+    ))]
+    MemoryOutOfBounds { address: usize, pc: usize },
+
+    #[snafu(display("{len} bytes at {address:#06X} are out of memory bounds"))]
+    MemoryRangeOutOfBounds { address: usize, len: usize },
+
+    #[snafu(display("The movie is for another ROM (hash {actual:#018X}, not {expected:#018X})"))]
+    MovieRomMismatch { expected: u64, actual: u64 },
+
+    #[snafu(display(
+        "The instruction {instruction:#06X} at {pc:#06X} is not well-formed ({state})"
+    ))]
+    NotWellFormedInstruction { instruction: u16, pc: usize, state: CpuState },
+
+    #[snafu(display("The instruction at {pc:#06X} jumped to the odd address {target:#06X}"))]
+    OddJump { target: usize, pc: usize },
+
+    #[snafu(display("The program of {size} bytes does not fit in memory"))]
+    ProgramTooLarge { size: usize },
+
+    #[snafu(display("The instruction at {pc:#06X} wrote to reserved memory at {address:#06X}"))]
+    ReservedMemoryWrite { address: usize, pc: usize },
+
+    #[snafu(display(
+        "The save state is for another ROM (hash {actual:#018X}, not {expected:#018X})"
+    ))]
+    SaveStateRomMismatch { expected: u64, actual: u64 },
 
     #[snafu(display(
-        "The instruction {instruction:#06X} at address {address:#06X} is not supported"
+        "The instruction {instruction:#06X} ({mnemonic}) at address {address:#06X} is not \
+         supported ({state})"
     ))]
-    UnsupportedInstruction { instruction: u16, address: usize },
+    UnsupportedInstruction { instruction: u16, mnemonic: String, address: usize, state: CpuState },
+
+    #[snafu(display("The save state version {version} is not supported"))]
+    UnsupportedSaveStateVersion { version: u16 },
+}
+
+type Result<T, E = Error> = core::result::Result<T, E>;
+
+const PROGRAM_SPACE: Range<usize> = 0x0200..0x1000;
+
+/// Selects between the variant semantics of some CHIP-8 instructions.
+///
+/// The default quirks are those that most CHIP-8 programs on the Internet expect.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Quirks {
+    /// <table>
+    /// <thead>
+    /// <tr>
+    ///   <th>Instruction</th>
+    ///   <th><code>shift</code></th>
+    ///   <th><code>!shift</code></th>
+    /// </tr>
+    /// </thead>
+    /// <tbody>
+    /// <tr>
+    ///   <td>8xy6</td>
+    ///   <td>Vx = Vx >> 1 and VF = carry</td>
+    ///   <td>Vx = Vy >> 1 and VF = carry</td>
+    /// </tr>
+    /// <tr>
+    ///   <td>8xyE</td>
+    ///   <td>Vx = Vx << 1 and VF = carry</td>
+    ///   <td>Vx = Vy << 1 and VF = carry</td>
+    /// </tr>
+    /// </tbody>
+    /// </table>
+    pub shift: bool,
+
+    /// <table>
+    /// <thead>
+    /// <tr>
+    ///   <th>Instruction</th>
+    ///   <th><code>load_store</code></th>
+    ///   <th><code>!load_store</code></th>
+    /// </tr>
+    /// </thead>
+    /// <tbody>
+    /// <tr>
+    ///   <td>Fx55</td>
+    ///   <td>Save V0..=Vx to memory I..=(I + x)</td>
+    ///   <td>Save V0..=Vx to memory I..=(I + x) and I = I + x + 1</td>
+    /// </tr>
+    /// <tr>
+    ///   <td>Fx65</td>
+    ///   <td>Load V0..=Vx from memory I..=(I + x)</td>
+    ///   <td>Load V0..=Vx from memory I..=(I + x) and I = I + x + 1</td>
+    /// </tr>
+    /// </tbody>
+    /// </table>
+    pub load_store: bool,
+
+    /// <table>
+    /// <thead>
+    /// <tr>
+    ///   <th>Instruction</th>
+    ///   <th><code>vf_reset</code></th>
+    ///   <th><code>!vf_reset</code></th>
+    /// </tr>
+    /// </thead>
+    /// <tbody>
+    /// <tr>
+    ///   <td>8xy1</td>
+    ///   <td>Vx = Vx | Vy and VF = 0</td>
+    ///   <td>Vx = Vx | Vy</td>
+    /// </tr>
+    /// <tr>
+    ///   <td>8xy2</td>
+    ///   <td>Vx = Vx & Vy and VF = 0</td>
+    ///   <td>Vx = Vx & Vy</td>
+    /// </tr>
+    /// <tr>
+    ///   <td>8xy3</td>
+    ///   <td>Vx = Vx ^ Vy and VF = 0</td>
+    ///   <td>Vx = Vx ^ Vy</td>
+    /// </tr>
+    /// </tbody>
+    /// </table>
+    pub vf_reset: bool,
+
+    /// If true, Dxyn clips sprites at the right edge of the screen. Otherwise, the parts of
+    /// sprites that would go off the right edge wrap around to the left side.
+    pub clip_horizontally: bool,
+
+    /// If true, Dxyn clips sprites at the bottom edge of the screen. Otherwise, the parts of
+    /// sprites that would go off the bottom edge wrap around to the top.
+    pub clip_vertically: bool,
+
+    /// If true, Dxyn waits for the next vertical blank interrupt after drawing a sprite, which
+    /// limits the number of sprites drawn per frame to one as on the COSMAC VIP.
+    pub display_wait: bool,
+
+    /// <table>
+    /// <thead>
+    /// <tr>
+    ///   <th>Instruction</th>
+    ///   <th><code>jump</code></th>
+    ///   <th><code>!jump</code></th>
+    /// </tr>
+    /// </thead>
+    /// <tbody>
+    /// <tr>
+    ///   <td>Bnnn</td>
+    ///   <td>Jump to address nnn + Vx, where x is the highest digit of nnn</td>
+    ///   <td>Jump to address nnn + V0</td>
+    /// </tr>
+    /// </tbody>
+    /// </table>
+    pub jump: bool,
+
+    /// If true, Fx1E sets VF to 1 if I + Vx is greater than 0x0FFF, and to 0 otherwise, as the
+    /// CHIP-8 interpreter for the Amiga did.
+    pub index_overflow: bool,
+
+    /// The mask applied to the I register whenever Fx1E, Fx55, or Fx65 increases it. `0x0FFF`
+    /// limits I to 12 bits as on the COSMAC VIP, and `0xFFFF` lets it grow to 16 bits.
+    pub index_mask: u16,
+
+    /// The maximum number of nested subroutine calls. The COSMAC VIP allowed 12, and SCHIP 16.
+    pub max_call_stack_depth: usize,
+
+    /// How long a key press stays visible to Ex9E, ExA1, and Fx0A after the key is released.
+    pub key_latch: KeyLatch,
+
+    /// What Fx33 and Fx55 do with writes below `0x200`, where the interpreter and the sprites for
+    /// the hexadecimal digits reside.
+    pub reserved_memory_writes: ReservedMemoryWrites,
+
+    /// What 1nnn, 2nnn, and Bnnn do when they jump to an odd address, which some programs do on
+    /// purpose to execute data as code and others by mistake.
+    pub odd_jumps: OddJumps,
+}
+
+/// What to do with writes to memory below the program space (`0x000..0x200`).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ReservedMemoryWrites {
+    /// Writes the memory, possibly corrupting the sprites for the hexadecimal digits.
+    Allow,
+    /// Leaves the memory unchanged.
+    Ignore,
+    /// Fails with `Error::ReservedMemoryWrite`.
+    Error,
+}
+
+/// What to do with jumps and calls to odd addresses.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OddJumps {
+    /// Jumps to the odd address, as the COSMAC VIP did, so that instructions are fetched across
+    /// the boundaries of those at even addresses.
+    Allow,
+    /// Jumps to the even address just below.
+    RoundDown,
+    /// Fails with `Error::OddJump`.
+    Error,
+}
+
+/// How long a key press stays visible to the interpreter after the key is released, so that
+/// programs polling keys only now and then do not miss brief presses.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KeyLatch {
+    /// Key releases are visible immediately.
+    None,
+    /// A key stays pressed for at least this many vertical blanks (see `Chip8::vblank`).
+    Frames(u32),
+    /// A key stays pressed for at least this many instruction cycles.
+    Cycles(u32),
+}
+
+impl Quirks {
+    /// The quirks that most CHIP-8 programs on the Internet expect.
+    pub const DEFAULT: Self = Self {
+        shift: true,
+        load_store: true,
+        vf_reset: false,
+        clip_horizontally: true,
+        clip_vertically: true,
+        display_wait: false,
+        jump: false,
+        index_overflow: false,
+        index_mask: 0xFFFF,
+        max_call_stack_depth: 16,
+        key_latch: KeyLatch::None,
+        reserved_memory_writes: ReservedMemoryWrites::Allow,
+        odd_jumps: OddJumps::Allow,
+    };
+
+    /// The quirks of the original CHIP-8 interpreter for the COSMAC VIP.
+    pub const COSMAC_VIP: Self = Self {
+        shift: false,
+        load_store: false,
+        vf_reset: true,
+        display_wait: true,
+        index_mask: 0x0FFF,
+        max_call_stack_depth: 12,
+        ..Self::DEFAULT
+    };
+}
+
+impl Default for Quirks {
+    /// Returns the quirks that most CHIP-8 programs on the Internet expect.
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// A CHIP-8 interpreter.
+///
+/// With the `serde` feature, its state can be serialized, except for the 0nnn policy, the random
+/// number generator, the cost table, the pacing of `run_for`, and the hooks, which are reset to
+/// their defaults when deserialized.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Chip8 {
+    ram: Vec<u8>, // random access memory
+    rom_hash: u64,
+    pc: usize,   // program counter (0 <= pc < 2 ** 16)
+    v: [u8; 16], // registers V0, ..., VF
+    i: u16,      // register I
+    call_stack: Vec<usize>,
+    call_stack_high_water_mark: usize,
+    /// The delay/sound timers.
+    pub timers: Timers,
+    is_key_pressed: [bool; 16], // If a hex key `k` is being pressed, `is_key_pressed[k]` is true.
+    key_latch_remaining: [u32; 16],
+    is_key_release_pending: [bool; 16],
+    key_wait: KeyWait,
+    pub screen: Screen,
+    quirks: Quirks,
+    waiting_for_vblank: bool,
+    #[cfg_attr(feature = "serde", serde(skip, default = "UnknownSyscallPolicy::default"))]
+    unknown_syscall_policy: UnknownSyscallPolicy,
+    #[cfg_attr(feature = "serde", serde(skip, default = "MalformedInstructionPolicy::default"))]
+    malformed_instruction_policy: MalformedInstructionPolicy,
+    #[cfg_attr(feature = "serde", serde(skip, default = "rng::default"))]
+    rng: Box<dyn RngSource>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    cost_table: CostTable,
+    cycles_executed: u64,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pacing: Pacing,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    hooks: Hooks,
+    trace: VecDeque<(usize, u16)>,
+    trace_len: usize,
+}
+
+/// The registers of an interpreter when an instruction failed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CpuState {
+    pub v: [u8; 16],
+    pub i: u16,
+    pub call_stack_depth: usize,
+}
+
+impl fmt::Display for CpuState {
+    /// Formats the registers as `V0..VF = 00 01 ... 0F, I = 0x0200, call stack depth = 0`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("V0..VF =")?;
+        for v in self.v {
+            write!(f, " {v:02X}")?;
+        }
+        write!(f, ", I = {:#06X}, call stack depth = {}", self.i, self.call_stack_depth)
+    }
+}
+
+/// What a call to `Chip8::fetch_execute_cycle` did.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct StepInfo {
+    /// The address of the instruction.
+    pub pc: usize,
+    /// The instruction that was executed, or `None` if none was because of waiting for the
+    /// vertical blank or the instruction was skipped.
+    pub instruction: Option<Instruction>,
+    /// True if the instruction was not well-formed and skipped (see
+    /// `MalformedInstructionPolicy`).
+    pub skipped: bool,
+    /// True if the screen was cleared or drawn to.
+    pub drew_to_screen: bool,
+    /// True if Fx0A is still waiting for a key to be pressed and released.
+    pub waiting_for_key: bool,
+    /// True if no instruction will be executed until the next vertical blank interrupt.
+    pub waiting_for_vblank: bool,
+    /// True if the sound timer was set while the beep was silent.
+    pub sound_started: bool,
+    /// True if a hook or a breakpoint paused execution, before the instruction if `instruction` is
+    /// `None`.
+    pub paused: bool,
+    /// The index of the breakpoint that paused execution, if one did (see
+    /// `Chip8::set_breakpoints`).
+    pub breakpoint: Option<usize>,
+    /// True if the program has ended by jumping to the jump itself (see `Chip8::is_halted`).
+    pub halted: bool,
+}
+
+/// What to do with a 0nnn instruction (call the machine code routine at address nnn) other than
+/// 00E0 and 00EE.
+pub enum UnknownSyscallPolicy {
+    /// Fails with `Error::UnsupportedInstruction`.
+    Error,
+    /// Does nothing.
+    Ignore,
+    /// Calls a function with the interpreter and the address nnn, which can emulate the routine.
+    Callback(SyscallCallback),
+}
+
+/// A function emulating the machine code routine at the given address.
+pub type SyscallCallback = Box<dyn FnMut(&mut Chip8, u16) + Send>;
+
+impl Default for UnknownSyscallPolicy {
+    /// Returns `UnknownSyscallPolicy::Error`.
+    fn default() -> Self {
+        UnknownSyscallPolicy::Error
+    }
+}
+
+impl Debug for UnknownSyscallPolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            UnknownSyscallPolicy::Error => f.write_str("Error"),
+            UnknownSyscallPolicy::Ignore => f.write_str("Ignore"),
+            UnknownSyscallPolicy::Callback(_) => f.write_str("Callback(..)"),
+        }
+    }
+}
+
+/// What to do with an instruction that is not well-formed, such as 5xy1 or junk data that the
+/// program counter has run into.
+pub enum MalformedInstructionPolicy {
+    /// Fails with `Error::NotWellFormedInstruction`.
+    Error,
+    /// Skips the instruction as if it did nothing.
+    Skip,
+    /// Calls a function with the instruction and its address, to warn about it, and skips the
+    /// instruction.
+    Warn(MalformedInstructionCallback),
+}
+
+/// A function warning about the not well-formed instruction at the given address.
+pub type MalformedInstructionCallback = Box<dyn FnMut(u16, usize) + Send>;
+
+impl Default for MalformedInstructionPolicy {
+    /// Returns `MalformedInstructionPolicy::Error`.
+    fn default() -> Self {
+        MalformedInstructionPolicy::Error
+    }
+}
+
+impl Debug for MalformedInstructionPolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            MalformedInstructionPolicy::Error => f.write_str("Error"),
+            MalformedInstructionPolicy::Skip => f.write_str("Skip"),
+            MalformedInstructionPolicy::Warn(_) => f.write_str("Warn(..)"),
+        }
+    }
+}
+
+/// Builds a `Chip8` with a fluent API, so that options can be set in any order and those left
+/// unset keep their defaults:
+///
+/// ```
+/// # fn main() -> Result<(), chip8::Error> {
+/// let chip8 = chip8::Chip8Builder::new().rom(&[0x12, 0x00]).seed(42).build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct Chip8Builder {
+    program: Vec<u8>,
+    quirks: Quirks,
+    rng: Option<Box<dyn RngSource>>,
+    unknown_syscall_policy: Option<UnknownSyscallPolicy>,
+    malformed_instruction_policy: Option<MalformedInstructionPolicy>,
+    cost_table: Option<CostTable>,
+    cpu_speed: Option<u32>,
+    timer_frequency: Option<u32>,
+    vip_timing: bool,
+}
+
+impl Chip8Builder {
+    /// Returns a builder for an interpreter with an empty program and the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the program, which will be loaded at 0x200.
+    pub fn rom(mut self, program: &[u8]) -> Self {
+        self.program = program.to_vec();
+        self
+    }
+
+    /// Sets the quirks, which are `Quirks::default()` by default.
+    pub fn quirks(mut self, quirks: Quirks) -> Self {
+        self.quirks = quirks;
+        self
+    }
+
+    /// Seeds the random number generator for Cxkk (see `rng::SeededRng`).
+    pub fn seed(self, seed: u64) -> Self {
+        self.rng(rng::SeededRng::new(seed))
+    }
+
+    /// Sets the random number generator for Cxkk (see `Chip8::set_rng`).
+    pub fn rng<R: RngSource + 'static>(mut self, rng: R) -> Self {
+        self.rng = Some(Box::new(rng));
+        self
+    }
+
+    /// Sets what to do with unknown 0nnn instructions (see `Chip8::set_unknown_syscall_policy`).
+    pub fn unknown_syscall_policy(mut self, policy: UnknownSyscallPolicy) -> Self {
+        self.unknown_syscall_policy = Some(policy);
+        self
+    }
+
+    /// Sets what to do with instructions that are not well-formed (see
+    /// `Chip8::set_malformed_instruction_policy`).
+    pub fn malformed_instruction_policy(mut self, policy: MalformedInstructionPolicy) -> Self {
+        self.malformed_instruction_policy = Some(policy);
+        self
+    }
+
+    /// Sets how many cycles each instruction costs (see `Chip8::set_cost_table`).
+    pub fn cost_table(mut self, cost_table: CostTable) -> Self {
+        self.cost_table = Some(cost_table);
+        self
+    }
+
+    /// Sets how many instructions `Chip8::run_for` executes per second (see
+    /// `Chip8::set_cpu_speed`).
+    pub fn cpu_speed(mut self, cpu_speed: u32) -> Self {
+        self.cpu_speed = Some(cpu_speed);
+        self
+    }
+
+    /// Sets how many times per second `Chip8::run_for` counts down the timers (see
+    /// `Chip8::set_timer_frequency`).
+    pub fn timer_frequency(mut self, timer_frequency: u32) -> Self {
+        self.timer_frequency = Some(timer_frequency);
+        self
+    }
+
+    /// Makes `Chip8::run_for` pace instructions by how long they took on the COSMAC VIP (see
+    /// `Chip8::set_vip_timing`).
+    pub fn vip_timing(mut self, vip_timing: bool) -> Self {
+        self.vip_timing = vip_timing;
+        self
+    }
+
+    /// Returns the configured interpreter.
+    pub fn build(self) -> Result<Chip8> {
+        let mut chip8 = Chip8::from_bytes(&self.program, self.quirks)?;
+        if let Some(rng) = self.rng {
+            chip8.rng = rng;
+        }
+        if let Some(policy) = self.unknown_syscall_policy {
+            chip8.unknown_syscall_policy = policy;
+        }
+        if let Some(policy) = self.malformed_instruction_policy {
+            chip8.malformed_instruction_policy = policy;
+        }
+        if let Some(cost_table) = self.cost_table {
+            chip8.cost_table = cost_table;
+        }
+        if let Some(cpu_speed) = self.cpu_speed {
+            chip8.set_cpu_speed(cpu_speed);
+        }
+        if let Some(timer_frequency) = self.timer_frequency {
+            chip8.set_timer_frequency(timer_frequency);
+        }
+        chip8.set_vip_timing(self.vip_timing);
+        Ok(chip8)
+    }
 }
 
-type Result<T, E = Error> = std::result::Result<T, E>;
+impl Chip8 {
+    /// Loads a program from a file, which will be run with the given quirks.
+    #[cfg(feature = "std")]
+    pub fn new<P: AsRef<Path>>(path: P, quirks: Quirks) -> Result<Self> {
+        let program = fs::read(path).context(IoSnafu)?;
+        Self::from_bytes(&program, quirks)
+    }
+
+    /// Returns a builder for an interpreter with more options than `new` and `from_bytes` take.
+    pub fn builder() -> Chip8Builder {
+        Chip8Builder::new()
+    }
+
+    /// Returns an interpreter with `program` loaded at 0x200, for ROMs that are not read from a
+    /// file, such as those embedded in the binary or downloaded.
+    pub fn from_bytes(program: &[u8], quirks: Quirks) -> Result<Self> {
+        let mut ram = Vec::with_capacity(PROGRAM_SPACE.end);
+        load_sprites_for_digits(&mut ram);
+        load_program(program, &mut ram)?;
+        Ok(Self {
+            ram,
+            rom_hash: save_state::rom_hash(program),
+            pc: PROGRAM_SPACE.start,
+            v: [0; 16],
+            i: 0,
+            call_stack: Vec::with_capacity(quirks.max_call_stack_depth),
+            call_stack_high_water_mark: 0,
+            timers: Timers { delay_timer: 0, sound_timer: 0 },
+            is_key_pressed: [false; 16],
+            key_latch_remaining: [0; 16],
+            is_key_release_pending: [false; 16],
+            key_wait: KeyWait::Idle,
+            screen: Screen::default(),
+            quirks,
+            waiting_for_vblank: false,
+            unknown_syscall_policy: UnknownSyscallPolicy::default(),
+            malformed_instruction_policy: MalformedInstructionPolicy::default(),
+            rng: rng::default(),
+            cost_table: CostTable::default(),
+            cycles_executed: 0,
+            pacing: Pacing::default(),
+            hooks: Hooks::default(),
+            trace: VecDeque::new(),
+            trace_len: 0,
+        })
+    }
+
+    /// Sets what to do with 0nnn instructions other than 00E0 and 00EE, which fail by default.
+    pub fn set_unknown_syscall_policy(&mut self, policy: UnknownSyscallPolicy) {
+        self.unknown_syscall_policy = policy;
+    }
+
+    /// Sets what to do with instructions that are not well-formed, which fail by default. Skipping
+    /// them lets old ROM dumps run whose junk data the original interpreters never reached
+    /// but this one does, such as after a skip that a quirk makes land elsewhere.
+    ///
+    /// ```
+    /// # fn main() -> Result<(), chip8::Error> {
+    /// use chip8::MalformedInstructionPolicy;
+    ///
+    /// let rom = [0x51, 0x21, 0x70, 0x01]; // (not well-formed); ADD V0, 0x01
+    /// let mut chip8 = chip8::Chip8::builder().rom(&rom).build()?;
+    /// assert!(chip8.fetch_execute_cycle().is_err());
+    ///
+    /// let mut chip8 = chip8::Chip8::builder().rom(&rom).build()?;
+    /// chip8.set_malformed_instruction_policy(MalformedInstructionPolicy::Skip);
+    /// assert!(chip8.fetch_execute_cycle()?.skipped);
+    /// chip8.fetch_execute_cycle()?;
+    /// assert_eq!(chip8.v()[0], 0x01);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_malformed_instruction_policy(&mut self, policy: MalformedInstructionPolicy) {
+        self.malformed_instruction_policy = policy;
+    }
+
+    /// Sets the random number generator for Cxkk, which is `rng::ThreadRng` by default (or a
+    /// `rng::SeededRng` seeded with 0 without the `std` feature). Use `rng::SeededRng` for
+    /// reproducible runs.
+    pub fn set_rng<R: RngSource + 'static>(&mut self, rng: R) {
+        self.rng = Box::new(rng);
+    }
+
+    /// Sets how many cycles each instruction costs. By default, every instruction costs 1.
+    pub fn set_cost_table(&mut self, cost_table: CostTable) {
+        self.cost_table = cost_table;
+    }
+
+    /// Sets how many instructions `run_for` executes per second, which is
+    /// `timing::DEFAULT_CPU_SPEED` by default.
+    pub fn set_cpu_speed(&mut self, cpu_speed: u32) {
+        self.pacing.instruction_cycle = Pacing::cycle(cpu_speed);
+    }
+
+    /// Sets how many times per second `run_for` counts down the timers and signals the vertical
+    /// blank, which is `timing::DEFAULT_TIMER_FREQUENCY` (60 Hz) by default. Some software was
+    /// written for 50 Hz PAL machines.
+    pub fn set_timer_frequency(&mut self, timer_frequency: u32) {
+        self.pacing.timer_cycle = Pacing::cycle(timer_frequency);
+    }
+
+    /// Returns the time between two ticks of the timers, which is also how long a frame lasts.
+    pub fn timer_cycle(&self) -> Duration {
+        self.pacing.timer_cycle
+    }
+
+    /// If true, `run_for` paces instructions by how long they took on the COSMAC VIP (see
+    /// `timing::cosmac_vip_duration`) instead of by the CPU speed.
+    pub fn set_vip_timing(&mut self, vip_timing: bool) {
+        self.pacing.vip_timing = vip_timing;
+    }
+
+    /// Sets a hook that is called before each instruction is executed. If it returns
+    /// `HookAction::Pause`, `fetch_execute_cycle` returns without executing the instruction, so
+    /// that the hook is called for it again by the next `fetch_execute_cycle`.
+    pub fn set_hook<F>(&mut self, hook: F)
+    where
+        F: FnMut(&Chip8, Instruction) -> HookAction + Send + 'static,
+    {
+        self.hooks.before = Some(Box::new(hook));
+    }
+
+    /// Sets a hook that is called after each instruction is executed.
+    pub fn set_post_hook<F>(&mut self, hook: F)
+    where
+        F: FnMut(&Chip8, Instruction) -> HookAction + Send + 'static,
+    {
+        self.hooks.after = Some(Box::new(hook));
+    }
+
+    /// Sets a listener that is called when the screen changes and when the beep starts or stops
+    /// (see `hook::Event`). Unlike polling `timers.sound_timer` once a frame, it sees beeps too
+    /// short to last until the frame ends. Events are raised by instructions and by the timers
+    /// counting down in `run_for` and `run_frame`, not by direct changes to `screen` or `timers`.
+    pub fn set_event_listener<F>(&mut self, listener: F)
+    where
+        F: FnMut(Event) + Send + 'static,
+    {
+        self.hooks.listener = Some(Box::new(listener));
+    }
+
+    /// Sets breakpoints, which pause execution before an instruction whenever their conditions
+    /// hold (see `condition`), such as `pc == 0x230 && v3 > 5`, replacing those set before. The
+    /// next `fetch_execute_cycle` then executes the instruction without checking them again,
+    /// unless the program counter has been changed.
+    ///
+    /// ```
+    /// # fn main() -> Result<(), chip8::Error> {
+    /// let rom = [0x70, 0x01, 0x12, 0x00]; // ADD V0, 0x01; JP 0x200
+    /// let mut chip8 = chip8::Chip8::builder().rom(&rom).build()?;
+    /// chip8.set_breakpoints(vec!["pc == 0x200 && v0 in 3..5".parse()?]);
+    /// let steps = chip8.run_frame(100)?;
+    /// assert_eq!(steps.last().and_then(|step| step.breakpoint), Some(0));
+    /// assert_eq!(chip8.v()[0], 3);
+    /// chip8.run_frame(2)?;
+    /// assert_eq!(chip8.v()[0], 4);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_breakpoints(&mut self, breakpoints: Vec<Condition>) {
+        self.hooks.breakpoints = breakpoints;
+        self.hooks.resume_pc = None;
+    }
+
+    /// Returns the breakpoints.
+    pub fn breakpoints(&self) -> &[Condition] {
+        &self.hooks.breakpoints
+    }
+
+    /// Removes the hooks, the breakpoints, and the event listener.
+    pub fn clear_hooks(&mut self) {
+        self.hooks = Hooks::default();
+    }
+
+    /// Returns the total cost, according to the cost table, of the instructions executed so far.
+    pub fn cycles_executed(&self) -> u64 {
+        self.cycles_executed
+    }
+
+    /// Keeps the address and the opcode of the last `len` instructions fetched, which `trace`
+    /// returns, for finding out how execution got to an error. Tracing is off (`len` is 0) by
+    /// default.
+    pub fn set_trace_len(&mut self, len: usize) {
+        self.trace_len = len;
+        self.trace.drain(..self.trace.len().saturating_sub(len));
+    }
+
+    /// Returns the address and the opcode of each of the last instructions fetched, oldest first,
+    /// including the one that failed if `fetch_execute_cycle` has just failed.
+    pub fn trace(&self) -> impl Iterator<Item = (usize, u16)> + '_ {
+        self.trace.iter().copied()
+    }
+
+    /// Returns the registers V0, ..., VF.
+    pub fn v(&self) -> &[u8; 16] {
+        &self.v
+    }
+
+    /// Returns the registers V0, ..., VF for modification.
+    pub fn v_mut(&mut self) -> &mut [u8; 16] {
+        &mut self.v
+    }
+
+    /// Returns the I register.
+    pub fn i(&self) -> u16 {
+        self.i
+    }
+
+    /// Sets the I register.
+    pub fn set_i(&mut self, i: u16) {
+        self.i = i;
+    }
+
+    /// Returns the program counter.
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    /// Sets the program counter, which fails the next `fetch_execute_cycle` if out of memory.
+    pub fn set_pc(&mut self, pc: usize) {
+        self.pc = pc;
+    }
+
+    /// Returns the hash of the ROM that this interpreter was created with, or of the one given to
+    /// the last `hot_reload` (see `save_state::rom_hash`).
+    pub fn rom_hash(&self) -> u64 {
+        self.rom_hash
+    }
+
+    /// Replaces the program, which was `old_program`, with `program` without resetting: only the
+    /// bytes in which they differ are written, treating the bytes past the end of the shorter one
+    /// as zeros, so the registers and the data that the program wrote elsewhere in memory are
+    /// kept. Fails without changing anything if `program` is too large.
+    ///
+    /// ```
+    /// # fn main() -> Result<(), chip8::Error> {
+    /// let rom = [0x60, 0x05, 0xA3, 0x00, 0xF0, 0x55, 0x12, 0x06]; // V0 = 5; [0x300] = V0; loop
+    /// let mut chip8 = chip8::Chip8::builder().rom(&rom).build()?;
+    /// chip8.run_frame(3)?;
+    /// let patched = [0x60, 0x07, 0xA3, 0x00, 0xF0, 0x55, 0x12, 0x06];
+    /// chip8.hot_reload(&rom, &patched)?;
+    /// assert_eq!(chip8.memory()[0x201], 0x07);
+    /// assert_eq!((chip8.pc(), chip8.v()[0], chip8.memory()[0x300]), (0x206, 5, 5));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn hot_reload(&mut self, old_program: &[u8], program: &[u8]) -> Result<()> {
+        ensure!(program.len() <= PROGRAM_SPACE.len(), ProgramTooLargeSnafu { size: program.len() });
+        let len = old_program.len().max(program.len()).min(PROGRAM_SPACE.len());
+        for offset in 0..len {
+            let byte = program.get(offset).copied().unwrap_or(0);
+            if old_program.get(offset).copied().unwrap_or(0) != byte {
+                self.ram[PROGRAM_SPACE.start + offset] = byte;
+            }
+        }
+        self.rom_hash = save_state::rom_hash(program);
+        Ok(())
+    }
+
+    /// Returns the whole memory, with the font at 0x000 and the program at 0x200.
+    pub fn memory(&self) -> &[u8] {
+        &self.ram
+    }
+
+    /// Returns the whole memory for modification. Writes through it are not subject to
+    /// `Quirks::reserved_memory_writes`.
+    pub fn memory_mut(&mut self) -> &mut [u8] {
+        &mut self.ram
+    }
+
+    /// Returns `len` bytes of memory at `address`, failing if they are out of bounds.
+    pub fn read(&self, address: usize, len: usize) -> Result<&[u8]> {
+        let range = address..address.saturating_add(len);
+        self.ram.get(range).context(MemoryRangeOutOfBoundsSnafu { address, len })
+    }
+
+    /// Writes `bytes` to memory at `address`, failing without writing anything if they are out of
+    /// bounds. The writes are not subject to `Quirks::reserved_memory_writes`.
+    pub fn write(&mut self, address: usize, bytes: &[u8]) -> Result<()> {
+        let len = bytes.len();
+        let range = address..address.saturating_add(len);
+        self.ram
+            .get_mut(range)
+            .context(MemoryRangeOutOfBoundsSnafu { address, len })?
+            .copy_from_slice(bytes);
+        Ok(())
+    }
+
+    /// Writes the byte of `cheat` to memory, failing if its address is out of bounds. Applying
+    /// `CheatMode::Freeze` cheats every frame is up to the caller.
+    ///
+    /// ```
+    /// # fn main() -> Result<(), chip8::Error> {
+    /// use chip8::cheat::{Cheat, CheatMode};
+    ///
+    /// let mut chip8 = chip8::Chip8::builder().build()?;
+    /// chip8.apply_cheat(&Cheat { address: 0x3A0, value: 9, mode: CheatMode::Freeze })?;
+    /// assert_eq!(chip8.memory()[0x3A0], 9);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn apply_cheat(&mut self, cheat: &Cheat) -> Result<()> {
+        self.write(usize::from(cheat.address), &[cheat.value])
+    }
+
+    /// Returns the return addresses of the subroutine calls that have not returned yet, the
+    /// innermost last.
+    pub fn call_stack(&self) -> &[usize] {
+        &self.call_stack
+    }
+
+    /// Returns the call stack for modification. Pushing beyond `Quirks::max_call_stack_depth`
+    /// makes the next 2nnn fail.
+    pub fn call_stack_mut(&mut self) -> &mut Vec<usize> {
+        &mut self.call_stack
+    }
+
+    /// Returns the number of subroutine calls that have not returned yet.
+    pub fn call_stack_depth(&self) -> usize {
+        self.call_stack.len()
+    }
+
+    /// Returns the largest call stack depth that has been reached so far.
+    pub fn call_stack_high_water_mark(&self) -> usize {
+        self.call_stack_high_water_mark
+    }
+
+    /// Presses a hex key `key` (0x0..=0xF).
+    pub fn press_key(&mut self, key: u8) -> Result<()> {
+        let pressed =
+            self.is_key_pressed.get_mut(usize::from(key)).context(InvalidKeySnafu { key })?;
+        if !*pressed {
+            *pressed = true;
+            if let KeyWait::Press = self.key_wait {
+                self.key_wait = KeyWait::Release(key);
+            }
+        }
+        self.key_latch_remaining[usize::from(key)] = match self.quirks.key_latch {
+            KeyLatch::None => 0,
+            KeyLatch::Frames(n) | KeyLatch::Cycles(n) => n,
+        };
+        self.is_key_release_pending[usize::from(key)] = false;
+        Ok(())
+    }
+
+    /// Releases a hex key `key` (0x0..=0xF). The release may become visible later depending on the
+    /// `key_latch` quirk.
+    pub fn release_key(&mut self, key: u8) -> Result<()> {
+        let remaining =
+            self.key_latch_remaining.get(usize::from(key)).context(InvalidKeySnafu { key })?;
+        if *remaining > 0 {
+            self.is_key_release_pending[usize::from(key)] = true;
+        } else {
+            self.release_latched_key(key);
+        }
+        Ok(())
+    }
+
+    fn release_latched_key(&mut self, key: u8) {
+        self.is_key_pressed[usize::from(key)] = false;
+        if let KeyWait::Release(pressed_key) = self.key_wait {
+            if pressed_key == key {
+                self.key_wait = KeyWait::Released(key);
+            }
+        }
+    }
+
+    fn count_down_key_latches(&mut self) {
+        for key in 0..16 {
+            let remaining = &mut self.key_latch_remaining[usize::from(key)];
+            if *remaining > 0 {
+                *remaining -= 1;
+                if *remaining == 0 && self.is_key_release_pending[usize::from(key)] {
+                    self.is_key_release_pending[usize::from(key)] = false;
+                    self.release_latched_key(key);
+                }
+            }
+        }
+    }
+
+    /// Returns true if a hex key `key` is being pressed, or false if it is not or out of bounds.
+    pub fn is_key_pressed(&self, key: u8) -> bool {
+        self.is_key_pressed.get(usize::from(key)).copied().unwrap_or(false)
+    }
+
+    /// Returns true if a sprite has been drawn with the `display_wait` quirk and the interpreter is
+    /// waiting for the next vertical blank interrupt, during which `fetch_execute_cycle` does
+    /// nothing.
+    pub fn is_waiting_for_vblank(&self) -> bool {
+        self.waiting_for_vblank
+    }
+
+    /// Signals the vertical blank interrupt, which should occur at the same rate as the timers.
+    pub fn vblank(&mut self) {
+        self.waiting_for_vblank = false;
+        if let KeyLatch::Frames(_) = self.quirks.key_latch {
+            self.count_down_key_latches();
+        }
+    }
+
+    /// Returns true if the next instruction is a 1nnn that jumps to itself, which many programs
+    /// end with, so that nothing but the timers will ever change again.
+    ///
+    /// Loops of several instructions are not reported, since programs wait for keys and timers in
+    /// such loops, like Fx0A waits without leaving its instruction, and resume afterwards.
+    pub fn is_halted(&self) -> bool {
+        matches!(
+            self.next_instruction().and_then(Instruction::decode),
+            Some(Instruction::Jump(nnn)) if usize::from(nnn) == self.pc
+        )
+    }
+
+    /// Returns the 2-bytes instruction pointed by the current program counter, if any, which will be
+    /// executed by the next `fetch_execute_cycle`.
+    pub fn next_instruction(&self) -> Option<u16> {
+        let bytes = self.ram.get(self.pc..(self.pc + 2))?;
+        Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// Fetches a 2-bytes instruction pointed by the current program counter and executes it,
+    /// returning what happened.
+    pub fn fetch_execute_cycle(&mut self) -> Result<StepInfo> {
+        let pc = self.pc;
+        if !self.waiting_for_vblank {
+            if let Some(index) = self.hit_breakpoint() {
+                let breakpoint = Some(index);
+                return Ok(StepInfo { pc, paused: true, breakpoint, ..StepInfo::default() });
+            }
+            if let Some(instruction) = self.next_instruction().and_then(Instruction::decode) {
+                if self.call_hook(false, pc, instruction)? {
+                    return Ok(StepInfo { pc, paused: true, ..StepInfo::default() });
+                }
+            }
+        }
+        if let KeyLatch::Cycles(_) = self.quirks.key_latch {
+            self.count_down_key_latches();
+        }
+        if self.waiting_for_vblank {
+            return Ok(StepInfo { pc, waiting_for_vblank: true, ..StepInfo::default() });
+        }
+        let sound_timer = self.timers.sound_timer;
+        let instruction = self.fetch_instruction()?;
+        if self.trace_len > 0 {
+            if self.trace.len() == self.trace_len {
+                self.trace.pop_front();
+            }
+            self.trace.push_back((pc, instruction));
+        }
+        let Some(decoded) = self.execute_instruction(instruction)? else {
+            self.cycles_executed += self.cost_table.cost(instruction);
+            return Ok(StepInfo { pc, skipped: true, ..StepInfo::default() });
+        };
+        trace_event!(
+            tracing::Level::TRACE,
+            pc,
+            opcode = instruction,
+            instruction = %decoded,
+            "executed"
+        );
+        self.cycles_executed += self.cost_table.cost(instruction);
+        let paused = self.call_hook(true, pc, decoded)?;
+        Ok(StepInfo {
+            pc,
+            instruction: Some(decoded),
+            drew_to_screen: matches!(decoded, Instruction::ClearScreen | Instruction::Draw { .. }),
+            // Fx0A executes again until a key is pressed and released.
+            waiting_for_key: matches!(decoded, Instruction::WaitKey { .. }) && self.pc == pc,
+            waiting_for_vblank: self.waiting_for_vblank,
+            sound_started: sound_timer == 0 && self.timers.sound_timer > 0,
+            skipped: false,
+            paused,
+            breakpoint: None,
+            halted: self.is_halted(),
+        })
+    }
+
+    /// Executes the next instruction and, if it is 2nnn, the subroutine it calls until it returns,
+    /// for debuggers stepping past subroutines. Returns what each instruction executed did.
+    ///
+    /// Execution stops early, before the subroutine returns, after `max_steps` instructions or a
+    /// step that a hook or a breakpoint paused, that waits for a key or for the vertical blank,
+    /// which cannot come while stepping, or that halts the program.
+    ///
+    /// ```
+    /// # fn main() -> Result<(), chip8::Error> {
+    /// // CALL 0x206; LD V1, 0x01; (halt); ADD V0, 0x01; ADD V0, 0x01; RET
+    /// let rom = [0x22, 0x06, 0x61, 0x01, 0x12, 0x04, 0x70, 0x01, 0x70, 0x01, 0x00, 0xEE];
+    /// let mut chip8 = chip8::Chip8::builder().rom(&rom).build()?;
+    /// assert_eq!(chip8.step_over(100)?.len(), 4);
+    /// assert_eq!((chip8.pc(), chip8.v()[0]), (0x202, 2));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn step_over(&mut self, max_steps: usize) -> Result<Vec<StepInfo>> {
+        let depth = self.call_stack.len();
+        self.step_while(max_steps, |chip8, steps| steps == 0 || chip8.call_stack.len() > depth)
+    }
+
+    /// Executes instructions until the subroutine being executed returns, for debuggers stepping
+    /// out of subroutines. Returns what each instruction executed did, which is nothing outside of
+    /// subroutines. Execution stops early as it does for `step_over`.
+    ///
+    /// ```
+    /// # fn main() -> Result<(), chip8::Error> {
+    /// // CALL 0x206; LD V1, 0x01; (halt); ADD V0, 0x01; ADD V0, 0x01; RET
+    /// let rom = [0x22, 0x06, 0x61, 0x01, 0x12, 0x04, 0x70, 0x01, 0x70, 0x01, 0x00, 0xEE];
+    /// let mut chip8 = chip8::Chip8::builder().rom(&rom).build()?;
+    /// chip8.fetch_execute_cycle()?;
+    /// chip8.fetch_execute_cycle()?;
+    /// assert_eq!(chip8.step_out(100)?.len(), 2);
+    /// assert_eq!((chip8.pc(), chip8.v()[0]), (0x202, 2));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn step_out(&mut self, max_steps: usize) -> Result<Vec<StepInfo>> {
+        let depth = self.call_stack.len();
+        self.step_while(max_steps, |chip8, _| depth > 0 && chip8.call_stack.len() >= depth)
+    }
+
+    /// Executes instructions while `proceed`, given the number of steps so far, returns true,
+    /// stopping as `step_over` describes.
+    fn step_while<F>(&mut self, max_steps: usize, mut proceed: F) -> Result<Vec<StepInfo>>
+    where
+        F: FnMut(&Chip8, usize) -> bool,
+    {
+        let mut steps = Vec::new();
+        while steps.len() < max_steps && proceed(self, steps.len()) {
+            let step = self.fetch_execute_cycle()?;
+            steps.push(step);
+            if step.paused || step.waiting_for_key || step.waiting_for_vblank || step.halted {
+                break;
+            }
+        }
+        Ok(steps)
+    }
+
+    /// Executes `instruction` as if it had been fetched at the program counter, without reading
+    /// it from memory, for test vectors and property tests that set up a state and check the
+    /// result of a single instruction. The program counter moves past the instruction first, as it
+    /// does when fetching, and `cycles_executed` counts it, but the hooks, the trace, and
+    /// waiting for the vertical blank are ignored.
+    ///
+    /// ```
+    /// # fn main() -> Result<(), chip8::Error> {
+    /// use chip8::instruction::Instruction;
+    ///
+    /// let mut chip8 = chip8::Chip8::from_bytes(&[], Default::default())?;
+    /// chip8.v_mut()[1] = 0xFF;
+    /// chip8.execute(Instruction::Add { x: 1, kk: 0x02 })?;
+    /// assert_eq!(chip8.v()[1], 0x01);
+    /// assert_eq!(chip8.v()[0xF], 0x00);
+    /// assert_eq!(chip8.pc(), 0x202);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn execute(&mut self, instruction: Instruction) -> Result<()> {
+        self.execute_opcode(instruction.encode())
+    }
+
+    /// Executes `opcode` like `execute`, failing if it is not well-formed unless the policy skips
+    /// it.
+    pub(crate) fn execute_opcode(&mut self, opcode: u16) -> Result<()> {
+        self.pc += 2;
+        self.execute_instruction(opcode)?;
+        self.cycles_executed += self.cost_table.cost(opcode);
+        Ok(())
+    }
+
+    /// Runs the interpreter for `elapsed` time, such as the time since the last call, returning
+    /// what each instruction executed did.
+    ///
+    /// The timers count down and the vertical blank is signaled at the timer frequency (see
+    /// `set_timer_frequency`), and then the instructions due are executed at the pace set by
+    /// `set_cpu_speed` or `set_vip_timing`. Time left over, less than a timer tick or an
+    /// instruction, is carried over to the next call.
+    /// Execution stops early if a hook or a breakpoint pauses it, the interpreter waits for the
+    /// vertical blank, or the program halts, dropping the time left over for instructions.
+    ///
+    /// ```
+    /// # fn main() -> Result<(), chip8::Error> {
+    /// let rom = [0x70, 0x01, 0x12, 0x00]; // ADD V0, 0x01; JP 0x200
+    /// let mut chip8 = chip8::Chip8::builder().rom(&rom).cpu_speed(1000).build()?;
+    /// let steps = chip8.run_for(std::time::Duration::from_millis(100))?;
+    /// assert_eq!(steps.len(), 100);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn run_for(&mut self, elapsed: Duration) -> Result<Vec<StepInfo>> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("run_for", ?elapsed).entered();
+        self.pacing.timer_time_lag += elapsed;
+        while self.pacing.timer_time_lag >= self.pacing.timer_cycle {
+            self.count_down_timers();
+            self.vblank();
+            self.pacing.timer_time_lag -= self.pacing.timer_cycle;
+        }
+
+        // NOTE: Unless emulating the COSMAC VIP timing, each CHIP-8 instruction is assumed to
+        // finish within a single instruction cycle.
+        self.pacing.cpu_time_lag += elapsed;
+        let mut steps = Vec::new();
+        loop {
+            let instruction_cycle = match self.next_instruction() {
+                Some(instruction) if self.pacing.vip_timing => {
+                    timing::cosmac_vip_duration(instruction)
+                }
+                _ => self.pacing.instruction_cycle,
+            };
+            if self.pacing.cpu_time_lag < instruction_cycle {
+                break;
+            }
+            if self.waiting_for_vblank {
+                self.pacing.cpu_time_lag = Duration::ZERO;
+                break;
+            }
+            let step = self.fetch_execute_cycle()?;
+            steps.push(step);
+            if step.paused || step.halted {
+                self.pacing.cpu_time_lag = Duration::ZERO;
+                break;
+            }
+            self.pacing.cpu_time_lag -= instruction_cycle;
+        }
+        Ok(steps)
+    }
+
+    /// Runs the interpreter for a frame: executes up to `instructions_per_frame` instructions,
+    /// then counts down the timers and signals the vertical blank, returning what each instruction
+    /// executed did. Execution stops early if a hook or a breakpoint pauses it, the interpreter
+    /// waits for the vertical blank, or the program halts.
+    ///
+    /// Unlike `run_for`, this paces nothing, for frontends that are called back at a fixed frame
+    /// rate.
+    pub fn run_frame(&mut self, instructions_per_frame: u32) -> Result<Vec<StepInfo>> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("run_frame", instructions_per_frame).entered();
+        let mut steps = Vec::new();
+        for _ in 0..instructions_per_frame {
+            if self.waiting_for_vblank {
+                break;
+            }
+            let step = self.fetch_execute_cycle()?;
+            steps.push(step);
+            if step.paused || step.halted {
+                break;
+            }
+        }
+        self.count_down_timers();
+        self.vblank();
+        Ok(steps)
+    }
+
+    fn count_down_timers(&mut self) {
+        let beeping = self.timers.sound_timer > 0;
+        self.timers.count_down();
+        trace_event!(
+            tracing::Level::TRACE,
+            delay_timer = self.timers.delay_timer,
+            sound_timer = self.timers.sound_timer,
+            "timer tick"
+        );
+        if beeping && self.timers.sound_timer == 0 {
+            self.emit(Event::SoundStopped);
+        }
+    }
 
-const PROGRAM_SPACE: Range<usize> = 0x0200..0x1000;
+    fn emit(&mut self, event: Event) {
+        if let Some(listener) = &mut self.hooks.listener {
+            listener(event);
+        }
+    }
 
-#[derive(Debug)]
-pub struct Chip8 {
-    ram: Vec<u8>, // random access memory
-    pc: usize,    // program counter (0 <= pc < 2 ** 16)
-    v: [u8; 16],  // registers V0, ..., VF
-    i: u16,       // register I
-    call_stack: Vec<usize>,
-    /// The delay/sound timers.
-    pub timers: Timers,
-    /// If a hex key `k` is being pressed, `is_key_pressed[k]` is true.
-    pub is_key_pressed: [bool; 16],
-    pub screen: Screen,
-    shift_quirks: bool,
-    load_store_quirks: bool,
-}
+    fn cpu_state(&self) -> CpuState {
+        CpuState { v: self.v, i: self.i, call_stack_depth: self.call_stack.len() }
+    }
 
-impl Chip8 {
-    /// Loads a program.
-    ///
-    /// <table>
-    /// <thead>
-    /// <tr>
-    ///   <th>Instruction</th>
-    ///   <th><code>shift_quirks</code></th>
-    ///   <th><code>!shift_quirks</code></th>
-    /// </tr>
-    /// </thead>
-    /// <tbody>
-    /// <tr>
-    ///   <td>8xy6</td>
-    ///   <td>Vx = Vx >> 1 and VF = carry</td>
-    ///   <td>Vx = Vy >> 1 and VF = carry</td>
-    /// </tr>
-    /// <tr>
-    ///   <td>8xyE</td>
-    ///   <td>Vx = Vx << 1 and VF = carry</td>
-    ///   <td>Vx = Vy << 1 and VF = carry</td>
-    /// </tr>
-    /// </tbody>
-    /// </table>
-    /// <table>
-    /// <thead>
-    /// <tr>
-    ///   <th>Instruction</th>
-    ///   <th><code>load_store_quirks</code></th>
-    ///   <th><code>!load_store_quirks</code></th>
-    /// </tr>
-    /// </thead>
-    /// <tbody>
-    /// <tr>
-    ///   <td>Fx55</td>
-    ///   <td>Save V0..=Vx to memory I..=(I + x)</td>
-    ///   <td>Save V0..=Vx to memory I..=(I + x) and I = I + x + 1</td>
-    /// </tr>
-    /// <tr>
-    ///   <td>Fx65</td>
-    ///   <td>Load V0..=Vx from memory I..=(I + x)</td>
-    ///   <td>Load V0..=Vx from memory I..=(I + x) and I = I + x + 1</td>
-    /// </tr>
-    /// </tbody>
-    /// </table>
-    pub fn new<P: AsRef<Path>>(
-        path: P,
-        shift_quirks: bool,
-        load_store_quirks: bool,
-    ) -> Result<Self> {
-        let mut ram = Vec::with_capacity(PROGRAM_SPACE.end);
-        load_sprites_for_digits(&mut ram);
-        load_program(path, &mut ram)?;
-        Ok(Self {
-            ram,
-            pc: PROGRAM_SPACE.start,
-            v: [0; 16],
-            i: 0,
-            call_stack: Vec::with_capacity(12),
-            timers: Timers { delay_timer: 0, sound_timer: 0 },
-            is_key_pressed: [false; 16],
-            screen: Screen::default(),
-            shift_quirks,
-            load_store_quirks,
-        })
+    /// Returns the index of the first breakpoint whose condition holds, unless execution resumes
+    /// from it.
+    fn hit_breakpoint(&mut self) -> Option<usize> {
+        if self.hooks.resume_pc.take() == Some(self.pc) {
+            return None;
+        }
+        let index =
+            self.hooks.breakpoints.iter().position(|breakpoint| breakpoint.evaluate(self))?;
+        self.hooks.resume_pc = Some(self.pc);
+        Some(index)
     }
 
-    /// Fetches a 2-bytes instruction pointed by the current program counter and executes it.
-    pub fn fetch_execute_cycle(&mut self) -> Result<()> {
-        let instruction = self.fetch_instruction()?;
-        self.execute_instruction(instruction)?;
-        Ok(())
+    /// Calls the hook before or after the instruction at `pc`, returning true if it pauses.
+    fn call_hook(&mut self, after: bool, pc: usize, instruction: Instruction) -> Result<bool> {
+        let mut hooks = mem::take(&mut self.hooks);
+        let hook = if after { &mut hooks.after } else { &mut hooks.before };
+        let action = hook.as_mut().map_or(HookAction::Continue, |hook| hook(self, instruction));
+        self.hooks = hooks;
+        match action {
+            HookAction::Continue => Ok(false),
+            HookAction::Pause => Ok(true),
+            HookAction::Abort => HookAbortedSnafu { pc }.fail(),
+        }
     }
 
     fn fetch_instruction(&mut self) -> Result<u16> {
@@ -141,270 +1368,396 @@ impl Chip8 {
         Ok(instruction)
     }
 
+    /// Reads a byte from memory for the instruction that has just been fetched.
+    fn read_memory(&self, address: usize) -> Result<u8> {
+        let pc = self.pc - 2;
+        self.ram.get(address).copied().context(MemoryOutOfBoundsSnafu { address, pc })
+    }
+
+    /// Fails unless memory `start..=end` is in bounds, so that an instruction accessing several
+    /// bytes fails before it changes anything.
+    fn check_memory_range(&self, start: usize, end: usize) -> Result<()> {
+        let (address, pc) = (start.max(self.ram.len()), self.pc - 2);
+        ensure!(end < self.ram.len(), MemoryOutOfBoundsSnafu { address, pc });
+        Ok(())
+    }
+
+    /// Writes a byte to memory for the instruction that has just been fetched.
+    fn write_memory(&mut self, address: usize, value: u8) -> Result<()> {
+        let pc = self.pc - 2;
+        if address < PROGRAM_SPACE.start {
+            match self.quirks.reserved_memory_writes {
+                ReservedMemoryWrites::Allow => (),
+                ReservedMemoryWrites::Ignore => return Ok(()),
+                ReservedMemoryWrites::Error => ReservedMemoryWriteSnafu { address, pc }.fail()?,
+            }
+        }
+        *self.ram.get_mut(address).context(MemoryOutOfBoundsSnafu { address, pc })? = value;
+        Ok(())
+    }
+
+    /// Sets the program counter to `target` for the jump or call that has just been fetched,
+    /// following `Quirks::odd_jumps` if `target` is odd.
+    fn jump(&mut self, target: usize) -> Result<()> {
+        self.pc = if target & 1 == 0 {
+            target
+        } else {
+            match self.quirks.odd_jumps {
+                OddJumps::Allow => target,
+                OddJumps::RoundDown => target - 1,
+                OddJumps::Error => OddJumpSnafu { target, pc: self.pc - 2 }.fail()?,
+            }
+        };
+        Ok(())
+    }
+
+    /// Executes the instruction that has just been fetched, returning it decoded, or `None` if it
+    /// was not well-formed and skipped.
     #[allow(clippy::cognitive_complexity)]
-    fn execute_instruction(&mut self, instruction: u16) -> Result<()> {
+    fn execute_instruction(&mut self, instruction: u16) -> Result<Option<Instruction>> {
         const F: usize = 0xF;
-        match instruction & 0xF000 {
-            0x0000 => match instruction & 0x0FFF {
-                0x00E0 => {
-                    // 00E0 (clear the screen)
-                    self.screen.clear();
+        let Some(decoded) = Instruction::decode(instruction) else {
+            let pc = self.pc - 2;
+            match &mut self.malformed_instruction_policy {
+                MalformedInstructionPolicy::Error => {
+                    let state = self.cpu_state();
+                    return NotWellFormedInstructionSnafu { instruction, pc, state }.fail();
+                }
+                MalformedInstructionPolicy::Skip => (),
+                MalformedInstructionPolicy::Warn(callback) => callback(instruction, pc),
+            }
+            return Ok(None);
+        };
+        match decoded {
+            Instruction::ClearScreen => {
+                // 00E0 (clear the screen)
+                self.screen.clear();
+                self.emit(Event::ScreenChanged);
+            }
+            Instruction::Return => {
+                // 00EE (return)
+                if let Some(return_address) = self.call_stack.pop() {
+                    self.pc = return_address;
+                } else {
+                    CallStackUnderflowSnafu { address: self.pc - 2 }.fail()?;
+                }
+            }
+            Instruction::Syscall(nnn) => match self.unknown_syscall_policy {
+                // 0nnn (call the machine code routine at address nnn)
+                UnknownSyscallPolicy::Error => UnsupportedInstructionSnafu {
+                    instruction,
+                    mnemonic: decoded.to_string(),
+                    address: self.pc - 2,
+                    state: self.cpu_state(),
                 }
-                0x00EE => {
-                    // 00EE (return)
-                    if let Some(return_address) = self.call_stack.pop() {
-                        self.pc = return_address;
-                    } else {
-                        CallStackUnderflowSnafu { address: self.pc - 2 }.fail()?;
+                .fail()?,
+                UnknownSyscallPolicy::Ignore => (),
+                UnknownSyscallPolicy::Callback(_) => {
+                    let mut policy = mem::replace(
+                        &mut self.unknown_syscall_policy,
+                        UnknownSyscallPolicy::Ignore,
+                    );
+                    if let UnknownSyscallPolicy::Callback(callback) = &mut policy {
+                        callback(self, nnn);
                     }
+                    self.unknown_syscall_policy = policy;
                 }
-                _ => UnsupportedInstructionSnafu { instruction, address: self.pc - 2 }.fail()?,
             },
-            0x1000 => {
+            Instruction::Jump(nnn) => {
                 // 1nnn (jump to address nnn)
-                self.pc = usize::from(instruction & 0x0FFF);
+                self.jump(usize::from(nnn))?;
             }
-            0x2000 => {
+            Instruction::Call(nnn) => {
                 // 2nnn (call subroutine at address nnn)
+                if self.call_stack.len() >= self.quirks.max_call_stack_depth {
+                    CallStackOverflowSnafu { address: self.pc - 2 }.fail()?;
+                }
                 self.call_stack.push(self.pc);
-                self.pc = usize::from(instruction & 0x0FFF);
+                self.call_stack_high_water_mark =
+                    self.call_stack_high_water_mark.max(self.call_stack.len());
+                self.jump(usize::from(nnn))?;
             }
-            0x3000 => {
+            Instruction::SkipIfEqual { x, kk } => {
                 // 3xkk (skip the next instruction if Vx == kk)
-                let x = usize::from((instruction & 0x0F00) >> 8);
-                if self.v[x] == (instruction & 0x00FF) as u8 {
+                if self.v[usize::from(x)] == kk {
                     self.pc += 2;
                 }
             }
-            0x4000 => {
+            Instruction::SkipIfNotEqual { x, kk } => {
                 // 4xkk (skip the next instruction if Vx != kk)
-                let x = usize::from((instruction & 0x0F00) >> 8);
-                if self.v[x] != (instruction & 0x00FF) as u8 {
+                if self.v[usize::from(x)] != kk {
                     self.pc += 2;
                 }
             }
-            0x5000 => {
+            Instruction::SkipIfRegEqual { x, y } => {
                 // 5xy0 (skip the next instruction if Vx == Vy)
-                let x = usize::from((instruction & 0x0F00) >> 8);
-                let y = usize::from((instruction & 0x00F0) >> 4);
-                if self.v[x] == self.v[y] {
+                if self.v[usize::from(x)] == self.v[usize::from(y)] {
                     self.pc += 2;
                 }
             }
-            0x6000 => {
+            Instruction::Load { x, kk } => {
                 // 6xkk (Vx = kk)
-                let x = usize::from((instruction & 0x0F00) >> 8);
-                self.v[x] = (instruction & 0x00FF) as u8
+                self.v[usize::from(x)] = kk;
             }
-            0x7000 => {
+            Instruction::Add { x, kk } => {
                 // 7xkk (Vx = Vx + kk)
-                let x = usize::from((instruction & 0x0F00) >> 8);
-                self.v[x] = self.v[x].wrapping_add((instruction & 0x00FF) as u8);
-            }
-            0x8000 => {
-                let x = usize::from((instruction & 0x0F00) >> 8);
-                let y = usize::from((instruction & 0x00F0) >> 4);
-                match instruction & 0x000F {
-                    0x0000 => {
-                        // 8xy0 (Vx = Vy)
-                        self.v[x] = self.v[y];
-                    }
-                    0x0001 => {
-                        // 8xy1 (Vx = Vx | Vy)
-                        self.v[x] |= self.v[y];
-                    }
-                    0x0002 => {
-                        // 8xy2 (Vx = Vx & Vy)
-                        self.v[x] &= self.v[y];
-                    }
-                    0x0003 => {
-                        // 8xy3 (Vx = Vx ^ Vy)
-                        self.v[x] ^= self.v[y];
-                    }
-                    0x0004 => {
-                        // 8xy4 (Vx = Vx + Vy, VF = carry)
-                        let (result, carry) = self.v[x].overflowing_add(self.v[y]);
-                        self.v[x] = result;
-                        self.v[F] = carry as u8;
-                    }
-                    0x0005 => {
-                        // 8xy5 (Vx = Vx - Vy, VF = no borrow)
-                        let (result, borrow) = self.v[x].overflowing_sub(self.v[y]);
-                        self.v[x] = result;
-                        self.v[F] = !borrow as u8;
-                    }
-                    0x0006 => {
-                        // 8xy6
-                        if self.shift_quirks {
-                            // SCHIP: Vx = Vx >> 1, VF = carry
-                            self.v[F] = (self.v[x] & 0x01 != 0) as u8;
-                            self.v[x] >>= 1;
-                        } else {
-                            // CHIP-8: Vx = Vy >> 1, VF = carry
-                            self.v[F] = (self.v[y] & 0x01 != 0) as u8;
-                            self.v[x] = self.v[y] >> 1;
-                        }
-                    }
-                    0x0007 => {
-                        // 8xy7 (Vx = Vy - Vx, VF = no borrow)
-                        let (result, borrow) = self.v[y].overflowing_sub(self.v[x]);
-                        self.v[x] = result;
-                        self.v[F] = !borrow as u8;
-                    }
-                    0x000E => {
-                        // 8xyE
-                        if self.shift_quirks {
-                            // SCHIP: Vx = Vx << 1, VF = carry
-                            self.v[F] = (self.v[x] & 0x80 != 0) as u8;
-                            self.v[x] <<= 1;
-                        } else {
-                            // CHIP-8: Vx = Vy << 1, VF = carry
-                            self.v[F] = (self.v[y] & 0x80 != 0) as u8;
-                            self.v[x] = self.v[y] << 1;
-                        }
-                    }
-                    _ => NotWellFormedInstructionSnafu { instruction, pc: self.pc - 2 }.fail()?,
+                let x = usize::from(x);
+                self.v[x] = self.v[x].wrapping_add(kk);
+            }
+            Instruction::LoadReg { x, y } => {
+                // 8xy0 (Vx = Vy)
+                self.v[usize::from(x)] = self.v[usize::from(y)];
+            }
+            Instruction::Or { x, y } => {
+                // 8xy1 (Vx = Vx | Vy)
+                self.v[usize::from(x)] |= self.v[usize::from(y)];
+                if self.quirks.vf_reset {
+                    // CHIP-8: VF = 0
+                    self.v[F] = 0;
                 }
             }
-            0x9000 => {
-                let x = usize::from((instruction & 0x0F00) >> 8);
-                let y = usize::from((instruction & 0x00F0) >> 4);
-                match instruction & 0x000F {
-                    0x0000 => {
-                        // 9xy0 (skip the next instruction if Vx != Vy)
-                        if self.v[x] != self.v[y] {
-                            self.pc += 2;
-                        }
-                    }
-                    _ => NotWellFormedInstructionSnafu { instruction, pc: self.pc - 2 }.fail()?,
+            Instruction::And { x, y } => {
+                // 8xy2 (Vx = Vx & Vy)
+                self.v[usize::from(x)] &= self.v[usize::from(y)];
+                if self.quirks.vf_reset {
+                    // CHIP-8: VF = 0
+                    self.v[F] = 0;
+                }
+            }
+            Instruction::Xor { x, y } => {
+                // 8xy3 (Vx = Vx ^ Vy)
+                self.v[usize::from(x)] ^= self.v[usize::from(y)];
+                if self.quirks.vf_reset {
+                    // CHIP-8: VF = 0
+                    self.v[F] = 0;
+                }
+            }
+            Instruction::AddReg { x, y } => {
+                // 8xy4 (Vx = Vx + Vy, VF = carry)
+                let (x, y) = (usize::from(x), usize::from(y));
+                let (result, carry) = self.v[x].overflowing_add(self.v[y]);
+                self.v[x] = result;
+                self.v[F] = carry as u8;
+            }
+            Instruction::SubReg { x, y } => {
+                // 8xy5 (Vx = Vx - Vy, VF = no borrow)
+                let (x, y) = (usize::from(x), usize::from(y));
+                let (result, borrow) = self.v[x].overflowing_sub(self.v[y]);
+                self.v[x] = result;
+                self.v[F] = !borrow as u8;
+            }
+            Instruction::ShiftRight { x, y } => {
+                // 8xy6
+                let (x, y) = (usize::from(x), usize::from(y));
+                // VF is set last so that it holds the carry even if x is F.
+                let shifted = if self.quirks.shift {
+                    // SCHIP: Vx = Vx >> 1, VF = carry
+                    self.v[x]
+                } else {
+                    // CHIP-8: Vx = Vy >> 1, VF = carry
+                    self.v[y]
+                };
+                self.v[x] = shifted >> 1;
+                self.v[F] = (shifted & 0x01 != 0) as u8;
+            }
+            Instruction::SubN { x, y } => {
+                // 8xy7 (Vx = Vy - Vx, VF = no borrow)
+                let (x, y) = (usize::from(x), usize::from(y));
+                let (result, borrow) = self.v[y].overflowing_sub(self.v[x]);
+                self.v[x] = result;
+                self.v[F] = !borrow as u8;
+            }
+            Instruction::ShiftLeft { x, y } => {
+                // 8xyE
+                let (x, y) = (usize::from(x), usize::from(y));
+                // VF is set last so that it holds the carry even if x is F.
+                let shifted = if self.quirks.shift {
+                    // SCHIP: Vx = Vx << 1, VF = carry
+                    self.v[x]
+                } else {
+                    // CHIP-8: Vx = Vy << 1, VF = carry
+                    self.v[y]
+                };
+                self.v[x] = shifted << 1;
+                self.v[F] = (shifted & 0x80 != 0) as u8;
+            }
+            Instruction::SkipIfRegNotEqual { x, y } => {
+                // 9xy0 (skip the next instruction if Vx != Vy)
+                if self.v[usize::from(x)] != self.v[usize::from(y)] {
+                    self.pc += 2;
                 }
             }
-            0xA000 => {
+            Instruction::LoadI(nnn) => {
                 // Annn (I = nnn)
-                self.i = instruction & 0x0FFF;
+                self.i = nnn;
             }
-            0xB000 => {
-                // Bnnn (jump to address nnn + V0)
-                self.pc = usize::from(instruction & 0x0FFF) + usize::from(self.v[0]);
+            Instruction::JumpOffset(nnn) => {
+                if self.quirks.jump {
+                    // SCHIP: Bxnn (jump to address xnn + Vx)
+                    let x = usize::from(nnn >> 8);
+                    self.jump(usize::from(nnn) + usize::from(self.v[x]))?;
+                } else {
+                    // CHIP-8: Bnnn (jump to address nnn + V0)
+                    self.jump(usize::from(nnn) + usize::from(self.v[0]))?;
+                }
             }
-            0xC000 => {
+            Instruction::Random { x, kk } => {
                 // Cxkk (Vx = rand() & kk)
-                let x = usize::from((instruction & 0x0F00) >> 8);
-                self.v[x] = rand::random::<u8>() & ((instruction & 0x00FF) as u8);
+                self.v[usize::from(x)] = self.rng.random_byte() & kk;
             }
-            0xD000 => {
+            Instruction::Draw { x, y, n } => {
                 // Dxyn (draw a sprite at memory I..(I + n) at position (Vx, Vy), VF = collision)
-                let x = usize::from((instruction & 0x0F00) >> 8);
-                let vx = usize::from(self.v[x]) % SCREEN_WIDTH;
-                let y = usize::from((instruction & 0x00F0) >> 4);
-                let vy = usize::from(self.v[y]) % SCREEN_HEIGHT;
+                let (width, height) = (self.screen.width(), self.screen.height());
+                let vx = usize::from(self.v[usize::from(x)]) % width;
+                let vy = usize::from(self.v[usize::from(y)]) % height;
                 self.v[F] = 0;
-                for row in 0..(instruction & 0x000F) {
-                    let pixel_y = vy + usize::from(row);
-                    if pixel_y >= SCREEN_HEIGHT {
-                        break;
+                let mut changed = false;
+                for row in 0..n {
+                    let mut pixel_y = vy + usize::from(row);
+                    if pixel_y >= height {
+                        if self.quirks.clip_vertically {
+                            break;
+                        }
+                        pixel_y %= height;
                     }
+                    let sprite_row = self.read_memory(usize::from(self.i) + usize::from(row))?;
                     for col in 0..8u16 {
-                        let pixel_x = vx + usize::from(col);
-                        if pixel_x >= SCREEN_WIDTH {
-                            break;
+                        let mut pixel_x = vx + usize::from(col);
+                        if pixel_x >= width {
+                            if self.quirks.clip_horizontally {
+                                break;
+                            }
+                            pixel_x %= width;
                         }
-                        if self.ram[usize::from(self.i + row)] & (1 << (7 - col)) != 0 {
+                        if sprite_row & (1 << (7 - col)) != 0 {
                             let pixel = &mut self.screen[pixel_y][pixel_x];
                             if let Color::White = *pixel {
                                 self.v[F] = 1;
                             }
                             *pixel ^= Color::White;
+                            changed = true;
                         }
                     }
                 }
+                trace_event!(
+                    tracing::Level::DEBUG,
+                    x = vx,
+                    y = vy,
+                    height = n,
+                    collision = self.v[F],
+                    "draw"
+                );
+                if changed {
+                    self.emit(Event::ScreenChanged);
+                }
+                if self.quirks.display_wait {
+                    self.waiting_for_vblank = true;
+                }
             }
-            0xE000 => {
-                let x = usize::from((instruction & 0x0F00) >> 8);
-                match instruction & 0x00FF {
-                    0x009E => {
-                        // Ex9E (skip the next instruction if the key in Vx is pressed)
-                        if self.is_key_pressed[usize::from(self.v[x])] {
-                            self.pc += 2;
-                        }
-                    }
-                    0x00A1 => {
-                        // ExA1 (skip the next instruction if the key in Vx is not pressed)
-                        if !self.is_key_pressed[usize::from(self.v[x])] {
-                            self.pc += 2;
-                        }
-                    }
-                    _ => NotWellFormedInstructionSnafu { instruction, pc: self.pc - 2 }.fail()?,
+            Instruction::SkipIfKey { x } => {
+                // Ex9E (skip the next instruction if the key in Vx is pressed)
+                if self.is_key_pressed(self.v[usize::from(x)]) {
+                    self.pc += 2;
                 }
             }
-            0xF000 => {
-                let x = usize::from((instruction & 0x0F00) >> 8);
-                match instruction & 0x00FF {
-                    0x0007 => {
-                        // Fx07 (Vx = delay timer)
-                        self.v[x] = self.timers.delay_timer;
-                    }
-                    0x000A => {
-                        // Fx0A (Vx = a key press)
-                        if let Some(key) = self.is_key_pressed.iter().position(|&pressed| pressed) {
-                            self.v[x] = key as u8;
-                        } else {
-                            self.pc -= 2;
-                        }
-                    }
-                    0x0015 => {
-                        // Fx15 (delay timer = Vx)
-                        self.timers.delay_timer = self.v[x];
-                    }
-                    0x0018 => {
-                        // Fx18 (sound timer = Vx)
-                        self.timers.sound_timer = self.v[x];
-                    }
-                    0x001E => {
-                        // Fx1E (I = I + Vx)
-                        self.i += u16::from(self.v[x]);
-                    }
-                    0x0029 => {
-                        // Fx29 (I = the address of the sprite for the hexadecimal digit in Vx)
-                        self.i = u16::from(self.v[x] & 0x0F) * SIZE_OF_SPRITE_FOR_DIGIT;
-                    }
-                    0x0033 => {
-                        // Fx33 (store the BCD of Vx in memory I..=(I + 2))
-                        self.ram[usize::from(self.i)] = self.v[x] / 100;
-                        self.ram[usize::from(self.i + 1)] = self.v[x] / 10 % 10;
-                        self.ram[usize::from(self.i + 2)] = self.v[x] % 10;
-                    }
-                    0x0055 => {
-                        // Fx55
-                        // CHIP-8: save V0..=Vx to memory I..=(I + x), I = I + x + 1
-                        // SCHIP: save V0..=Vx to memory I..=(I + x)
-                        for offset in 0..=x {
-                            self.ram[usize::from(self.i + offset as u16)] = self.v[offset];
-                        }
-                        if !self.load_store_quirks {
-                            self.i += x as u16 + 1;
-                        }
-                    }
-                    0x0065 => {
-                        // Fx65
-                        // CHIP-8: load V0..=Vx from memory I..=(I + x), I = I + x + 1
-                        // SCHIP: load V0..=Vx from memory I..=(I + x)
-                        for offset in 0..=x {
-                            self.v[offset] = self.ram[usize::from(self.i + offset as u16)];
-                        }
-                        if !self.load_store_quirks {
-                            self.i += x as u16 + 1;
-                        }
+            Instruction::SkipIfNotKey { x } => {
+                // ExA1 (skip the next instruction if the key in Vx is not pressed)
+                if !self.is_key_pressed(self.v[usize::from(x)]) {
+                    self.pc += 2;
+                }
+            }
+            Instruction::LoadDelayTimer { x } => {
+                // Fx07 (Vx = delay timer)
+                self.v[usize::from(x)] = self.timers.delay_timer;
+            }
+            Instruction::WaitKey { x } => {
+                // Fx0A (Vx = a key pressed and then released)
+                if let KeyWait::Released(key) = self.key_wait {
+                    trace_event!(tracing::Level::DEBUG, key, "key wait ended");
+                    self.v[usize::from(x)] = key;
+                    self.key_wait = KeyWait::Idle;
+                } else {
+                    if let KeyWait::Idle = self.key_wait {
+                        trace_event!(tracing::Level::DEBUG, pc = self.pc - 2, "key wait started");
+                        self.key_wait = KeyWait::Press;
                     }
-                    _ => NotWellFormedInstructionSnafu { instruction, pc: self.pc - 2 }.fail()?,
+                    self.pc -= 2;
+                }
+            }
+            Instruction::SetDelayTimer { x } => {
+                // Fx15 (delay timer = Vx)
+                self.timers.delay_timer = self.v[usize::from(x)];
+            }
+            Instruction::SetSoundTimer { x } => {
+                // Fx18 (sound timer = Vx)
+                let beeping = self.timers.sound_timer > 0;
+                self.timers.sound_timer = self.v[usize::from(x)];
+                match (beeping, self.timers.sound_timer > 0) {
+                    (false, true) => self.emit(Event::SoundStarted),
+                    (true, false) => self.emit(Event::SoundStopped),
+                    _ => (),
+                }
+            }
+            Instruction::AddI { x } => {
+                // Fx1E (I = I + Vx)
+                let sum = u32::from(self.i) + u32::from(self.v[usize::from(x)]);
+                if self.quirks.index_overflow {
+                    // Amiga: VF = overflow
+                    self.v[F] = (sum > 0x0FFF) as u8;
+                }
+                self.i = sum as u16 & self.quirks.index_mask;
+            }
+            Instruction::LoadDigit { x } => {
+                // Fx29 (I = the address of the sprite for the hexadecimal digit in Vx)
+                self.i = u16::from(self.v[usize::from(x)] & 0x0F) * SIZE_OF_SPRITE_FOR_DIGIT;
+            }
+            Instruction::StoreBcd { x } => {
+                // Fx33 (store the BCD of Vx in memory I..=(I + 2))
+                let (i, vx) = (usize::from(self.i), self.v[usize::from(x)]);
+                self.check_memory_range(i, i + 2)?;
+                self.write_memory(i, vx / 100)?;
+                self.write_memory(i + 1, vx / 10 % 10)?;
+                self.write_memory(i + 2, vx % 10)?;
+            }
+            Instruction::Store { x } => {
+                // Fx55
+                // CHIP-8: save V0..=Vx to memory I..=(I + x), I = I + x + 1
+                // SCHIP: save V0..=Vx to memory I..=(I + x)
+                self.check_memory_range(usize::from(self.i), usize::from(self.i) + usize::from(x))?;
+                for offset in 0..=usize::from(x) {
+                    self.write_memory(usize::from(self.i) + offset, self.v[offset])?;
+                }
+                if !self.quirks.load_store {
+                    self.i = self.i.wrapping_add(u16::from(x) + 1) & self.quirks.index_mask;
+                }
+            }
+            Instruction::Restore { x } => {
+                // Fx65
+                // CHIP-8: load V0..=Vx from memory I..=(I + x), I = I + x + 1
+                // SCHIP: load V0..=Vx from memory I..=(I + x)
+                self.check_memory_range(usize::from(self.i), usize::from(self.i) + usize::from(x))?;
+                for offset in 0..=usize::from(x) {
+                    self.v[offset] = self.read_memory(usize::from(self.i) + offset)?;
+                }
+                if !self.quirks.load_store {
+                    self.i = self.i.wrapping_add(u16::from(x) + 1) & self.quirks.index_mask;
                 }
             }
-            _ => NotWellFormedInstructionSnafu { instruction, pc: self.pc - 2 }.fail()?,
         }
-        Ok(())
+        Ok(Some(decoded))
     }
 }
 
+/// The progress of Fx0A, which waits for a key to be pressed and then released.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum KeyWait {
+    Idle,
+    Press,
+    Release(u8),
+    Released(u8),
+}
+
 const SIZE_OF_SPRITE_FOR_DIGIT: u16 = 5;
 
 const SPRITES_FOR_DIGITS: [u8; 80] = [
@@ -426,25 +1779,37 @@ const SPRITES_FOR_DIGITS: [u8; 80] = [
     0xF0, 0x80, 0xF0, 0x80, 0x80, // F
 ];
 
+/// Returns the sprite that Fx29 points I to for the low nibble of `digit`: 5 rows of 4 pixels, in
+/// the high nibbles of the bytes.
+///
+/// ```
+/// assert_eq!(chip8::sprite_for_digit(0xC), [0xF0, 0x80, 0x80, 0x80, 0xF0]);
+/// ```
+pub fn sprite_for_digit(digit: u8) -> &'static [u8] {
+    let start = usize::from(digit & 0x0F) * usize::from(SIZE_OF_SPRITE_FOR_DIGIT);
+    &SPRITES_FOR_DIGITS[start..start + usize::from(SIZE_OF_SPRITE_FOR_DIGIT)]
+}
+
 fn load_sprites_for_digits(ram: &mut Vec<u8>) {
     debug_assert_eq!(ram.len(), 0);
     ram.extend(SPRITES_FOR_DIGITS.iter());
 }
 
-fn load_program<P: AsRef<Path>>(path: P, ram: &mut Vec<u8>) -> Result<()> {
+fn load_program(program: &[u8], ram: &mut Vec<u8>) -> Result<()> {
     debug_assert!(ram.len() <= PROGRAM_SPACE.start);
+    ensure!(program.len() <= PROGRAM_SPACE.len(), ProgramTooLargeSnafu { size: program.len() });
     ram.resize(PROGRAM_SPACE.start, 0);
-    let mut program = File::open(path).context(IoSnafu)?;
-    program.read_to_end(ram).context(IoSnafu)?;
-    debug_assert!(ram.len() <= PROGRAM_SPACE.end);
+    ram.extend_from_slice(program);
     ram.resize(PROGRAM_SPACE.end, 0);
     Ok(())
 }
 
-// 16,666,667 nanoseconds = 1 / 60 Hz.
+/// The time between two ticks of the timers at the default 60 Hz (16,666,667 nanoseconds); see
+/// `Chip8::timer_cycle` for the rate an interpreter actually runs at.
 pub const TIMER_CLOCK_CYCLE: Duration = Duration::from_nanos(16_666_667);
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Timers {
     delay_timer: u8,
     /// A sound timer.
@@ -452,6 +1817,16 @@ pub struct Timers {
 }
 
 impl Timers {
+    /// Returns the delay timer.
+    pub fn delay_timer(&self) -> u8 {
+        self.delay_timer
+    }
+
+    /// Sets the delay timer.
+    pub fn set_delay_timer(&mut self, delay_timer: u8) {
+        self.delay_timer = delay_timer;
+    }
+
     /// Decreases each timer by 1 if it is greater than zero.
     pub fn count_down(&mut self) {
         self.delay_timer = self.delay_timer.saturating_sub(1);
@@ -463,35 +1838,349 @@ impl Timers {
 pub const SCREEN_WIDTH: usize = 64;
 /// The height of a CHIP-8 screen.
 pub const SCREEN_HEIGHT: usize = 32;
+/// The width of the largest screen, that of SCHIP's high-resolution mode.
+pub const MAX_SCREEN_WIDTH: usize = 128;
+/// The height of the largest screen, that of SCHIP's high-resolution mode.
+pub const MAX_SCREEN_HEIGHT: usize = 64;
 
-/// A monochrome screen of `SCREEN_WIDTH` x `SCREEN_HEIGHT` pixels.
-#[derive(Copy, Clone)]
+/// A monochrome screen, of `SCREEN_WIDTH` x `SCREEN_HEIGHT` pixels by default and of at most
+/// `MAX_SCREEN_WIDTH` x `MAX_SCREEN_HEIGHT` pixels.
+#[derive(Copy, Clone, Eq, PartialEq)]
 pub struct Screen {
-    pixels: [Color; SCREEN_WIDTH * SCREEN_HEIGHT],
+    // The first `width * height` pixels, row by row, are those of the screen.
+    pixels: [Color; MAX_SCREEN_WIDTH * MAX_SCREEN_HEIGHT],
+    width: usize,
+    height: usize,
 }
 
 impl Screen {
+    /// Returns a black screen of `width` x `height` pixels, panicking if `try_with_size` returns
+    /// `None`.
+    pub fn with_size(width: usize, height: usize) -> Self {
+        Self::try_with_size(width, height)
+            .unwrap_or_else(|| panic!("a screen cannot be {width}x{height} pixels"))
+    }
+
+    /// Returns a black screen of `width` x `height` pixels, or `None` if it is empty, if it is
+    /// larger than `MAX_SCREEN_WIDTH` x `MAX_SCREEN_HEIGHT`, or if `width` is not a multiple of 8.
+    ///
+    /// ```
+    /// assert!(chip8::Screen::try_with_size(128, 64).is_some());
+    /// assert!(chip8::Screen::try_with_size(0, 32).is_none());
+    /// assert!(chip8::Screen::try_with_size(60, 32).is_none());
+    /// ```
+    pub fn try_with_size(width: usize, height: usize) -> Option<Self> {
+        let pixels = [Color::Black; MAX_SCREEN_WIDTH * MAX_SCREEN_HEIGHT];
+        ((1..=MAX_SCREEN_WIDTH).contains(&width)
+            && (1..=MAX_SCREEN_HEIGHT).contains(&height)
+            && width.is_multiple_of(8))
+        .then_some(Self { pixels, width, height })
+    }
+
+    /// Returns the number of columns of pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the number of rows of pixels.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the color of the pixel in column `x` and row `y`, or `None` if out of bounds.
+    pub fn get(&self, x: usize, y: usize) -> Option<Color> {
+        (x < self.width && y < self.height).then(|| self.pixels[y * self.width + x])
+    }
+
+    /// Sets the color of the pixel in column `x` and row `y`, panicking if out of bounds.
+    pub fn set(&mut self, x: usize, y: usize, color: Color) {
+        assert!(
+            x < self.width && y < self.height,
+            "the pixel ({x}, {y}) is out of the screen bounds"
+        );
+        self.pixels[y * self.width + x] = color;
+    }
+
+    /// Returns every pixel as `(x, y, color)`, row by row from the top-left corner.
+    ///
+    /// ```
+    /// let screen = chip8::Screen::default();
+    /// assert_eq!(screen.iter_pixels().count(), chip8::SCREEN_WIDTH * chip8::SCREEN_HEIGHT);
+    /// assert_eq!(screen.iter_pixels().nth(65), Some((1, 1, chip8::Color::Black)));
+    /// ```
+    pub fn iter_pixels(&self) -> impl Iterator<Item = (usize, usize, Color)> + '_ {
+        let width = self.width;
+        self.pixels()
+            .iter()
+            .enumerate()
+            .map(move |(index, &color)| (index % width, index / width, color))
+    }
+
+    /// Returns the rows of pixels from top to bottom, each from left to right.
+    pub fn rows(&self) -> impl Iterator<Item = &[Color]> + '_ {
+        self.pixels().chunks(self.width)
+    }
+
+    /// Returns the pixels with four bytes each (red, green, blue, and alpha), `foreground` for
+    /// white pixels and `background` for black ones, row by row from the top-left corner.
+    pub fn to_rgba8888(&self, foreground: [u8; 4], background: [u8; 4]) -> Vec<u8> {
+        self.encode(PixelLayout::Rgba8888 { foreground, background })
+    }
+
+    /// Returns the pixels with one bit each, set for white pixels, row by row from the top-left
+    /// corner. The leftmost pixel of each byte is its most significant bit.
+    pub fn to_packed_1bpp(&self) -> Vec<u8> {
+        self.encode(PixelLayout::Packed1bpp)
+    }
+
+    /// Returns the screen as text with one character per pixel, `O` for white and `.` for black,
+    /// and a newline after each row.
+    pub fn to_ascii(&self) -> String {
+        let mut text = String::with_capacity((self.width + 1) * self.height);
+        for row in self.rows() {
+            text.extend(row.iter().map(|&color| if color == Color::White { 'O' } else { '.' }));
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Returns the screen as text with one braille character per 2 x 4 pixels, a dot for each
+    /// white pixel, and a newline after each row of characters.
+    ///
+    /// ```
+    /// let mut screen = chip8::Screen::default();
+    /// screen.set(0, 0, chip8::Color::White);
+    /// screen.set(1, 3, chip8::Color::White);
+    /// assert!(screen.to_braille().starts_with("\u{2881}\u{2800}"));
+    /// assert_eq!(screen.to_braille().lines().count(), chip8::SCREEN_HEIGHT / 4);
+    /// ```
+    pub fn to_braille(&self) -> String {
+        // The dots of a braille character from the top-left one, column by column, as in Unicode.
+        const DOTS: [(usize, usize, u32); 8] = [
+            (0, 0, 0x01),
+            (0, 1, 0x02),
+            (0, 2, 0x04),
+            (1, 0, 0x08),
+            (1, 1, 0x10),
+            (1, 2, 0x20),
+            (0, 3, 0x40),
+            (1, 3, 0x80),
+        ];
+        let mut text = String::new();
+        for y in (0..self.height).step_by(4) {
+            for x in (0..self.width).step_by(2) {
+                let bits = DOTS
+                    .iter()
+                    .filter(|&&(dx, dy, _)| self.get(x + dx, y + dy) == Some(Color::White))
+                    .fold(0, |bits, &(_, _, bit)| bits | bit);
+                text.push(char::from_u32(0x2800 + bits).expect("braille patterns are characters"));
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Writes the screen as a binary PBM (portable bitmap) image, which most image viewers and
+    /// converters can read.
+    #[cfg(feature = "std")]
+    pub fn write_pbm<W: io::Write>(&self, writer: &mut W) -> Result<()> {
+        // In PBM, set bits are black.
+        let bits: Vec<u8> = self.to_packed_1bpp().iter().map(|byte| !byte).collect();
+        let header = alloc::format!("P4\n{} {}\n", self.width, self.height);
+        writer.write_all(header.as_bytes()).context(IoSnafu)?;
+        writer.write_all(&bits).context(IoSnafu)
+    }
+
+    /// Returns the screen as a grayscale image, 0 for black pixels and 255 for white ones, which
+    /// can be saved as PNG with `GrayImage::save`.
+    ///
+    /// ```
+    /// let mut screen = chip8::Screen::default();
+    /// screen.set(3, 1, chip8::Color::White);
+    /// let image = screen.to_image();
+    /// assert_eq!(image.dimensions(), (64, 32));
+    /// assert_eq!(image.get_pixel(3, 1).0, [255]);
+    /// ```
+    #[cfg(feature = "image")]
+    pub fn to_image(&self) -> image::GrayImage {
+        let width = u32::try_from(self.width).expect("the screen width fits in u32");
+        let height = u32::try_from(self.height).expect("the screen height fits in u32");
+        image::GrayImage::from_raw(width, height, self.as_ref().to_vec())
+            .expect("the screen has one byte per pixel")
+    }
+
+    /// Returns how many bytes the pixels take in `layout`.
+    pub fn len_in(&self, layout: PixelLayout) -> usize {
+        let pixels = self.width * self.height;
+        match layout {
+            PixelLayout::Rgb332 => pixels,
+            PixelLayout::Rgba8888 { .. } => pixels * 4,
+            PixelLayout::Packed1bpp => pixels / 8,
+        }
+    }
+
+    /// Writes the pixels into the start of `buffer` in `layout`, failing if `buffer` is shorter
+    /// than `len_in(layout)`.
+    ///
+    /// ```
+    /// # fn main() -> Result<(), chip8::Error> {
+    /// use chip8::{PixelLayout, Screen};
+    ///
+    /// let mut buffer = [0; 8 * chip8::SCREEN_HEIGHT];
+    /// Screen::default().write_into(&mut buffer, PixelLayout::Packed1bpp)?;
+    /// assert!(Screen::default().write_into(&mut buffer, PixelLayout::Rgb332).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn write_into(&self, buffer: &mut [u8], layout: PixelLayout) -> Result<()> {
+        let required = self.len_in(layout);
+        ensure!(buffer.len() >= required, BufferTooSmallSnafu { len: buffer.len(), required });
+        match layout {
+            PixelLayout::Rgb332 => buffer[..required].copy_from_slice(self.as_ref()),
+            PixelLayout::Rgba8888 { foreground, background } => {
+                for (bytes, &color) in buffer.chunks_exact_mut(4).zip(self.pixels()) {
+                    bytes.copy_from_slice(match color {
+                        Color::Black => &background,
+                        Color::White => &foreground,
+                    });
+                }
+            }
+            PixelLayout::Packed1bpp => {
+                for (byte, pixels) in buffer.iter_mut().zip(self.pixels().chunks(8)) {
+                    *byte = pixels.iter().fold(0, |byte, &pixel| byte << 1 | pixel as u8 & 1);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn encode(&self, layout: PixelLayout) -> Vec<u8> {
+        let mut buffer = vec![0; self.len_in(layout)];
+        self.write_into(&mut buffer, layout).expect("the buffer fits the layout");
+        buffer
+    }
+
+    /// Returns the pixels of the screen, row by row.
+    fn pixels(&self) -> &[Color] {
+        &self.pixels[..(self.width * self.height)]
+    }
+
+    fn pixels_mut(&mut self) -> &mut [Color] {
+        &mut self.pixels[..(self.width * self.height)]
+    }
+
     fn clear(&mut self) {
-        self.pixels.iter_mut().for_each(|pixel| *pixel = Color::Black);
+        self.pixels_mut().fill(Color::Black);
     }
 }
 
+/// How `Screen::write_into` lays out pixels in bytes, row by row from the top-left corner.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PixelLayout {
+    /// One byte per pixel in the RGB332 format, 0x00 for black and 0xFF for white (see
+    /// `Screen::as_ref`).
+    Rgb332,
+    /// Four bytes per pixel (red, green, blue, and alpha), `foreground` for white pixels and
+    /// `background` for black ones.
+    Rgba8888 { foreground: [u8; 4], background: [u8; 4] },
+    /// One bit per pixel, set for white pixels, the leftmost pixel in the most significant bit.
+    Packed1bpp,
+}
+
 impl Default for Screen {
-    /// Creates a black screen.
+    /// Creates a black screen of `SCREEN_WIDTH` x `SCREEN_HEIGHT` pixels.
     fn default() -> Self {
-        Self { pixels: [Color::Black; SCREEN_WIDTH * SCREEN_HEIGHT] }
+        Self::with_size(SCREEN_WIDTH, SCREEN_HEIGHT)
     }
 }
 
-impl Debug for Screen {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        for y in 0..SCREEN_HEIGHT {
-            for x in 0..SCREEN_WIDTH {
-                f.write_str(if let Color::White = self[y][x] { "O" } else { "." })?;
+#[cfg(feature = "serde")]
+impl serde::Serialize for Screen {
+    /// Serializes the size of the screen and its pixels as bytes in the format of `as_ref`.
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        struct Bytes<'a>(&'a [u8]);
+
+        impl serde::Serialize for Bytes<'_> {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_bytes(self.0)
             }
-            f.write_str("\n")?;
         }
-        Ok(())
+
+        let mut state = serializer.serialize_struct("Screen", 3)?;
+        state.serialize_field("width", &self.width)?;
+        state.serialize_field("height", &self.height)?;
+        state.serialize_field("pixels", &Bytes(self.as_ref()))?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Screen {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::{Error, Unexpected};
+
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "Screen")]
+        struct Fields {
+            width: usize,
+            height: usize,
+            pixels: Vec<u8>,
+        }
+
+        let Fields { width, height, pixels } = Fields::deserialize(deserializer)?;
+        let mut screen = Screen::try_with_size(width, height).ok_or_else(|| {
+            D::Error::custom(format_args!("a screen cannot be {width}x{height} pixels"))
+        })?;
+        if pixels.len() != width * height {
+            return Err(D::Error::invalid_length(pixels.len(), &"one byte per pixel"));
+        }
+        for (pixel, &byte) in screen.pixels_mut().iter_mut().zip(&pixels) {
+            *pixel = match byte {
+                0x00 => Color::Black,
+                0xFF => Color::White,
+                _ => {
+                    let unexpected = Unexpected::Unsigned(byte.into());
+                    return Err(D::Error::invalid_value(unexpected, &"0x00 or 0xFF"));
+                }
+            };
+        }
+        Ok(screen)
+    }
+}
+
+#[cfg(feature = "embedded-graphics")]
+impl embedded_graphics_core::geometry::OriginDimensions for Screen {
+    fn size(&self) -> embedded_graphics_core::geometry::Size {
+        embedded_graphics_core::geometry::Size::new(self.width as u32, self.height as u32)
+    }
+}
+
+#[cfg(feature = "embedded-graphics")]
+impl embedded_graphics_core::Drawable for Screen {
+    type Color = embedded_graphics_core::pixelcolor::BinaryColor;
+    type Output = ();
+
+    /// Draws every pixel with its top-left corner at the origin of `target`, white pixels being
+    /// `BinaryColor::On`.
+    fn draw<D>(&self, target: &mut D) -> Result<Self::Output, D::Error>
+    where
+        D: embedded_graphics_core::draw_target::DrawTarget<Color = Self::Color>,
+    {
+        use embedded_graphics_core::{geometry::Point, Pixel};
+
+        target.draw_iter(
+            self.iter_pixels()
+                .map(|(x, y, color)| Pixel(Point::new(x as i32, y as i32), color.into())),
+        )
+    }
+}
+
+impl Debug for Screen {
+    /// Formats the screen in braille (see `to_braille`).
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_braille())
     }
 }
 
@@ -501,42 +2190,54 @@ impl Index<usize> for Screen {
 
     /// Returns a shared reference to the `y`-th row of pixels, panicking if out of bounds.
     fn index(&self, y: usize) -> &Self::Output {
-        let start = y * SCREEN_WIDTH;
-        &self.pixels[start..(start + SCREEN_WIDTH)]
+        let start = y * self.width;
+        &self.pixels()[start..(start + self.width)]
     }
 }
 
 impl IndexMut<usize> for Screen {
     /// Returns a mutable reference to the `y`-th row of pixels, panicking if out of bounds.
     fn index_mut(&mut self, y: usize) -> &mut Self::Output {
-        let start = y * SCREEN_WIDTH;
-        &mut self.pixels[start..(start + SCREEN_WIDTH)]
+        let start = y * self.width;
+        let width = self.width;
+        &mut self.pixels_mut()[start..(start + width)]
     }
 }
 
 impl AsRef<[u8]> for Screen {
     /// Returns the raw pixel data in the sdl2::pixels::PixelFormatEnum::RGB332 format.
     fn as_ref(&self) -> &[u8] {
-        unsafe { &*(&self.pixels as *const [Color] as *const [u8]) }
+        bytemuck::cast_slice(self.pixels())
     }
 }
 
 impl BitOrAssign<&Screen> for Screen {
     /// Performs the `|=` operation pixelwise.
     fn bitor_assign(&mut self, other: &Screen) {
-        (self.pixels.iter_mut()).zip(other.pixels.iter()).for_each(|(pixel1, pixel2)| {
+        (self.pixels_mut().iter_mut()).zip(other.pixels()).for_each(|(pixel1, pixel2)| {
             *pixel1 |= pixel2;
         });
     }
 }
 
-#[derive(Clone, Copy)]
+/// The color of a pixel.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, bytemuck::NoUninit)]
 #[repr(u8)]
 pub enum Color {
     Black = 0x00,
     White = 0xFF,
 }
 
+#[cfg(feature = "embedded-graphics")]
+impl From<Color> for embedded_graphics_core::pixelcolor::BinaryColor {
+    fn from(color: Color) -> Self {
+        match color {
+            Color::Black => Self::Off,
+            Color::White => Self::On,
+        }
+    }
+}
+
 impl BitOrAssign<&Color> for Color {
     /// Assgins `White` if either `self` or `other` is `White`, otherwise assigns `Black`.
     fn bitor_assign(&mut self, other: &Color) {
@@ -2,37 +2,93 @@
 
 use std::{
     f32,
-    path::PathBuf,
+    fs::{self, File},
+    io::{self, BufWriter},
+    ops::Range,
+    path::{Path, PathBuf},
     process,
+    sync::{Arc, Mutex},
     time::{Duration, Instant},
 };
 
-use clap::Parser;
+use clap::{Args, Parser, Subcommand};
 
-use log::{debug, info};
+use log::{debug, error, info, trace, warn};
 
 use sdl2::{
     audio::{AudioCallback, AudioDevice, AudioSpec, AudioSpecDesired},
     event::Event,
-    keyboard::Scancode,
+    keyboard::{Mod, Scancode},
     pixels::{Color, PixelFormatEnum},
+    rect::Rect,
     render::{Canvas, Texture, TextureAccess, TextureCreator},
-    video::{Window, WindowContext},
+    video::{FullscreenType, Window, WindowContext},
     EventPump,
 };
 
-use snafu::{ErrorCompat, ResultExt, Snafu};
+use snafu::{Backtrace, ErrorCompat, ResultExt, Snafu};
 
 use spin_sleep_util::MissedTickBehavior;
 
 use strum::VariantNames;
 use strum_macros::{EnumString, EnumVariantNames};
 
-use chip8::Screen;
+use chip8::{
+    cheat::{self, CheatMode},
+    coverage::Coverage,
+    disasm::Item,
+    frontend::{AudioSink, DisplaySink, InputSource},
+    post::{BeepGate, Phosphor},
+    profiler::Profiler,
+    rewind::RewindBuffer,
+    rom_db::RomInfo,
+    trace_export::TraceExporter,
+};
+
+use crate::{
+    achievements::Achievements,
+    controller::{ButtonBinding, Controllers},
+    high_scores::HighScores,
+    keymap::{KeyBinding, KeyMap},
+    slots::SaveSlots,
+    touch::TouchKeypad,
+    watch::Watcher,
+};
+
+mod achievements;
+mod controller;
+mod crash;
+mod dedupe;
+mod diagnose;
+mod high_scores;
+mod keymap;
+mod slots;
+mod touch;
+#[cfg(feature = "test-vectors")]
+mod vectors;
+mod watch;
 
 const WINDOW_WIDTH: u32 = chip8::SCREEN_WIDTH as u32 * 10;
 const WINDOW_HEIGHT: u32 = chip8::SCREEN_HEIGHT as u32 * 10;
 
+/// How long a notification stays in the title bar of the window.
+const NOTIFICATION_DURATION: Duration = Duration::from_secs(3);
+
+/// How many instructions per second + and - add to and take from the CPU speed.
+const CPU_SPEED_STEP: u32 = 100;
+
+/// How many times as fast as normal the interpreter runs while Tab is held.
+const TURBO_SPEED: u32 = 8;
+
+/// How many frames back Backspace can rewind, one snapshot per frame.
+const REWIND_FRAMES: usize = 600;
+
+/// The color of the sign shown over the screen while the interpreter is paused.
+const PAUSE_SIGN_COLOR: Color = Color::RGB(0xFF, 0xA0, 0x00);
+
+/// How many of the last instructions executed are logged when the interpreter fails.
+const TRACE_LEN: usize = 16;
+
 #[derive(Debug, Snafu)]
 enum Error {
     #[snafu(display("{source}"))]
@@ -41,8 +97,21 @@ enum Error {
         source: chip8::Error,
     },
 
+    #[snafu(display("{}:{line}: {reason}", path.display()))]
+    InvalidAchievement { path: PathBuf, line: usize, reason: String },
+
+    #[snafu(display("{}:{line}: {reason}", path.display()))]
+    InvalidButtonProfile { path: PathBuf, line: usize, reason: String },
+
+    #[snafu(display("{source}"))]
+    Io { source: io::Error, backtrace: Backtrace },
+
     #[snafu(display("{source}"))]
     Sdl { source: Box<dyn std::error::Error> },
+
+    #[cfg(feature = "test-vectors")]
+    #[snafu(display("{failed} of {total} test vectors failed"))]
+    TestVectorsFailed { failed: usize, total: usize },
 }
 
 impl From<String> for Error {
@@ -51,6 +120,12 @@ impl From<String> for Error {
     }
 }
 
+impl From<std::ffi::NulError> for Error {
+    fn from(error: std::ffi::NulError) -> Self {
+        Self::Sdl { source: error.into() }
+    }
+}
+
 impl From<sdl2::IntegerOrSdlError> for Error {
     fn from(error: sdl2::IntegerOrSdlError) -> Self {
         Self::Sdl { source: error.into() }
@@ -78,23 +153,165 @@ impl From<sdl2::video::WindowBuildError> for Error {
 type Result<T, E = Error> = std::result::Result<T, E>;
 
 #[derive(Debug, Parser)]
-#[command(about, author, version)]
+#[command(
+    about,
+    author,
+    version,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 struct Opt {
-    /// Sets how many CHIP-8 instructions will be executed per second
-    #[arg(long = "cpu-speed", value_name = "CPU-SPEED", default_value = "700")]
-    cpu_speed: u32,
+    #[command(subcommand)]
+    command: Option<Command>,
 
-    /// Increases I by X + 1 for FX55/FX65, emulating the original CHIP-8
-    #[arg(long = "no-load-store-quirks", action = clap::ArgAction::SetFalse)]
-    load_store_quirks: bool,
+    /// Sets an achievements file [default: the ROM file with the `achievements` extension]
+    #[arg(long, value_name = "ACHIEVEMENTS-FILE")]
+    achievements: Option<PathBuf>,
 
-    /// Sets a ROM file to run
-    #[arg(name = "ROM-FILE")]
-    rom_file: PathBuf,
+    /// Maps game controller buttons to CHIP-8 keys, by their SDL names (e.g. a=5,leftshoulder=4),
+    /// after the ROM file with the `buttons` extension, if it exists
+    #[arg(
+        long,
+        value_name = "BUTTON=KEY",
+        value_delimiter = ',',
+        value_parser = controller::parse_button_binding
+    )]
+    buttons: Vec<ButtonBinding>,
 
-    /// Shifts VY (not VX) for 8XY6/8XYE, emulating the original CHIP-8
-    #[arg(long = "no-shift-quirks", action = clap::ArgAction::SetFalse)]
-    shift_quirks: bool,
+    /// Sets a cheat file [default: the ROM file with the `cheats` extension]
+    #[arg(long, value_name = "CHEAT-FILE")]
+    cheats: Option<PathBuf>,
+
+    /// Writes the ranges of addresses executed during the session to a file on exit
+    #[arg(long, value_name = "COVERAGE-FILE")]
+    coverage: Option<PathBuf>,
+
+    /// Sets how many CHIP-8 instructions will be executed per second [default: what the ROM
+    /// database recommends, or 700]
+    #[arg(long = "cpu-speed", value_name = "CPU-SPEED")]
+    cpu_speed: Option<u32>,
+
+    /// Prints a report on the video, audio, and timing of this platform instead of running a ROM
+    #[arg(long, exclusive = true)]
+    diagnose: bool,
+
+    /// Starts in fullscreen, which F11 or Alt+Enter toggles
+    #[arg(long, conflicts_with = "no_video")]
+    fullscreen: bool,
+
+    /// Sets how fullscreen takes over the display: as a borderless window the size of the desktop,
+    /// or by changing its video mode
+    #[arg(
+        long = "fullscreen-mode",
+        value_parser = clap::builder::PossibleValuesParser::new(FullscreenMode::VARIANTS),
+        ignore_case(true),
+        default_value_t)]
+    fullscreen_mode: FullscreenMode,
+
+    /// With --watch, patches the changed bytes of the ROM into memory instead of restarting
+    #[arg(long = "hot-reload", requires = "watch")]
+    hot_reload: bool,
+
+    /// Ignores 0NNN (machine code routine calls) other than 00E0/00EE instead of stopping
+    #[arg(long = "ignore-unknown-syscalls")]
+    ignore_unknown_syscalls: bool,
+
+    /// Skips instructions that are not well-formed, with a warning, instead of stopping
+    #[arg(long = "skip-malformed-instructions")]
+    skip_malformed_instructions: bool,
+
+    /// Maps PC keys by the characters they type in the keyboard layout (SDL keycodes), not by their
+    /// positions
+    #[arg(long)]
+    keycodes: bool,
+
+    /// Adds PC keys to type CHIP-8 keys with, by their SDL names (e.g. 5=Up,7=Left,9=Right,0=Space)
+    #[arg(
+        long,
+        value_name = "KEY=NAME",
+        value_delimiter = ',',
+        value_parser = keymap::parse_key_binding
+    )]
+    map: Vec<KeyBinding>,
+
+    /// Keeps each beep on for at least this many milliseconds, however low the sound timer is set
+    #[arg(long = "min-beep", value_name = "MILLISECONDS", default_value = "50")]
+    min_beep: u32,
+
+    /// Keeps each key pressed for at least this many milliseconds, however briefly it is tapped
+    #[arg(long = "min-key-hold", value_name = "MILLISECONDS", default_value = "0")]
+    min_key_hold: u32,
+
+    /// Ignores the quirks and the CPU speed recommended for known ROMs
+    #[arg(long = "no-rom-database")]
+    no_rom_database: bool,
+
+    /// Runs without initializing the audio subsystem
+    #[arg(long = "no-audio")]
+    no_audio: bool,
+
+    /// Runs without a window, only beeping (keyboard input is unavailable, so press Ctrl+C to quit)
+    #[arg(long = "no-video", conflicts_with = "no_audio")]
+    no_video: bool,
+
+    /// Prints how many times each opcode and the most executed addresses were executed on exit
+    #[arg(long)]
+    profile: bool,
+
+    #[command(flatten)]
+    quirks: QuirksOpt,
+
+    /// Sets a ROM file to run, or an Octo source file (`.8o`) to assemble and run
+    #[arg(name = "ROM-FILE", required = true)]
+    rom_file: Option<PathBuf>,
+
+    /// Sets where the score is in memory (e.g. "bcd 0x3A0 3") [default: read from the ROM file with
+    /// the `score` extension]
+    #[arg(long = "score-location", value_name = "LOCATION")]
+    score_location: Option<String>,
+
+    /// Seeds the random number generator for CXKK, making runs reproducible
+    #[arg(long, value_name = "SEED")]
+    seed: Option<u64>,
+
+    /// Makes each key toggle between pressed and released when tapped, instead of being held
+    #[arg(long = "sticky-keys")]
+    sticky_keys: bool,
+
+    /// Sets how many times per second the timers count down and the screen is refreshed (e.g. 50
+    /// for PAL)
+    #[arg(long = "timer-frequency", value_name = "HZ", default_value = "60")]
+    timer_frequency: u32,
+
+    /// Shows an on-screen keypad for touchscreens, which is shown anyway if one is present, and
+    /// makes mouse clicks count as touches
+    #[arg(long, conflicts_with = "no_video")]
+    touch: bool,
+
+    /// Writes a line for each instruction executed to a file
+    #[arg(long = "trace-file", value_name = "TRACE-FILE")]
+    trace_file: Option<PathBuf>,
+
+    /// Sets the format of the trace file
+    #[arg(
+        long = "trace-format",
+        value_parser = clap::builder::PossibleValuesParser::new(TraceFormat::VARIANTS),
+        ignore_case(true),
+        default_value_t)]
+    trace_format: TraceFormat,
+
+    /// Traces only the instructions at addresses in START..END (e.g. 0x200..0x300), which can be
+    /// given more than once
+    #[arg(long = "trace-range", value_name = "START..END", value_parser = parse_address_range)]
+    trace_range: Vec<Range<usize>>,
+
+    /// Paces instructions by how long they took on the COSMAC VIP instead of by --cpu-speed
+    #[arg(long = "vip-timing")]
+    vip_timing: bool,
+
+    /// Reloads the ROM file (reassembling Octo source files) and restarts whenever it changes
+    #[arg(long)]
+    watch: bool,
 
     /// Sets the waveform of the beep
     #[arg(
@@ -105,6 +322,269 @@ struct Opt {
     waveform: Waveform,
 }
 
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Assembles a program written with the mnemonics that `disasm` prints, or in Octo, into a ROM
+    Asm {
+        /// Sets a source file to assemble, which is Octo if its extension is `8o`
+        #[arg(name = "SOURCE-FILE")]
+        source_file: PathBuf,
+
+        /// Sets a ROM file to write
+        #[arg(name = "ROM-FILE")]
+        rom_file: PathBuf,
+    },
+
+    /// Checks the interpreter against single-step test vectors in JSON
+    #[cfg(feature = "test-vectors")]
+    CheckVectors(vectors::CheckOpt),
+
+    /// Runs a ROM with the quirks given and with a preset of quirks in lockstep, without a window,
+    /// and reports the first instruction after which they differ
+    Compare {
+        /// Sets the preset of quirks to compare with
+        #[arg(
+            long,
+            value_parser = clap::builder::PossibleValuesParser::new(QuirksPreset::VARIANTS),
+            ignore_case(true))]
+        against: QuirksPreset,
+
+        /// Sets how many frames (1/60 seconds) to run for at most
+        #[arg(long, value_name = "FRAMES", default_value = "3600")]
+        frames: u32,
+
+        /// Sets how many instructions are executed per frame
+        #[arg(long = "instructions-per-frame", value_name = "COUNT", default_value = "12")]
+        instructions_per_frame: u32,
+
+        #[command(flatten)]
+        quirks: QuirksOpt,
+
+        /// Seeds the random number generator for CXKK of both runs
+        #[arg(long, value_name = "SEED", default_value = "0")]
+        seed: u64,
+
+        /// Sets a ROM file to run
+        #[arg(name = "ROM-FILE")]
+        rom_file: PathBuf,
+    },
+
+    /// Runs every ROM in a directory without a window and reports likely duplicates or variants
+    Dedupe(dedupe::Opt),
+
+    /// Prints the instructions of a ROM, treating bytes that are never executed as data
+    Disasm {
+        /// Sets a ROM file to disassemble
+        #[arg(name = "ROM-FILE")]
+        rom_file: PathBuf,
+
+        /// Marks the subroutines and lists the instructions referring to each address
+        #[arg(long = "xrefs")]
+        xrefs: bool,
+
+        /// Prints Octo source, which the asm subcommand can assemble back into the ROM
+        #[arg(long = "octo", conflicts_with = "xrefs")]
+        octo: bool,
+    },
+
+    /// Writes single-step test vectors in JSON for each opcode, from random initial states
+    #[cfg(feature = "test-vectors")]
+    GenVectors(vectors::GenerateOpt),
+}
+
+#[derive(Debug, Args)]
+struct QuirksOpt {
+    /// Waits for the vertical blank after DXYN, emulating the original CHIP-8
+    #[arg(long = "display-wait")]
+    display_wait: bool,
+
+    /// Wraps sprites around the left and right edges of the screen instead of clipping them
+    #[arg(long = "no-horizontal-clip-quirks")]
+    no_horizontal_clip_quirks: bool,
+
+    /// Limits I to 12 bits for FX1E/FX55/FX65, emulating the original CHIP-8
+    #[arg(long = "index-12-bit")]
+    index_12_bit: bool,
+
+    /// Sets VF to 1 if I + VX exceeds 0xFFF for FX1E, emulating the Amiga CHIP-8 interpreter
+    #[arg(long = "index-overflow")]
+    index_overflow: bool,
+
+    /// Jumps to XNN + VX (not NNN + V0) for BXNN, emulating SCHIP
+    #[arg(long = "jump-quirks")]
+    jump_quirks: bool,
+
+    /// Keeps each key press visible to the program for at least this many instructions
+    #[arg(long = "key-latch-cycles", value_name = "CYCLES", conflicts_with = "key_latch_frames")]
+    key_latch_cycles: Option<u32>,
+
+    /// Keeps each key press visible to the program for at least this many frames (timer ticks)
+    #[arg(long = "key-latch-frames", value_name = "FRAMES")]
+    key_latch_frames: Option<u32>,
+
+    /// Increases I by X + 1 for FX55/FX65, emulating the original CHIP-8
+    #[arg(long = "no-load-store-quirks")]
+    no_load_store_quirks: bool,
+
+    /// Sets the maximum number of nested subroutine calls [default: what the ROM database
+    /// recommends, or 16]
+    #[arg(long = "max-call-stack-depth", value_name = "DEPTH")]
+    max_call_stack_depth: Option<usize>,
+
+    /// Sets what to do with jumps and calls to odd addresses [default: allow]
+    #[arg(
+        long = "odd-jumps",
+        value_parser = clap::builder::PossibleValuesParser::new(OddJumps::VARIANTS),
+        ignore_case(true))]
+    odd_jumps: Option<OddJumps>,
+
+    /// Sets what to do with writes below 0x200, where the font resides [default: allow]
+    #[arg(
+        long = "reserved-memory-writes",
+        value_parser = clap::builder::PossibleValuesParser::new(ReservedMemoryWrites::VARIANTS),
+        ignore_case(true))]
+    reserved_memory_writes: Option<ReservedMemoryWrites>,
+
+    /// Shifts VY (not VX) for 8XY6/8XYE, emulating the original CHIP-8
+    #[arg(long = "no-shift-quirks")]
+    no_shift_quirks: bool,
+
+    /// Wraps sprites around the top and bottom edges of the screen instead of clipping them
+    #[arg(long = "no-vertical-clip-quirks")]
+    no_vertical_clip_quirks: bool,
+
+    /// Resets VF to 0 for 8XY1/8XY2/8XY3, emulating the original CHIP-8
+    #[arg(long = "vf-reset")]
+    vf_reset: bool,
+}
+
+impl QuirksOpt {
+    /// Returns `quirks` with the quirks that these options set, leaving the others as they are.
+    fn apply(&self, mut quirks: chip8::Quirks) -> chip8::Quirks {
+        quirks.shift &= !self.no_shift_quirks;
+        quirks.load_store &= !self.no_load_store_quirks;
+        quirks.vf_reset |= self.vf_reset;
+        quirks.clip_horizontally &= !self.no_horizontal_clip_quirks;
+        quirks.clip_vertically &= !self.no_vertical_clip_quirks;
+        quirks.display_wait |= self.display_wait;
+        quirks.jump |= self.jump_quirks;
+        quirks.index_overflow |= self.index_overflow;
+        if self.index_12_bit {
+            quirks.index_mask = 0x0FFF;
+        }
+        if let Some(depth) = self.max_call_stack_depth {
+            quirks.max_call_stack_depth = depth;
+        }
+        match (self.key_latch_cycles, self.key_latch_frames) {
+            (Some(cycles), _) => quirks.key_latch = chip8::KeyLatch::Cycles(cycles),
+            (None, Some(frames)) => quirks.key_latch = chip8::KeyLatch::Frames(frames),
+            (None, None) => (),
+        }
+        if let Some(reserved_memory_writes) = &self.reserved_memory_writes {
+            quirks.reserved_memory_writes = match reserved_memory_writes {
+                ReservedMemoryWrites::Allow => chip8::ReservedMemoryWrites::Allow,
+                ReservedMemoryWrites::Ignore => chip8::ReservedMemoryWrites::Ignore,
+                ReservedMemoryWrites::Error => chip8::ReservedMemoryWrites::Error,
+            };
+        }
+        if let Some(odd_jumps) = &self.odd_jumps {
+            quirks.odd_jumps = match odd_jumps {
+                OddJumps::Allow => chip8::OddJumps::Allow,
+                OddJumps::RoundDown => chip8::OddJumps::RoundDown,
+                OddJumps::Error => chip8::OddJumps::Error,
+            };
+        }
+        quirks
+    }
+}
+
+impl From<&QuirksOpt> for chip8::Quirks {
+    fn from(opt: &QuirksOpt) -> Self {
+        opt.apply(Self::DEFAULT)
+    }
+}
+
+#[derive(Clone, Debug, Default, strum_macros::Display, EnumString, EnumVariantNames)]
+#[strum(serialize_all = "kebab_case")]
+enum ReservedMemoryWrites {
+    #[default]
+    Allow,
+    Ignore,
+    Error,
+}
+
+#[derive(Clone, Debug, Default, strum_macros::Display, EnumString, EnumVariantNames)]
+#[strum(serialize_all = "kebab_case")]
+enum OddJumps {
+    #[default]
+    Allow,
+    RoundDown,
+    Error,
+}
+
+#[derive(Clone, Debug, strum_macros::Display, EnumString, EnumVariantNames)]
+#[strum(serialize_all = "kebab_case")]
+enum QuirksPreset {
+    Default,
+    CosmacVip,
+}
+
+impl From<&QuirksPreset> for chip8::Quirks {
+    fn from(preset: &QuirksPreset) -> Self {
+        match preset {
+            QuirksPreset::Default => Self::DEFAULT,
+            QuirksPreset::CosmacVip => Self::COSMAC_VIP,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, strum_macros::Display, EnumString, EnumVariantNames)]
+#[strum(serialize_all = "kebab_case")]
+enum FullscreenMode {
+    #[default]
+    Desktop,
+    Exclusive,
+}
+
+impl From<&FullscreenMode> for FullscreenType {
+    fn from(mode: &FullscreenMode) -> Self {
+        match mode {
+            FullscreenMode::Desktop => Self::Desktop,
+            FullscreenMode::Exclusive => Self::True,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, strum_macros::Display, EnumString, EnumVariantNames)]
+#[strum(serialize_all = "kebab_case")]
+enum TraceFormat {
+    #[default]
+    Text,
+    Jsonl,
+}
+
+impl From<&TraceFormat> for chip8::trace_export::TraceFormat {
+    fn from(format: &TraceFormat) -> Self {
+        match format {
+            TraceFormat::Text => Self::Text,
+            TraceFormat::Jsonl => Self::Jsonl,
+        }
+    }
+}
+
+/// Parses a range of addresses written as `START..END`, each in decimal or, with the `0x` prefix,
+/// in hexadecimal.
+fn parse_address_range(range: &str) -> std::result::Result<Range<usize>, String> {
+    let address = |address: &str| match address.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => address.parse(),
+    };
+    range
+        .split_once("..")
+        .and_then(|(start, end)| Some(address(start).ok()?..address(end).ok()?))
+        .ok_or_else(|| format!("expected START..END, not {range:?}"))
+}
+
 #[derive(Clone, Debug, Default, strum_macros::Display, EnumString, EnumVariantNames)]
 #[strum(serialize_all = "kebab_case")]
 enum Waveform {
@@ -116,7 +596,30 @@ enum Waveform {
 }
 
 fn main() {
-    if let Err(err) = run(Opt::parse()) {
+    let mut opt = Opt::parse();
+    let result = match opt.command.take() {
+        Some(Command::Asm { source_file, rom_file }) => assemble(&source_file, &rom_file),
+        #[cfg(feature = "test-vectors")]
+        Some(Command::CheckVectors(check_opt)) => vectors::check(&check_opt),
+        Some(Command::Compare {
+            against,
+            frames,
+            instructions_per_frame,
+            quirks,
+            seed,
+            rom_file,
+        }) => {
+            let quirks = (chip8::Quirks::from(&quirks), chip8::Quirks::from(&against));
+            compare(&rom_file, quirks, seed, instructions_per_frame, frames)
+        }
+        Some(Command::Dedupe(dedupe_opt)) => dedupe::run(&dedupe_opt),
+        Some(Command::Disasm { rom_file, xrefs, octo }) => disassemble(&rom_file, xrefs, octo),
+        #[cfg(feature = "test-vectors")]
+        Some(Command::GenVectors(generate_opt)) => vectors::generate(&generate_opt),
+        None if opt.diagnose => diagnose::run(),
+        None => run(opt),
+    };
+    if let Err(err) = result {
         eprintln!("Error: {err}");
         if let Some(backtrace) = ErrorCompat::backtrace(&err) {
             eprintln!("{backtrace}");
@@ -125,24 +628,466 @@ fn main() {
     }
 }
 
+fn assemble(source_file: &Path, rom_file: &Path) -> Result<()> {
+    let source = fs::read_to_string(source_file).context(IoSnafu)?;
+    let program = if is_octo(source_file) {
+        chip8::octo::assemble(&source)
+    } else {
+        chip8::asm::assemble(&source)
+    };
+    fs::write(rom_file, program.context(Chip8Snafu)?).context(IoSnafu)
+}
+
+/// Reads a ROM file, assembling it first if it is an Octo source file.
+fn read_rom(rom_file: &Path) -> Result<Vec<u8>> {
+    if is_octo(rom_file) {
+        let source = fs::read_to_string(rom_file).context(IoSnafu)?;
+        chip8::octo::assemble(&source).context(Chip8Snafu)
+    } else {
+        fs::read(rom_file).context(IoSnafu)
+    }
+}
+
+fn is_octo(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == "8o")
+}
+
+/// Runs `rom_file` with two sets of quirks in lockstep, printing where they first differ.
+fn compare(
+    rom_file: &Path,
+    (first, second): (chip8::Quirks, chip8::Quirks),
+    seed: u64,
+    instructions_per_frame: u32,
+    frames: u32,
+) -> Result<()> {
+    let rom = read_rom(rom_file)?;
+    let build = |quirks| {
+        chip8::Chip8::builder().rom(&rom).quirks(quirks).seed(seed).build().context(Chip8Snafu)
+    };
+    let (mut first, mut second) = (build(first)?, build(second)?);
+    match chip8::differential::find_divergence(
+        &mut first,
+        &mut second,
+        instructions_per_frame,
+        frames,
+    ) {
+        Some(divergence) => print!("{divergence}"),
+        None => println!("No difference in {frames} frames"),
+    }
+    Ok(())
+}
+
+fn disassemble(rom_file: &Path, xrefs: bool, octo: bool) -> Result<()> {
+    let program = fs::read(rom_file).context(IoSnafu)?;
+    if octo {
+        print!("{}", chip8::octo::decompile(&program));
+        return Ok(());
+    }
+    let analysis = chip8::analysis::analyze(&program);
+    for item in &analysis.items {
+        let address = match *item {
+            Item::Code { address, .. } | Item::Data { address, .. } => address,
+        };
+        if xrefs {
+            if analysis.subroutines.binary_search(&address).is_ok() {
+                println!("; subroutine");
+            }
+            let references: Vec<_> = analysis
+                .references_to(address)
+                .map(|reference| format!("{:#05X} ({:?})", reference.from, reference.kind))
+                .collect();
+            if !references.is_empty() {
+                println!("; referred to by {}", references.join(", "));
+            }
+        }
+        println!("{item}");
+    }
+    Ok(())
+}
+
 fn run(opt: Opt) -> Result<()> {
     env_logger::init();
 
     // Initialize SDL stuff.
 
+    if opt.touch {
+        sdl2::hint::set("SDL_MOUSE_TOUCH_EVENTS", "1");
+    }
     let sdl_context = sdl2::init()?;
 
+    let (canvas, keypad) = if opt.no_video {
+        (None, false)
+    } else {
+        let (canvas, keypad) = open_canvas(&sdl_context, opt.touch)?;
+        (Some(canvas), keypad)
+    };
+    let texture_creator = canvas.as_ref().map(|canvas| canvas.texture_creator());
+
+    let mut audio_device = if opt.no_audio {
+        None
+    } else {
+        match open_audio(&sdl_context, &opt.waveform) {
+            Ok(audio_device) => Some(Audio(audio_device)),
+            Err(err) => {
+                warn!("Audio is disabled: {err}");
+                None
+            }
+        }
+    };
+
+    let event_pump = sdl_context.event_pump()?;
+
+    // Run a CHIP-8 ROM image.
+
+    let rom_file = opt.rom_file.as_ref().expect("ROM-FILE is required without a subcommand");
+    let mut rom = read_rom(rom_file)?;
+    let mut quirks = chip8::Quirks::DEFAULT;
+    let mut cpu_speed = opt.cpu_speed;
+    let mut keys = "";
+    if let Some(info) = RomInfo::lookup(&rom).filter(|_| !opt.no_rom_database) {
+        info!("Recognized {}", info.title);
+        keys = info.keys;
+        quirks = info.quirks;
+        cpu_speed = cpu_speed.or(info.cpu_speed);
+    }
+    // Each quirk option given takes precedence over the database.
+    let quirks = opt.quirks.apply(quirks);
+    let cheats_file = opt.cheats.clone().unwrap_or_else(|| rom_file.with_extension("cheats"));
+    let cheats = if opt.cheats.is_some() || cheats_file.is_file() {
+        cheat::parse(&fs::read_to_string(&cheats_file).context(IoSnafu)?).context(Chip8Snafu)?
+    } else {
+        Vec::new()
+    };
+    let trace_exporter = opt
+        .trace_file
+        .as_ref()
+        .map(|trace_file| -> Result<_> {
+            let writer = BufWriter::new(File::create(trace_file).context(IoSnafu)?);
+            let mut exporter = TraceExporter::new(writer, (&opt.trace_format).into());
+            exporter.set_ranges(opt.trace_range.clone());
+            Ok(Arc::new(Mutex::new(exporter)))
+        })
+        .transpose()?;
+    // The quirks and the CPU speed of the ROM first loaded are kept when it is reloaded.
+    let start = |rom: &[u8]| -> Result<chip8::Chip8> {
+        let mut builder = chip8::Chip8::builder()
+            .rom(rom)
+            .quirks(quirks)
+            .cpu_speed(cpu_speed.unwrap_or(chip8::timing::DEFAULT_CPU_SPEED))
+            .timer_frequency(opt.timer_frequency)
+            .vip_timing(opt.vip_timing);
+        if let Some(seed) = opt.seed {
+            builder = builder.seed(seed);
+        }
+        if opt.ignore_unknown_syscalls {
+            builder = builder.unknown_syscall_policy(chip8::UnknownSyscallPolicy::Ignore);
+        }
+        if opt.skip_malformed_instructions {
+            builder = builder.malformed_instruction_policy(
+                chip8::MalformedInstructionPolicy::Warn(Box::new(|instruction, pc| {
+                    warn!("Skipped the malformed instruction {instruction:#06X} at {pc:#06X}")
+                })),
+            );
+        }
+        let mut chip8 = builder.build().context(Chip8Snafu)?;
+        chip8.set_trace_len(TRACE_LEN);
+        if let Some(trace_exporter) = &trace_exporter {
+            TraceExporter::install(trace_exporter, &mut chip8);
+        }
+        for cheat in &cheats {
+            chip8.apply_cheat(cheat).context(Chip8Snafu)?;
+        }
+        Ok(chip8)
+    };
+    let mut chip8 = start(&rom)?;
+    debug!("{:?}", chip8);
+    let mut cpu_speed = cpu_speed.unwrap_or(chip8::timing::DEFAULT_CPU_SPEED);
+    let mut watcher = opt.watch.then(|| Watcher::new(rom_file));
+    let achievements_file =
+        opt.achievements.clone().unwrap_or_else(|| rom_file.with_extension("achievements"));
+    let mut achievements = if opt.achievements.is_some() || achievements_file.is_file() {
+        Some(Achievements::load(&achievements_file)?)
+    } else {
+        None
+    };
+    let mut high_scores = HighScores::new(rom_file, opt.score_location.as_deref())?;
+    let mut save_slots = SaveSlots::new(rom_file);
+    let mut rewind = RewindBuffer::new(1, REWIND_FRAMES);
+    let mut notification_deadline = None;
+    let mut halted = false;
+    let mut paused = false;
+    let controllers = match sdl_context.game_controller() {
+        Ok(subsystem) => Some(Controllers::new(subsystem, rom_file, &opt.buttons)?),
+        Err(err) => {
+            warn!("Game controllers are disabled: {err}");
+            None
+        }
+    };
+    let mut keyboard = Keyboard::new(
+        event_pump,
+        KeyMap::new(&opt.map, opt.keycodes),
+        controllers,
+        keypad.then(TouchKeypad::new),
+        opt.sticky_keys,
+        Duration::from_millis(opt.min_key_hold.into()),
+    );
+    let mut updater = Updater::new();
+    if opt.coverage.is_some() {
+        updater.coverage = Some(Coverage::new());
+    }
+    if opt.profile {
+        updater.profiler = Some(Profiler::new());
+    }
+    let mut graphics = canvas
+        .zip(texture_creator.as_ref())
+        .map(|(canvas, texture_creator)| {
+            Graphics::new(canvas, texture_creator, keypad, (&opt.fullscreen_mode).into())
+        })
+        .transpose()?;
+    if let Some(graphics) = graphics.as_mut().filter(|_| opt.fullscreen) {
+        graphics.toggle_fullscreen()?;
+    }
+    if !keys.is_empty() {
+        notify(&mut graphics, &mut notification_deadline, &format!("Keys: {keys}"))?;
+    }
+    let mut phosphor = Phosphor::new();
+    let mut beep_gate = BeepGate::new(Duration::from_millis(opt.min_beep.into()));
+    let mut interval = spin_sleep_util::interval(chip8.timer_cycle())
+        .with_missed_tick_behavior(MissedTickBehavior::Delay);
+    #[cfg(feature = "report_frame_rate")]
+    let mut reporter = spin_sleep_util::RateReporter::new(Duration::from_secs(1) / 10);
+    loop {
+        interval.tick();
+        if !keyboard.poll(&mut chip8)? {
+            break;
+        }
+        if watcher.as_mut().is_some_and(Watcher::poll) {
+            // A ROM that fails to load is reported, and the one running is kept until it is fixed.
+            match read_rom(rom_file).and_then(|new_rom| {
+                if opt.hot_reload {
+                    chip8.hot_reload(&rom, &new_rom).context(Chip8Snafu)?;
+                } else {
+                    chip8 = start(&new_rom)?;
+                    chip8.set_cpu_speed(cpu_speed);
+                }
+                Ok(new_rom)
+            }) {
+                Ok(new_rom) => {
+                    println!("Reloaded {}", rom_file.display());
+                    rom = new_rom;
+                    // The snapshots are of another ROM, which cannot be rewound to.
+                    rewind.clear();
+                    if let Some(graphics) = &mut graphics {
+                        graphics.canvas.window_mut().set_title(window_title(paused, false))?;
+                    }
+                }
+                Err(err) => error!("Could not reload {}: {err}", rom_file.display()),
+            }
+        }
+        let mut advance_frame = false;
+        for hotkey in keyboard.hotkeys.drain(..) {
+            let message = match hotkey {
+                Hotkey::AdvanceFrame => {
+                    advance_frame = true;
+                    continue;
+                }
+                Hotkey::ToggleFullscreen => {
+                    if let Some(graphics) = &mut graphics {
+                        graphics.toggle_fullscreen()?;
+                    }
+                    continue;
+                }
+                Hotkey::SaveState => match save_slots.save(&chip8) {
+                    Ok(path) => format!("Saved slot {} to {}", save_slots.slot(), path.display()),
+                    Err(err) => format!("Could not save slot {}: {err}", save_slots.slot()),
+                },
+                Hotkey::SelectSlot(forward) => {
+                    save_slots.select_next(forward);
+                    format!("Selected slot {}", save_slots.slot())
+                }
+                Hotkey::ChangeCpuSpeed(faster) => {
+                    cpu_speed = if faster {
+                        cpu_speed.saturating_add(CPU_SPEED_STEP)
+                    } else {
+                        cpu_speed.saturating_sub(CPU_SPEED_STEP).max(CPU_SPEED_STEP)
+                    };
+                    chip8.set_cpu_speed(cpu_speed);
+                    let unused = if opt.vip_timing { " (unused with --vip-timing)" } else { "" };
+                    format!("CPU speed: {cpu_speed} instructions per second{unused}")
+                }
+                Hotkey::LoadState => match save_slots.load(&mut chip8) {
+                    Ok(Some(path)) => {
+                        rewind.clear();
+                        format!("Loaded slot {} from {}", save_slots.slot(), path.display())
+                    }
+                    Ok(None) => format!("Slot {} is empty", save_slots.slot()),
+                    Err(err) => format!("Could not load slot {}: {err}", save_slots.slot()),
+                },
+            };
+            notify(&mut graphics, &mut notification_deadline, &message)?;
+        }
+        if keyboard.paused != paused {
+            paused = keyboard.paused;
+            if let Some(graphics) = &mut graphics {
+                graphics.paused = paused;
+                graphics.canvas.window_mut().set_title(window_title(paused, halted))?;
+            }
+            if let Some(high_scores) = high_scores.as_ref().filter(|_| paused) {
+                let message = match high_scores.table() {
+                    Ok(table) => high_scores::format_table(&table),
+                    Err(err) => format!("Could not read the high scores: {err}"),
+                };
+                notify(&mut graphics, &mut notification_deadline, &message)?;
+            }
+        }
+        for cheat in cheats.iter().filter(|cheat| cheat.mode == CheatMode::Freeze) {
+            chip8.apply_cheat(cheat).context(Chip8Snafu)?;
+        }
+        let sound_started = if keyboard.rewinding {
+            // Go back a frame per frame until the oldest snapshot, then stay there.
+            if rewind.step_back(&mut chip8).context(Chip8Snafu)? {
+                // The keys held back then are not held now.
+                for key in 0..16 {
+                    chip8.release_key(key).context(Chip8Snafu)?;
+                }
+            }
+            updater.skip();
+            false
+        } else if paused && !advance_frame {
+            // Neither the instructions nor the timers run while paused.
+            updater.skip();
+            false
+        } else {
+            let result = if paused {
+                updater.advance_frame(&mut chip8)
+            } else {
+                // Turbo runs `TURBO_SPEED` times the time since the last frame, so it keeps the
+                // frame limiter: without it, frames would only get shorter, and vsync, which sdl2
+                // cannot turn off once the canvas is built, would cap them anyway.
+                let speed = if keyboard.turbo { TURBO_SPEED } else { 1 };
+                updater.update(&mut chip8, speed)
+            };
+            let sound_started = result.map_err(|err| {
+                // Report why the trace exporter aborted execution rather than that it did.
+                let trace_error =
+                    trace_exporter.as_ref().and_then(|exporter| exporter.lock().ok()?.take_error());
+                let err = trace_error.map_or(err, |source| Error::Chip8 { source });
+                match crash::write_bundle(rom_file, &quirks, &chip8, &err) {
+                    Ok(directory) => eprintln!(
+                        "A crash report was written to {}; please attach it to bug reports",
+                        directory.display()
+                    ),
+                    Err(bundle_err) => warn!("Could not write a crash report: {bundle_err}"),
+                }
+                err
+            })?;
+            rewind.record(&chip8).context(Chip8Snafu)?;
+            sound_started
+        };
+        if chip8.is_halted() && !halted {
+            println!("The program has ended");
+            if let Some(graphics) = &mut graphics {
+                graphics.canvas.window_mut().set_title(window_title(paused, true))?;
+            }
+        }
+        halted = chip8.is_halted();
+        if let Some(achievements) = &mut achievements {
+            for name in achievements.update(&chip8)? {
+                let message = format!("Achievement unlocked: {name}");
+                notify(&mut graphics, &mut notification_deadline, &message)?;
+            }
+        }
+        if let Some(high_scores) = &mut high_scores {
+            high_scores.update(&chip8);
+        }
+        if notification_deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            if let Some(graphics) = &mut graphics {
+                graphics.canvas.window_mut().set_title(window_title(paused, halted))?;
+            }
+            notification_deadline = None;
+        }
+        #[cfg(feature = "report_frame_rate")]
+        {
+            if let Some(fps) = reporter.increment_and_report() {
+                info!("Frame rate: {} Hz", fps);
+            }
+        }
+        if let Some(graphics) = &mut graphics {
+            if let Some(pressed_keys) = &mut graphics.pressed_keys {
+                *pressed_keys = std::array::from_fn(|key| chip8.is_key_pressed(key as u8));
+            }
+            // Emulate the screen ghosting effect to reduce flicker.
+            graphics.present(&phosphor.process(&chip8.screen))?;
+        }
+        if let Some(audio_device) = &mut audio_device {
+            // A beep that started and stopped within the frame still plays for the frame.
+            let beeping =
+                !paused && !keyboard.rewinding && (sound_started || chip8.timers.sound_timer > 0);
+            audio_device.set_beeping(beep_gate.process(beeping, chip8.timer_cycle()));
+        }
+    }
+    if let Some(trace_exporter) = &trace_exporter {
+        if let Ok(mut trace_exporter) = trace_exporter.lock() {
+            trace_exporter.flush().context(Chip8Snafu)?;
+        }
+    }
+    if let (Some(coverage_file), Some(coverage)) = (&opt.coverage, &updater.coverage) {
+        coverage.write(&mut File::create(coverage_file).context(IoSnafu)?).context(Chip8Snafu)?;
+    }
+    if let Some(profiler) = &updater.profiler {
+        print!("{}", profiler.report());
+    }
+    if let Some(high_scores) = &mut high_scores {
+        println!("High scores:");
+        for (rank, score) in high_scores.save()?.iter().enumerate() {
+            println!("{:>4}. {score}", rank + 1);
+        }
+    }
+    Ok(())
+}
+
+/// Prints `message` and shows it in the title bar of the window for `NOTIFICATION_DURATION`.
+fn notify(
+    graphics: &mut Option<Graphics<'_>>,
+    deadline: &mut Option<Instant>,
+    message: &str,
+) -> Result<()> {
+    println!("{message}");
+    if let Some(graphics) = graphics {
+        graphics.canvas.window_mut().set_title(&format!("CHIP-8 - {message}"))?;
+        *deadline = Some(Instant::now() + NOTIFICATION_DURATION);
+    }
+    Ok(())
+}
+
+/// Returns the title of the window, which tells whether the interpreter is paused or has halted.
+fn window_title(paused: bool, halted: bool) -> &'static str {
+    match (paused, halted) {
+        (true, _) => "CHIP-8 - Paused",
+        (false, true) => "CHIP-8 - Program ended",
+        (false, false) => "CHIP-8",
+    }
+}
+
+/// Opens a window, returning its canvas and whether it has room for the on-screen keypad, which it
+/// does if `touch` is true or a touch device is present.
+fn open_canvas(sdl_context: &sdl2::Sdl, touch: bool) -> Result<(Canvas<Window>, bool)> {
     let video_subsystem = sdl_context.video()?;
+    // Touch devices are only known once the video subsystem is initialized.
+    let keypad = touch || sdl2::touch::num_touch_devices() > 0;
+    let height = if keypad { WINDOW_HEIGHT * 2 } else { WINDOW_HEIGHT };
     let window = video_subsystem
-        .window("CHIP-8", WINDOW_WIDTH, WINDOW_HEIGHT)
+        .window("CHIP-8", WINDOW_WIDTH, height)
         .allow_highdpi()
         .resizable()
         .build()?;
     info!("{:?}", window.display_mode()?);
-    let mut canvas = window.into_canvas().accelerated().present_vsync().build()?;
+    let canvas = window.into_canvas().accelerated().present_vsync().build()?;
     info!("{:?}", canvas.info());
-    let texture_creator = canvas.texture_creator();
+    Ok((canvas, keypad))
+}
 
+fn open_audio(sdl_context: &sdl2::Sdl, waveform: &Waveform) -> Result<AudioDevice<Sampler>> {
     let audio_subsystem = sdl_context.audio()?;
     let audio_spec_desired = AudioSpecDesired {
         freq: None,        // the SDL_AUDIO_FREQUENCY environment variable or, if not set, 22050 Hz
@@ -152,7 +1097,7 @@ fn run(opt: Opt) -> Result<()> {
     let sampler = |audio_spec: AudioSpec| Sampler {
         phase: 0.0,
         step: 440.0 / audio_spec.freq as f32,
-        waveform: match opt.waveform {
+        waveform: match waveform {
             Waveform::Sawtooth => {
                 Box::new(|phase| if phase < 0.5 { 2.0 * phase } else { 2.0 * phase - 2.0 })
             }
@@ -163,39 +1108,12 @@ fn run(opt: Opt) -> Result<()> {
             }
         },
     };
-    let audio_device = audio_subsystem.open_playback(None, &audio_spec_desired, sampler)?;
-
-    let mut event_pump = sdl_context.event_pump()?;
-
-    // Run a CHIP-8 ROM image.
-
-    let mut chip8 = chip8::Chip8::new(&opt.rom_file, opt.shift_quirks, opt.load_store_quirks)
-        .context(Chip8Snafu)?;
-    debug!("{:?}", chip8);
-    let mut updater = Updater::new(opt.cpu_speed);
-    let mut graphics = Graphics::new(&texture_creator)?;
-    let mut interval = spin_sleep_util::interval(Duration::from_secs(1) / 60)
-        .with_missed_tick_behavior(MissedTickBehavior::Delay);
-    #[cfg(feature = "report_frame_rate")]
-    let mut reporter = spin_sleep_util::RateReporter::new(Duration::from_secs(1) / 10);
-    loop {
-        interval.tick();
-        if !process_input(&mut event_pump, &mut chip8) {
-            break;
-        }
-        updater.update(&mut chip8)?;
-        #[cfg(feature = "report_frame_rate")]
-        {
-            if let Some(fps) = reporter.increment_and_report() {
-                info!("Frame rate: {} Hz", fps);
-            }
-        }
-        graphics.render(&chip8, &mut canvas)?;
-        play_audio(&chip8, &audio_device);
-    }
-    Ok(())
+    Ok(audio_subsystem.open_playback(None, &audio_spec_desired, sampler)?)
 }
 
+/// An audio device playing a beep.
+struct Audio(AudioDevice<Sampler>);
+
 struct Sampler {
     phase: f32,
     step: f32,
@@ -213,132 +1131,343 @@ impl AudioCallback for Sampler {
     }
 }
 
-fn process_input(event_pump: &mut EventPump, chip8: &mut chip8::Chip8) -> bool {
-    for event in event_pump.poll_iter() {
-        match event {
-            Event::KeyDown { scancode: Some(scancode), repeat, .. } => {
-                if !repeat {
-                    if let Some(key) = scancode_to_chip8_key(scancode) {
-                        chip8.is_key_pressed[key] = true;
+/// A key that controls the frontend rather than typing a CHIP-8 key.
+#[derive(Clone, Copy, Debug)]
+enum Hotkey {
+    /// Period, while paused: runs the interpreter for a frame and pauses it again.
+    AdvanceFrame,
+    /// + or -: changes the CPU speed by `CPU_SPEED_STEP`, up if true.
+    ChangeCpuSpeed(bool),
+    /// F5: saves the state in the selected slot.
+    SaveState,
+    /// F6: selects the next slot, or with Shift, the previous one.
+    SelectSlot(bool),
+    /// F7: loads the state in the selected slot.
+    LoadState,
+    /// F11 or Alt+Enter: switches between the window and fullscreen.
+    ToggleFullscreen,
+}
+
+struct Keyboard {
+    event_pump: EventPump,
+    key_map: KeyMap,
+    controllers: Option<Controllers>,
+    touch_keypad: Option<TouchKeypad>,
+    /// Whether P or Space has paused the interpreter.
+    paused: bool,
+    /// Whether Backspace is held to rewind the interpreter.
+    rewinding: bool,
+    /// Whether Tab is held to run the interpreter faster.
+    turbo: bool,
+    /// The hotkeys pressed since they were last taken.
+    hotkeys: Vec<Hotkey>,
+    sticky_keys: bool,
+    min_key_hold: Duration,
+    pressed_at: [Option<Instant>; 16],
+    is_release_pending: [bool; 16],
+}
+
+impl Keyboard {
+    fn new(
+        event_pump: EventPump,
+        key_map: KeyMap,
+        controllers: Option<Controllers>,
+        touch_keypad: Option<TouchKeypad>,
+        sticky_keys: bool,
+        min_key_hold: Duration,
+    ) -> Self {
+        Self {
+            event_pump,
+            key_map,
+            controllers,
+            touch_keypad,
+            paused: false,
+            rewinding: false,
+            turbo: false,
+            hotkeys: Vec::new(),
+            sticky_keys,
+            min_key_hold,
+            pressed_at: [None; 16],
+            is_release_pending: [false; 16],
+        }
+    }
+
+    fn key_down(&mut self, key: u8, chip8: &mut chip8::Chip8) -> Result<()> {
+        if self.sticky_keys && chip8.is_key_pressed(key) {
+            return chip8.release_key(key).context(Chip8Snafu);
+        }
+        self.pressed_at[usize::from(key)] = Some(Instant::now());
+        self.is_release_pending[usize::from(key)] = false;
+        chip8.press_key(key).context(Chip8Snafu)
+    }
+
+    fn key_up(&mut self, key: u8, chip8: &mut chip8::Chip8) -> Result<()> {
+        if self.sticky_keys {
+            Ok(())
+        } else if self.is_held_too_briefly(key) {
+            self.is_release_pending[usize::from(key)] = true;
+            Ok(())
+        } else {
+            chip8.release_key(key).context(Chip8Snafu)
+        }
+    }
+
+    fn is_held_too_briefly(&self, key: u8) -> bool {
+        self.pressed_at[usize::from(key)]
+            .is_some_and(|pressed_at| pressed_at.elapsed() < self.min_key_hold)
+    }
+}
+
+impl InputSource for Keyboard {
+    type Error = Error;
+
+    fn poll(&mut self, chip8: &mut chip8::Chip8) -> Result<bool> {
+        while let Some(event) = self.event_pump.poll_event() {
+            match event {
+                Event::KeyDown { scancode, keycode, keymod, repeat: false, .. } => {
+                    if let Some(key) = self.key_map.key(scancode, keycode) {
+                        self.key_down(key, chip8)?;
+                    } else {
+                        let shift = keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD);
+                        let alt = keymod.intersects(Mod::LALTMOD | Mod::RALTMOD);
+                        match scancode {
+                            Some(Scancode::P | Scancode::Space) => self.paused = !self.paused,
+                            Some(Scancode::Backspace) => self.rewinding = true,
+                            // Period pauses a running interpreter and advances a paused one.
+                            Some(Scancode::Period) if self.paused => {
+                                self.hotkeys.push(Hotkey::AdvanceFrame);
+                            }
+                            Some(Scancode::Period) => self.paused = true,
+                            Some(Scancode::Tab) => self.turbo = true,
+                            Some(Scancode::Equals | Scancode::KpPlus) => {
+                                self.hotkeys.push(Hotkey::ChangeCpuSpeed(true));
+                            }
+                            Some(Scancode::Minus | Scancode::KpMinus) => {
+                                self.hotkeys.push(Hotkey::ChangeCpuSpeed(false));
+                            }
+                            Some(Scancode::F5) => self.hotkeys.push(Hotkey::SaveState),
+                            Some(Scancode::F6) => self.hotkeys.push(Hotkey::SelectSlot(!shift)),
+                            Some(Scancode::F7) => self.hotkeys.push(Hotkey::LoadState),
+                            Some(Scancode::F11) => self.hotkeys.push(Hotkey::ToggleFullscreen),
+                            Some(Scancode::Return) if alt => {
+                                self.hotkeys.push(Hotkey::ToggleFullscreen);
+                            }
+                            _ => (),
+                        }
                     }
                 }
-            }
-            Event::KeyUp { scancode: Some(scancode), repeat, .. } => {
-                if !repeat {
-                    if let Some(key) = scancode_to_chip8_key(scancode) {
-                        chip8.is_key_pressed[key] = false;
+                Event::KeyUp { scancode, keycode, repeat: false, .. } => {
+                    if let Some(key) = self.key_map.key(scancode, keycode) {
+                        self.key_up(key, chip8)?;
+                    } else {
+                        match scancode {
+                            Some(Scancode::Backspace) => self.rewinding = false,
+                            Some(Scancode::Tab) => self.turbo = false,
+                            _ => (),
+                        }
+                    }
+                }
+                Event::Quit { .. } => return Ok(false),
+                _ => {
+                    let key_event = self
+                        .controllers
+                        .as_mut()
+                        .and_then(|controllers| controllers.handle(&event))
+                        .or_else(|| self.touch_keypad.as_mut()?.handle(&event));
+                    match key_event {
+                        Some((key, true)) => self.key_down(key, chip8)?,
+                        Some((key, false)) => self.key_up(key, chip8)?,
+                        None => (),
                     }
                 }
             }
-            Event::Quit { .. } => return false,
-            _ => (),
-        }
-    }
-    true
-}
-
-// The PC keys (or the SDL scancodes) on the left are mapped to the CHIP-8 keys on the right:
-//
-//   1 2 3 4   1 2 3 C
-//   Q W E R   4 5 6 D
-//   A S D F   7 8 9 E
-//   Z X C V   A 0 B F
-fn scancode_to_chip8_key(scancode: Scancode) -> Option<usize> {
-    match scancode {
-        Scancode::Num1 => Some(0x1),
-        Scancode::Num2 => Some(0x2),
-        Scancode::Num3 => Some(0x3),
-        Scancode::Num4 => Some(0xC),
-        Scancode::Q => Some(0x4),
-        Scancode::W => Some(0x5),
-        Scancode::E => Some(0x6),
-        Scancode::R => Some(0xD),
-        Scancode::A => Some(0x7),
-        Scancode::S => Some(0x8),
-        Scancode::D => Some(0x9),
-        Scancode::F => Some(0xE),
-        Scancode::Z => Some(0xA),
-        Scancode::X => Some(0x0),
-        Scancode::C => Some(0xB),
-        Scancode::V => Some(0xF),
-        _ => None,
+        }
+
+        // Release the keys that have been held long enough since they were let go of.
+        for key in 0..16 {
+            if self.is_release_pending[usize::from(key)] && !self.is_held_too_briefly(key) {
+                self.is_release_pending[usize::from(key)] = false;
+                chip8.release_key(key).context(Chip8Snafu)?;
+            }
+        }
+        Ok(true)
     }
 }
 
 struct Updater {
     clock: Instant,
-    timer_time_lag: Duration,
-    cpu_time_lag: Duration,
-    instruction_cycle: Duration,
+    coverage: Option<Coverage>,
+    profiler: Option<Profiler>,
 }
 
 impl Updater {
-    fn new(cpu_speed: u32) -> Self {
-        let instruction_cycle =
-            Duration::from_nanos((1_000_000_000.0 / f64::from(cpu_speed)).round() as u64);
-        Self {
-            clock: Instant::now(),
-            timer_time_lag: Duration::new(0, 0),
-            cpu_time_lag: Duration::new(0, 0),
-            instruction_cycle,
-        }
+    fn new() -> Self {
+        Self { clock: Instant::now(), coverage: None, profiler: None }
     }
 
-    fn update(&mut self, chip8: &mut chip8::Chip8) -> Result<()> {
+    /// Discards the time since the last update, so that the time paused is not run.
+    fn skip(&mut self) {
+        self.clock = Instant::now();
+    }
+
+    /// Runs `chip8` for `speed` times the time since the last update, returning true if a beep
+    /// started.
+    fn update(&mut self, chip8: &mut chip8::Chip8, speed: u32) -> Result<bool> {
         let elapsed_time = self.clock.elapsed();
         self.clock = Instant::now();
+        self.run(chip8, elapsed_time, speed)
+    }
+
+    /// Runs `chip8` for a single frame: a tick of the timers and the instructions of its time.
+    fn advance_frame(&mut self, chip8: &mut chip8::Chip8) -> Result<bool> {
+        self.clock = Instant::now();
+        self.run(chip8, chip8.timer_cycle(), 1)
+    }
 
-        self.timer_time_lag += elapsed_time;
-        while self.timer_time_lag >= chip8::TIMER_CLOCK_CYCLE {
-            chip8.timers.count_down();
-            self.timer_time_lag -= chip8::TIMER_CLOCK_CYCLE;
+    /// Runs `chip8` for `speed` times `elapsed_time`, returning true if a beep started.
+    fn run(
+        &mut self,
+        chip8: &mut chip8::Chip8,
+        elapsed_time: Duration,
+        speed: u32,
+    ) -> Result<bool> {
+        // Running for the time since the last update over and over, rather than for a multiple of
+        // it at once, counts the timers down between runs, for programs that wait for the vertical
+        // blank.
+        let mut steps = Vec::new();
+        for _ in 0..speed {
+            match chip8.run_for(elapsed_time) {
+                Ok(run_steps) => steps.extend(run_steps),
+                Err(err) => {
+                    log_trace(chip8);
+                    return Err(err).context(Chip8Snafu);
+                }
+            }
         }
+        for step in &steps {
+            if let Some(coverage) = &mut self.coverage {
+                coverage.record(step);
+            }
+            if let Some(profiler) = &mut self.profiler {
+                profiler.record(step);
+            }
+        }
+        if !steps.is_empty() {
+            trace!("{:?}", chip8);
+        }
+        Ok(steps.iter().any(|step| step.sound_started))
+    }
+}
 
-        // NOTE: Each CHIP-8 instruction is assumed to finish within a single instruction cycle.
-        self.cpu_time_lag += elapsed_time;
-        while self.cpu_time_lag >= self.instruction_cycle {
-            chip8.fetch_execute_cycle().context(Chip8Snafu)?;
-            debug!("{:?}", chip8);
-            self.cpu_time_lag -= self.instruction_cycle;
+/// Logs the instructions that led to an error.
+fn log_trace(chip8: &chip8::Chip8) {
+    error!("The last instructions executed:{}", format_trace(chip8));
+}
+
+/// Returns the instructions in the trace of `chip8`, each on a line of its own after a newline.
+fn format_trace(chip8: &chip8::Chip8) -> String {
+    let mut trace = String::new();
+    for (pc, opcode) in chip8.trace() {
+        trace += &format!("\n{pc:#05X}  {opcode:04X}");
+        if let Some(instruction) = chip8::instruction::Instruction::decode(opcode) {
+            trace += &format!("  {instruction}");
         }
-        Ok(())
     }
+    trace
 }
 
 struct Graphics<'texture_creator> {
-    screen: Screen,
+    canvas: Canvas<Window>,
     texture: Texture<'texture_creator>,
+    /// Which keys the on-screen keypad shows as pressed, if it is shown.
+    pressed_keys: Option<[bool; 16]>,
+    /// Whether to show the pause sign.
+    paused: bool,
+    /// How F11 and Alt+Enter go fullscreen.
+    fullscreen: FullscreenType,
 }
 
 impl<'texture_creator> Graphics<'texture_creator> {
-    fn new(texture_creator: &'texture_creator TextureCreator<WindowContext>) -> Result<Self> {
+    fn new(
+        canvas: Canvas<Window>,
+        texture_creator: &'texture_creator TextureCreator<WindowContext>,
+        keypad: bool,
+        fullscreen: FullscreenType,
+    ) -> Result<Self> {
         let texture = texture_creator.create_texture(
             Some(PixelFormatEnum::RGB332),
             TextureAccess::Static,
             chip8::SCREEN_WIDTH as u32,
             chip8::SCREEN_HEIGHT as u32,
         )?;
-        Ok(Self { screen: Screen::default(), texture })
+        Ok(Self {
+            canvas,
+            texture,
+            pressed_keys: keypad.then_some([false; 16]),
+            paused: false,
+            fullscreen,
+        })
     }
 
-    fn render(&mut self, chip8: &chip8::Chip8, canvas: &mut Canvas<Window>) -> Result<()> {
-        // Emulate the screen ghosting effect to reduce flicker.
-        self.screen |= &chip8.screen;
-        self.texture.update(None, self.screen.as_ref(), chip8::SCREEN_WIDTH)?;
-        self.screen = chip8.screen;
+    /// Switches between the window and fullscreen.
+    fn toggle_fullscreen(&mut self) -> Result<()> {
+        let window = self.canvas.window_mut();
+        let state = match window.fullscreen_state() {
+            FullscreenType::Off => self.fullscreen,
+            _ => FullscreenType::Off,
+        };
+        window.set_fullscreen(state)?;
+        Ok(())
+    }
+}
+
+/// Returns the largest rectangle centered in `area` that shows the screen with the same whole
+/// number of pixels per CHIP-8 pixel across and down, so that every CHIP-8 pixel is the same size.
+fn scale_screen(area: Rect) -> Rect {
+    let (width, height) = (chip8::SCREEN_WIDTH as u32, chip8::SCREEN_HEIGHT as u32);
+    let scale = (area.width() / width).min(area.height() / height).max(1);
+    let mut screen = Rect::new(0, 0, width * scale, height * scale);
+    screen.center_on(area.center());
+    screen
+}
+
+impl DisplaySink for Graphics<'_> {
+    type Error = Error;
+
+    fn present(&mut self, frame: &chip8::Screen) -> Result<()> {
+        self.texture.update(None, frame.as_ref(), chip8::SCREEN_WIDTH)?;
 
-        canvas.set_draw_color(Color::RGB(0, 0, 0));
-        canvas.clear();
-        canvas.copy(&self.texture, None, None)?;
-        canvas.present();
+        self.canvas.set_draw_color(Color::RGB(0, 0, 0));
+        self.canvas.clear();
+        let (width, height) = self.canvas.output_size()?;
+        let screen = scale_screen(if let Some(pressed_keys) = self.pressed_keys {
+            let (screen, keypad) = touch::split(width, height);
+            touch::draw(&mut self.canvas, keypad, |key| pressed_keys[usize::from(key)])?;
+            screen
+        } else {
+            Rect::new(0, 0, width, height)
+        });
+        self.canvas.copy(&self.texture, None, screen)?;
+        if self.paused {
+            // Two bars in the top right corner of the screen, in a color that the screen never has.
+            let unit = screen.height() / 24;
+            self.canvas.set_draw_color(PAUSE_SIGN_COLOR);
+            for bar in [4, 2] {
+                let x = screen.right() - (unit * bar) as i32;
+                self.canvas.fill_rect(Rect::new(x, screen.y() + unit as i32, unit, unit * 3))?;
+            }
+        }
+        self.canvas.present();
         Ok(())
     }
 }
 
-fn play_audio(chip8: &chip8::Chip8, audio_device: &AudioDevice<Sampler>) {
-    if chip8.timers.sound_timer > 0 {
-        audio_device.resume();
-    } else {
-        audio_device.pause();
+impl AudioSink for Audio {
+    fn set_beeping(&mut self, beeping: bool) {
+        if beeping {
+            self.0.resume();
+        } else {
+            self.0.pause();
+        }
     }
 }
@@ -0,0 +1,199 @@
+//! Recording key presses and replaying them deterministically.
+//!
+//! A movie holds the seed of the random number generator, the hash of the ROM, and the key events
+//! of every frame. Replaying it reproduces a run exactly if every frame executes the same number of
+//! instructions as when it was recorded. Movies are saved as text, one event per line:
+//!
+//! ``` text
+//! chip8-movie 1
+//! seed 42
+//! rom 0x9E9C1F04539F3666
+//! 120 press 5
+//! 123 release 5
+//! ```
+
+use std::{
+    format,
+    io::{BufRead, BufReader, Read, Write},
+    vec::Vec,
+};
+
+use snafu::{ensure, ResultExt};
+
+use crate::{rng::SeededRng, Chip8, InvalidMovieSnafu, IoSnafu, MovieRomMismatchSnafu, Result};
+
+const HEADER: &str = "chip8-movie 1";
+
+/// A key press or release in a movie.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct KeyEvent {
+    /// The number of frames before the event, counting from 0.
+    pub frame: u64,
+    /// The hex key (0x0..=0xF).
+    pub key: u8,
+    /// True for a press, false for a release.
+    pub pressed: bool,
+}
+
+/// A recorded run, which can be saved, loaded, and replayed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Movie {
+    pub seed: u64,
+    pub rom_hash: u64,
+    /// The key events in the order in which they happened.
+    pub events: Vec<KeyEvent>,
+}
+
+impl Movie {
+    /// Prepares `chip8` for replaying this movie, seeding its random number generator and failing
+    /// if it was created with another ROM. `chip8` should be freshly created.
+    pub fn prepare(&self, chip8: &mut Chip8) -> Result<()> {
+        let actual = chip8.rom_hash();
+        ensure!(actual == self.rom_hash, MovieRomMismatchSnafu { expected: self.rom_hash, actual });
+        chip8.set_rng(SeededRng::new(self.seed));
+        Ok(())
+    }
+
+    /// Writes this movie as text.
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let mut text = format!("{HEADER}\nseed {}\nrom {:#018X}\n", self.seed, self.rom_hash);
+        for event in &self.events {
+            let action = if event.pressed { "press" } else { "release" };
+            text += &format!("{} {action} {:X}\n", event.frame, event.key);
+        }
+        writer.write_all(text.as_bytes()).context(IoSnafu)
+    }
+
+    /// Reads a movie written by `write`.
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let mut lines = Vec::new();
+        for line in BufReader::new(reader).lines() {
+            lines.push(line.context(IoSnafu)?);
+        }
+        let fail = |index: usize, reason| InvalidMovieSnafu { line: index + 1, reason }.fail();
+        if lines.first().map(|line| line.trim()) != Some(HEADER) {
+            return fail(0, "expected `chip8-movie 1`");
+        }
+        let mut seed = None;
+        let mut rom_hash = None;
+        let mut events: Vec<KeyEvent> = Vec::new();
+        for (index, line) in lines.iter().enumerate().skip(1) {
+            let words: Vec<_> = line.split_whitespace().collect();
+            match words.as_slice() {
+                [] => (),
+                ["seed", value] => match value.parse() {
+                    Ok(value) => seed = Some(value),
+                    Err(_) => return fail(index, "expected a seed"),
+                },
+                ["rom", value] => {
+                    match value.strip_prefix("0x").map(|hex| u64::from_str_radix(hex, 16)) {
+                        Some(Ok(value)) => rom_hash = Some(value),
+                        _ => return fail(index, "expected a hexadecimal ROM hash"),
+                    }
+                }
+                [frame, action, key] => {
+                    let Ok(frame) = frame.parse() else {
+                        return fail(index, "expected a frame number");
+                    };
+                    let pressed = match *action {
+                        "press" => true,
+                        "release" => false,
+                        _ => return fail(index, "expected `press` or `release`"),
+                    };
+                    let key = match u8::from_str_radix(key, 16) {
+                        Ok(key) if key < 16 => key,
+                        _ => return fail(index, "expected a hex key between 0 and F"),
+                    };
+                    if events.last().is_some_and(|last| last.frame > frame) {
+                        return fail(index, "frame numbers must not decrease");
+                    }
+                    events.push(KeyEvent { frame, key, pressed });
+                }
+                _ => return fail(index, "expected `seed`, `rom`, or a key event"),
+            }
+        }
+        let (Some(seed), Some(rom_hash)) = (seed, rom_hash) else {
+            return fail(lines.len().saturating_sub(1), "the seed or the ROM hash is missing");
+        };
+        Ok(Self { seed, rom_hash, events })
+    }
+}
+
+/// Records the key events of a run into a movie.
+#[derive(Debug)]
+pub struct Recorder {
+    movie: Movie,
+    frame: u64,
+}
+
+impl Recorder {
+    /// Starts recording a run of `chip8`, seeding its random number generator with `seed`.
+    pub fn new(chip8: &mut Chip8, seed: u64) -> Self {
+        chip8.set_rng(SeededRng::new(seed));
+        Self { movie: Movie { seed, rom_hash: chip8.rom_hash(), events: Vec::new() }, frame: 0 }
+    }
+
+    /// Presses `key` on `chip8`, recording the press in the current frame. Like `Player::apply`,
+    /// key events should happen before the instructions of the frame are executed.
+    pub fn press_key(&mut self, chip8: &mut Chip8, key: u8) -> Result<()> {
+        chip8.press_key(key)?;
+        self.movie.events.push(KeyEvent { frame: self.frame, key, pressed: true });
+        Ok(())
+    }
+
+    /// Releases `key` on `chip8`, recording the release in the current frame.
+    pub fn release_key(&mut self, chip8: &mut Chip8, key: u8) -> Result<()> {
+        chip8.release_key(key)?;
+        self.movie.events.push(KeyEvent { frame: self.frame, key, pressed: false });
+        Ok(())
+    }
+
+    /// Moves on to the next frame. Call this once at the end of every frame.
+    pub fn end_frame(&mut self) {
+        self.frame += 1;
+    }
+
+    /// Returns the movie recorded so far.
+    pub fn movie(&self) -> &Movie {
+        &self.movie
+    }
+}
+
+/// Replays the key events of a movie.
+#[derive(Debug)]
+pub struct Player {
+    movie: Movie,
+    frame: u64,
+    next_event: usize,
+}
+
+impl Player {
+    /// Starts replaying `movie` on `chip8` (see `Movie::prepare`).
+    pub fn new(movie: Movie, chip8: &mut Chip8) -> Result<Self> {
+        movie.prepare(chip8)?;
+        Ok(Self { movie, frame: 0, next_event: 0 })
+    }
+
+    /// Applies the key events of the current frame to `chip8` and moves on to the next frame. Call
+    /// this once at the start of every frame.
+    pub fn apply(&mut self, chip8: &mut Chip8) -> Result<()> {
+        while let Some(event) = self.movie.events.get(self.next_event) {
+            if event.frame > self.frame {
+                break;
+            }
+            if event.pressed {
+                chip8.press_key(event.key)?;
+            } else {
+                chip8.release_key(event.key)?;
+            }
+            self.next_event += 1;
+        }
+        self.frame += 1;
+        Ok(())
+    }
+
+    /// Returns true once every event has been applied.
+    pub fn is_finished(&self) -> bool {
+        self.next_event == self.movie.events.len()
+    }
+}
@@ -0,0 +1,665 @@
+//! A front-end for Octo (`.8o` files), the assembly language that most modern CHIP-8 programs are
+//! written in.
+//!
+//! The CHIP-8 subset of the language is supported: labels (`: name`), `:alias`, `:const`,
+//! `:call`, `:byte`, every CHIP-8 statement, `if ... then`, `if ... begin ... else ... end`, and
+//! `loop ... while ... again`. Conditions compare with `==` and `!=`, or test keys with `key` and
+//! `-key`. A bare number emits a byte, and a bare name calls the subroutine of that name.
+//! SUPER-CHIP and XO-CHIP statements, `:macro`, `:calc`, `:org`, `:next`, `:unpack`, and the `<`,
+//! `>`, `<=`, and `>=` conditions are not supported. Execution starts at `: main`, to which the
+//! instruction at 0x200 jumps, unless, as in Octo, `: main` comes before any other label,
+//! instruction, or byte, in which case the program starts with it at 0x200.
+//!
+//! ``` text
+//! :alias x v0
+//! :const STEP 8
+//!
+//! : main
+//!   i := zero
+//!   loop
+//!     sprite x v1 5
+//!     x += STEP
+//!     while x != 64
+//!   again
+//!   loop again
+//!
+//! : zero 0xF0 0x90 0x90 0x90 0xF0
+//! ```
+//!
+//! `decompile` turns a ROM back into source in this subset.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Write,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use snafu::{ensure, OptionExt};
+
+use crate::{
+    analysis::{self, ReferenceKind},
+    asm::{Program, Value},
+    disasm::Item,
+    instruction::Instruction,
+    isa::Operand,
+    InvalidAssemblySnafu, Result,
+};
+
+/// Assembles an Octo program, returning the ROM image.
+pub fn assemble(source: &str) -> Result<Vec<u8>> {
+    let mut tokens = Vec::new();
+    for (index, line) in source.lines().enumerate() {
+        let code = line.split('#').next().unwrap_or_default();
+        tokens.extend(code.split_whitespace().map(|token| (token, index + 1)));
+    }
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+        program: Program::default(),
+        aliases: HashMap::new(),
+        constants: HashMap::new(),
+        blocks: Vec::new(),
+        has_main: false,
+    };
+    if !starts_with_main(&parser.tokens) {
+        parser.program.instruction(Instruction::Jump(0), Some((Operand::Nnn, label("main"))), 1)?;
+    }
+    while parser.position < parser.tokens.len() {
+        parser.statement()?;
+    }
+    let line = parser.tokens.last().map_or(1, |&(_, line)| line);
+    ensure!(
+        parser.blocks.is_empty(),
+        InvalidAssemblySnafu { line, reason: "missing `end` or `again`" }
+    );
+    ensure!(parser.has_main, InvalidAssemblySnafu { line, reason: "missing `: main`" });
+    parser.program.finish()
+}
+
+/// Decompiles a ROM into source that `assemble` turns back into the same ROM.
+///
+/// The code and the data are told apart by `analysis::analyze`. Jumps, calls, and `i :=` refer
+/// to labels named after the original addresses, such as `sub_204` for a subroutine and
+/// `sprite_2F0` for a sprite, which is written a row per line in binary. Addresses outside the
+/// ROM that I is set to become constants, such as `addr_EA0`.
+///
+/// If the ROM starts with a jump, its target becomes `main`, so that the jump is the one that
+/// `assemble` puts at 0x200. Otherwise, `main` is 0x200 and comes first in the source.
+///
+/// ```
+/// # fn main() -> Result<(), chip8::Error> {
+/// // JP 0x204; (data); LD I, 0x202; DRW V0, V0, 2; JP 0x204
+/// let rom = [0x12, 0x04, 0xF0, 0x90, 0xA2, 0x02, 0xD0, 0x02, 0x12, 0x04];
+/// let source = chip8::octo::decompile(&rom);
+/// assert!(source.contains("i := sprite_202"));
+/// assert_eq!(chip8::octo::assemble(&source)?, rom);
+///
+/// // LD I, 0x204; JP 0x202; (data)
+/// let rom = [0xA2, 0x04, 0x12, 0x02, 0xF0, 0x90];
+/// assert_eq!(chip8::octo::assemble(&chip8::octo::decompile(&rom))?, rom);
+/// # Ok(())
+/// # }
+/// ```
+pub fn decompile(program: &[u8]) -> String {
+    let analysis = analysis::analyze(program);
+    let mut items = analysis.items.as_slice();
+    let mut starts: BTreeSet<usize> = items.iter().map(|&item| item_address(item)).collect();
+    // The jump to `main` that `assemble` puts at 0x200 unless `main` comes first can replace the
+    // first instruction if it is a jump past 0x202 and nothing else refers to 0x200.
+    let entry = match items.first() {
+        Some(&Item::Code { instruction: Instruction::Jump(nnn), .. })
+            if usize::from(nnn) > PROGRAM_START + 2
+                && starts.contains(&usize::from(nnn))
+                && analysis.references_to(PROGRAM_START).next().is_none() =>
+        {
+            items = &items[1..];
+            starts.remove(&PROGRAM_START);
+            usize::from(nnn)
+        }
+        _ => PROGRAM_START,
+    };
+    let mut labels = BTreeMap::from([(entry, String::from("main"))]);
+    let mut name = |address: usize, prefix: &str| {
+        if starts.contains(&address) {
+            labels.entry(address).or_insert_with(|| format!("{prefix}_{address:03X}"));
+        }
+    };
+
+    for &subroutine in &analysis.subroutines {
+        name(subroutine, "sub");
+    }
+    let mut sprite_rows = BTreeSet::new();
+    for reference in &analysis.references {
+        if reference.kind == ReferenceKind::Draw {
+            name(reference.to, "sprite");
+            if let Some(&Item::Code { instruction: Instruction::Draw { n, .. }, .. }) =
+                items.iter().find(|&&item| item_address(item) == reference.from)
+            {
+                sprite_rows.extend(reference.to..(reference.to + usize::from(n)));
+            }
+        }
+    }
+    for reference in &analysis.references {
+        match reference.kind {
+            ReferenceKind::LoadI => name(reference.to, "data"),
+            ReferenceKind::Jump => name(reference.to, "label"),
+            _ => (),
+        }
+    }
+    for &item in items {
+        if let Item::Code { instruction: Instruction::JumpOffset(nnn), .. } = item {
+            name(usize::from(nnn), "table");
+        }
+    }
+    let constants: BTreeMap<usize, String> = analysis
+        .references
+        .iter()
+        .filter(|reference| reference.kind == ReferenceKind::LoadI)
+        .map(|reference| reference.to)
+        .filter(|&address| !starts.contains(&address))
+        .map(|address| (address, format!("addr_{address:03X}")))
+        .collect();
+
+    let mut source = format!("# Decompiled from a ROM of {} bytes\n", program.len());
+    for (address, constant) in &constants {
+        let _ = writeln!(source, ":const {constant} {address:#05X}");
+    }
+    let mut data_line = Vec::new();
+    for (index, &item) in items.iter().enumerate() {
+        let address = item_address(item);
+        if let Some(label) = labels.get(&address) {
+            flush_data(&mut source, &mut data_line);
+            if source.ends_with(" then") {
+                source.push('\n');
+            }
+            let _ = writeln!(source, "\n: {label}");
+        }
+        match item {
+            // A skip followed by data has no statement for `then`, so it is written as data.
+            Item::Code { opcode, instruction, .. }
+                if !matches!(items.get(index + 1), Some(Item::Code { .. }))
+                    && statement(instruction, &labels, &constants).ends_with("then") =>
+            {
+                data_line.extend(opcode.to_be_bytes().map(|byte| format!("{byte:#04X}")));
+            }
+            Item::Code { instruction, .. } => {
+                flush_data(&mut source, &mut data_line);
+                // The statement that a skip skips goes on the line of its `if ... then`.
+                source += if source.ends_with(" then") { " " } else { "  " };
+                source += &statement(instruction, &labels, &constants);
+                if !source.ends_with(" then") {
+                    source.push('\n');
+                }
+            }
+            Item::Data { byte, .. } if sprite_rows.contains(&address) => {
+                flush_data(&mut source, &mut data_line);
+                let _ = writeln!(source, "  {byte:#010b}");
+            }
+            Item::Data { byte, .. } => {
+                data_line.push(format!("{byte:#04X}"));
+                if data_line.len() == 8 {
+                    flush_data(&mut source, &mut data_line);
+                }
+            }
+        }
+    }
+    flush_data(&mut source, &mut data_line);
+    if items.is_empty() {
+        source += "\n: main\n";
+    }
+    source
+}
+
+const PROGRAM_START: usize = 0x200;
+
+fn item_address(item: Item) -> usize {
+    match item {
+        Item::Code { address, .. } | Item::Data { address, .. } => address,
+    }
+}
+
+/// Writes the bytes of data gathered so far as a line.
+fn flush_data(source: &mut String, data_line: &mut Vec<String>) {
+    if !data_line.is_empty() {
+        let _ = writeln!(source, "  {}", data_line.join(" "));
+        data_line.clear();
+    }
+}
+
+/// Returns the statement for `instruction`, with the labels and the constants of the addresses it
+/// refers to. A skip becomes `if ... then`, to be followed by the statement it skips.
+fn statement(
+    instruction: Instruction,
+    labels: &BTreeMap<usize, String>,
+    constants: &BTreeMap<usize, String>,
+) -> String {
+    let address = |nnn: u16| {
+        let nnn = usize::from(nnn);
+        labels.get(&nnn).or(constants.get(&nnn)).cloned().unwrap_or_else(|| format!("{nnn:#05X}"))
+    };
+    match instruction {
+        Instruction::ClearScreen => String::from("clear"),
+        Instruction::Return => String::from("return"),
+        Instruction::Syscall(nnn) => format!("native {nnn:#05X}"),
+        Instruction::Jump(nnn) => format!("jump {}", address(nnn)),
+        Instruction::Call(nnn) if labels.contains_key(&usize::from(nnn)) => address(nnn),
+        Instruction::Call(nnn) => format!(":call {nnn:#05X}"),
+        Instruction::SkipIfEqual { x, kk } => format!("if v{x:X} != {kk:#04X} then"),
+        Instruction::SkipIfNotEqual { x, kk } => format!("if v{x:X} == {kk:#04X} then"),
+        Instruction::SkipIfRegEqual { x, y } => format!("if v{x:X} != v{y:X} then"),
+        Instruction::Load { x, kk } => format!("v{x:X} := {kk:#04X}"),
+        Instruction::Add { x, kk } => format!("v{x:X} += {kk:#04X}"),
+        Instruction::LoadReg { x, y } => format!("v{x:X} := v{y:X}"),
+        Instruction::Or { x, y } => format!("v{x:X} |= v{y:X}"),
+        Instruction::And { x, y } => format!("v{x:X} &= v{y:X}"),
+        Instruction::Xor { x, y } => format!("v{x:X} ^= v{y:X}"),
+        Instruction::AddReg { x, y } => format!("v{x:X} += v{y:X}"),
+        Instruction::SubReg { x, y } => format!("v{x:X} -= v{y:X}"),
+        Instruction::ShiftRight { x, y } => format!("v{x:X} >>= v{y:X}"),
+        Instruction::SubN { x, y } => format!("v{x:X} =- v{y:X}"),
+        Instruction::ShiftLeft { x, y } => format!("v{x:X} <<= v{y:X}"),
+        Instruction::SkipIfRegNotEqual { x, y } => format!("if v{x:X} == v{y:X} then"),
+        Instruction::LoadI(nnn) => format!("i := {}", address(nnn)),
+        Instruction::JumpOffset(nnn) => format!("jump0 {}", address(nnn)),
+        Instruction::Random { x, kk } => format!("v{x:X} := random {kk:#04X}"),
+        Instruction::Draw { x, y, n } => format!("sprite v{x:X} v{y:X} {n}"),
+        Instruction::SkipIfKey { x } => format!("if v{x:X} -key then"),
+        Instruction::SkipIfNotKey { x } => format!("if v{x:X} key then"),
+        Instruction::LoadDelayTimer { x } => format!("v{x:X} := delay"),
+        Instruction::WaitKey { x } => format!("v{x:X} := key"),
+        Instruction::SetDelayTimer { x } => format!("delay := v{x:X}"),
+        Instruction::SetSoundTimer { x } => format!("buzzer := v{x:X}"),
+        Instruction::AddI { x } => format!("i += v{x:X}"),
+        Instruction::LoadDigit { x } => format!("i := hex v{x:X}"),
+        Instruction::StoreBcd { x } => format!("bcd v{x:X}"),
+        Instruction::Store { x } => format!("save v{x:X}"),
+        Instruction::Restore { x } => format!("load v{x:X}"),
+    }
+}
+
+/// An instruction to emit, with an operand that may refer to a label.
+type Emit = (Instruction, Option<(Operand, Value)>);
+
+/// A condition, as the instructions that skip the next instruction when it is false or true.
+struct Condition {
+    skip_if_false: Emit,
+    skip_if_true: Emit,
+}
+
+/// A block that is still open.
+enum Block {
+    /// `if ... begin`, with the address of the jump to its `else` or `end`.
+    If(u16),
+    /// `else`, with the address of the jump to its `end`.
+    Else(u16),
+    /// `loop`, with its address and the addresses of the jumps of its `while`s.
+    Loop(u16, Vec<u16>),
+}
+
+struct Parser<'a> {
+    tokens: Vec<(&'a str, usize)>,
+    position: usize,
+    program: Program,
+    aliases: HashMap<String, u8>,
+    constants: HashMap<String, u16>,
+    blocks: Vec<Block>,
+    has_main: bool,
+}
+
+impl<'a> Parser<'a> {
+    fn statement(&mut self) -> Result<()> {
+        let (token, line) = self.next()?;
+        match token {
+            ":" => {
+                let name = self.name()?;
+                self.has_main |= name == "main";
+                self.program.define(&name, line)?;
+            }
+            ":alias" => {
+                let name = self.name()?;
+                let x = self.register()?;
+                self.aliases.insert(name, x);
+            }
+            ":const" => {
+                let name = self.name()?;
+                let Value::Number(value) = self.value()? else {
+                    return self.fail("expected a number or a constant");
+                };
+                self.constants.insert(name, value);
+            }
+            ":call" => {
+                let address = self.value()?;
+                self.emit((Instruction::Call(0), Some((Operand::Nnn, address))))?;
+            }
+            ":byte" => {
+                let value = self.value()?;
+                self.program.byte(value, line)?;
+            }
+            "return" | ";" => self.emit((Instruction::Return, None))?,
+            "clear" => self.emit((Instruction::ClearScreen, None))?,
+            "jump" | "jump0" | "native" => {
+                let address = self.value()?;
+                let instruction = match token {
+                    "jump" => Instruction::Jump(0),
+                    "jump0" => Instruction::JumpOffset(0),
+                    _ => Instruction::Syscall(0),
+                };
+                self.emit((instruction, Some((Operand::Nnn, address))))?;
+            }
+            "sprite" => {
+                let x = self.register()?;
+                let y = self.register()?;
+                let n = self.value()?;
+                self.emit((Instruction::Draw { x, y, n: 0 }, Some((Operand::N, n))))?;
+            }
+            "save" | "load" | "bcd" => {
+                let x = self.register()?;
+                let instruction = match token {
+                    "save" => Instruction::Store { x },
+                    "load" => Instruction::Restore { x },
+                    _ => Instruction::StoreBcd { x },
+                };
+                self.emit((instruction, None))?;
+            }
+            "delay" | "buzzer" => {
+                self.expect(":=")?;
+                let x = self.register()?;
+                let instruction = if token == "delay" {
+                    Instruction::SetDelayTimer { x }
+                } else {
+                    Instruction::SetSoundTimer { x }
+                };
+                self.emit((instruction, None))?;
+            }
+            "i" => {
+                let emit = match self.next()?.0 {
+                    ":=" if self.peek() == Some("hex") => {
+                        self.position += 1;
+                        (Instruction::LoadDigit { x: self.register()? }, None)
+                    }
+                    ":=" => (Instruction::LoadI(0), Some((Operand::Nnn, self.value()?))),
+                    "+=" => (Instruction::AddI { x: self.register()? }, None),
+                    _ => return self.fail("expected `:=` or `+=`"),
+                };
+                self.emit(emit)?;
+            }
+            "if" => {
+                let condition = self.condition()?;
+                match self.next()?.0 {
+                    "then" => {
+                        self.emit(condition.skip_if_false)?;
+                        self.statement()?;
+                    }
+                    "begin" => {
+                        self.emit(condition.skip_if_true)?;
+                        let jump = self.jump_forward()?;
+                        self.blocks.push(Block::If(jump));
+                    }
+                    _ => return self.fail("expected `then` or `begin`"),
+                }
+            }
+            "else" => {
+                let Some(Block::If(jump)) = self.blocks.pop() else {
+                    return self.fail("`else` without `if ... begin`");
+                };
+                let end = self.jump_forward()?;
+                self.program.resolve_jump(jump, line)?;
+                self.blocks.push(Block::Else(end));
+            }
+            "end" => match self.blocks.pop() {
+                Some(Block::If(jump) | Block::Else(jump)) => {
+                    self.program.resolve_jump(jump, line)?
+                }
+                _ => return self.fail("`end` without `if ... begin`"),
+            },
+            "loop" => {
+                let start = self.program.address();
+                self.blocks.push(Block::Loop(start, Vec::new()));
+            }
+            "while" => {
+                let condition = self.condition()?;
+                self.emit(condition.skip_if_true)?;
+                let jump = self.jump_forward()?;
+                match self.blocks.iter_mut().rev().find(|block| matches!(block, Block::Loop(..))) {
+                    Some(Block::Loop(_, breaks)) => breaks.push(jump),
+                    _ => return self.fail("`while` outside of `loop`"),
+                }
+            }
+            "again" => {
+                let Some(Block::Loop(start, breaks)) = self.blocks.pop() else {
+                    return self.fail("`again` without `loop`");
+                };
+                self.emit((Instruction::Jump(0), Some((Operand::Nnn, Value::Number(start)))))?;
+                for jump in breaks {
+                    self.program.resolve_jump(jump, line)?;
+                }
+            }
+            _ if self.is_register(token) => {
+                self.position -= 1;
+                self.register_statement()?;
+            }
+            _ => {
+                self.position -= 1;
+                match self.value()? {
+                    Value::Number(byte) => self.program.byte(Value::Number(byte), line)?,
+                    label => self.emit((Instruction::Call(0), Some((Operand::Nnn, label))))?,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses a statement that starts with a register, such as `v0 += v1`.
+    fn register_statement(&mut self) -> Result<()> {
+        let x = self.register()?;
+        let operator = self.next()?.0;
+        let y = match self.peek() {
+            Some(token) if self.is_register(token) => Some(self.register()?),
+            _ => None,
+        };
+        let emit = match (operator, y) {
+            (":=", Some(y)) => (Instruction::LoadReg { x, y }, None),
+            (":=", None) => match self.peek() {
+                Some("delay") => {
+                    self.position += 1;
+                    (Instruction::LoadDelayTimer { x }, None)
+                }
+                Some("key") => {
+                    self.position += 1;
+                    (Instruction::WaitKey { x }, None)
+                }
+                Some("random") => {
+                    self.position += 1;
+                    (Instruction::Random { x, kk: 0 }, Some((Operand::Kk, self.value()?)))
+                }
+                _ => (Instruction::Load { x, kk: 0 }, Some((Operand::Kk, self.value()?))),
+            },
+            ("+=", Some(y)) => (Instruction::AddReg { x, y }, None),
+            ("+=", None) => (Instruction::Add { x, kk: 0 }, Some((Operand::Kk, self.value()?))),
+            ("-=", Some(y)) => (Instruction::SubReg { x, y }, None),
+            ("-=", None) => {
+                let kk = match self.value()? {
+                    Value::Number(value) if value <= 0xFF => 0u8.wrapping_sub(value as u8),
+                    _ => return self.fail("expected a byte"),
+                };
+                (Instruction::Add { x, kk }, None)
+            }
+            ("=-", Some(y)) => (Instruction::SubN { x, y }, None),
+            ("|=", Some(y)) => (Instruction::Or { x, y }, None),
+            ("&=", Some(y)) => (Instruction::And { x, y }, None),
+            ("^=", Some(y)) => (Instruction::Xor { x, y }, None),
+            (">>=", Some(y)) => (Instruction::ShiftRight { x, y }, None),
+            ("<<=", Some(y)) => (Instruction::ShiftLeft { x, y }, None),
+            _ => return self.fail("unknown operator or invalid operand"),
+        };
+        self.emit(emit)
+    }
+
+    /// Parses a condition, such as `v0 == 5` or `v1 -key`.
+    fn condition(&mut self) -> Result<Condition> {
+        let x = self.register()?;
+        let operator = self.next()?.0;
+        let (equal, not_equal) = match operator {
+            "key" | "-key" => {
+                let pressed = (Instruction::SkipIfKey { x }, None);
+                let not_pressed = (Instruction::SkipIfNotKey { x }, None);
+                return Ok(if operator == "key" {
+                    Condition { skip_if_false: not_pressed, skip_if_true: pressed }
+                } else {
+                    Condition { skip_if_false: pressed, skip_if_true: not_pressed }
+                });
+            }
+            "==" | "!=" => match self.peek() {
+                Some(token) if self.is_register(token) => {
+                    let y = self.register()?;
+                    (
+                        (Instruction::SkipIfRegEqual { x, y }, None),
+                        (Instruction::SkipIfRegNotEqual { x, y }, None),
+                    )
+                }
+                _ => {
+                    let kk = self.value()?;
+                    (
+                        (Instruction::SkipIfEqual { x, kk: 0 }, Some((Operand::Kk, kk.clone()))),
+                        (Instruction::SkipIfNotEqual { x, kk: 0 }, Some((Operand::Kk, kk))),
+                    )
+                }
+            },
+            _ => return self.fail("expected `==`, `!=`, `key`, or `-key`"),
+        };
+        Ok(if operator == "==" {
+            Condition { skip_if_false: not_equal, skip_if_true: equal }
+        } else {
+            Condition { skip_if_false: equal, skip_if_true: not_equal }
+        })
+    }
+
+    /// Emits a jump without a target, returning its address for `Program::resolve_jump`.
+    fn jump_forward(&mut self) -> Result<u16> {
+        let address = self.program.address();
+        self.emit((Instruction::Jump(0), None))?;
+        Ok(address)
+    }
+
+    fn emit(&mut self, (instruction, operand): Emit) -> Result<()> {
+        let line = self.line();
+        self.program.instruction(instruction, operand, line)
+    }
+
+    fn next(&mut self) -> Result<(&'a str, usize)> {
+        let line = self.line();
+        let token = self
+            .tokens
+            .get(self.position)
+            .context(InvalidAssemblySnafu { line, reason: "unexpected end" })?;
+        self.position += 1;
+        Ok(*token)
+    }
+
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.position).map(|&(token, _)| token)
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<()> {
+        if self.next()?.0 != expected {
+            return self.fail("unexpected token");
+        }
+        Ok(())
+    }
+
+    fn is_register(&self, token: &str) -> bool {
+        self.aliases.contains_key(token) || register(token).is_some()
+    }
+
+    fn register(&mut self) -> Result<u8> {
+        let token = self.next()?.0;
+        match self.aliases.get(token).copied().or_else(|| register(token)) {
+            Some(x) => Ok(x),
+            None => self.fail("expected a register"),
+        }
+    }
+
+    fn name(&mut self) -> Result<String> {
+        let token = self.next()?.0;
+        if !is_name(token) {
+            return self.fail("expected a name");
+        }
+        Ok(token.to_string())
+    }
+
+    /// Parses a number, a constant, or a label.
+    fn value(&mut self) -> Result<Value> {
+        let token = self.next()?.0;
+        let (negative, digits) = match token.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, token),
+        };
+        let number = if let Some(hex) = digits.strip_prefix("0x") {
+            u16::from_str_radix(hex, 16).ok()
+        } else if let Some(binary) = digits.strip_prefix("0b") {
+            u16::from_str_radix(binary, 2).ok()
+        } else if digits.starts_with(|c: char| c.is_ascii_digit()) {
+            digits.parse().ok()
+        } else if let Some(&value) = self.constants.get(token) {
+            return Ok(Value::Number(value));
+        } else if is_name(token) && !self.is_register(token) {
+            return Ok(label(token));
+        } else {
+            return self.fail("expected a number, a constant, or a label");
+        };
+        match number {
+            // Negative numbers are bytes in two's complement, such as -1 for 0xFF.
+            Some(number) if negative && number <= 0x80 => {
+                Ok(Value::Number((0x100 - number) & 0xFF))
+            }
+            Some(number) if !negative => Ok(Value::Number(number)),
+            _ => self.fail("the number is out of range"),
+        }
+    }
+
+    /// Returns the line of the current token.
+    fn line(&self) -> usize {
+        let index = self.position.min(self.tokens.len()).saturating_sub(1);
+        self.tokens.get(index).map_or(1, |&(_, line)| line)
+    }
+
+    fn fail<T>(&self, reason: &'static str) -> Result<T> {
+        InvalidAssemblySnafu { line: self.line(), reason }.fail()
+    }
+}
+
+/// Returns true if `: main` comes before any label, instruction, or byte, in which case the program
+/// starts at `main` and needs no jump to it.
+fn starts_with_main(tokens: &[(&str, usize)]) -> bool {
+    let mut tokens = tokens.iter().map(|&(token, _)| token);
+    loop {
+        match tokens.next() {
+            // Aliases and constants take two more tokens and emit nothing.
+            Some(":alias" | ":const") => {
+                tokens.nth(1);
+            }
+            Some(":") => return tokens.next() == Some("main"),
+            _ => return false,
+        }
+    }
+}
+
+fn label(name: &str) -> Value {
+    Value::Label(name.to_string())
+}
+
+fn register(token: &str) -> Option<u8> {
+    let x = token.strip_prefix(['v', 'V'])?;
+    if x.len() != 1 {
+        return None;
+    }
+    u8::from_str_radix(x, 16).ok()
+}
+
+/// Returns true if `name` can name a label, an alias, or a constant.
+fn is_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
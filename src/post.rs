@@ -0,0 +1,68 @@
+//! Post-processing of screens and beeps for output, shared by every frontend.
+
+use core::time::Duration;
+
+use crate::Screen;
+
+/// Emulates the ghosting of phosphor displays, which reduces the flicker of CHIP-8 programs that
+/// erase and redraw sprites every frame.
+///
+/// Each frame shows the pixels that are lit on either the current screen or the previous one.
+#[derive(Clone, Debug, Default)]
+pub struct Phosphor {
+    previous: Screen,
+}
+
+impl Phosphor {
+    /// Returns a phosphor simulation whose previous screen is black.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the frame to display for `screen`, blended with the previous screen.
+    pub fn process(&mut self, screen: &Screen) -> Screen {
+        let mut frame = self.previous;
+        frame |= screen;
+        self.previous = *screen;
+        frame
+    }
+}
+
+/// Stretches beeps to a minimum duration, so that those of a sound timer set to 1 or 2 are heard
+/// as beeps rather than as clicks or not at all.
+#[derive(Clone, Debug, Default)]
+pub struct BeepGate {
+    min_duration: Duration,
+    beeping: bool,
+    remaining: Duration,
+}
+
+impl BeepGate {
+    /// Returns a gate that keeps each beep on for at least `min_duration`.
+    pub fn new(min_duration: Duration) -> Self {
+        Self { min_duration, ..Self::default() }
+    }
+
+    /// Returns whether to beep for the next `elapsed` time, such as a frame, given whether the
+    /// interpreter beeps.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// let mut gate = chip8::post::BeepGate::new(Duration::from_millis(50));
+    /// let frame = Duration::from_millis(20);
+    /// assert!(gate.process(true, frame));
+    /// assert!(gate.process(false, frame));
+    /// assert!(gate.process(false, frame));
+    /// assert!(!gate.process(false, frame));
+    /// ```
+    pub fn process(&mut self, beeping: bool, elapsed: Duration) -> bool {
+        if beeping && !self.beeping {
+            self.remaining = self.min_duration;
+        }
+        self.beeping = beeping;
+        let gated = beeping || !self.remaining.is_zero();
+        self.remaining = self.remaining.saturating_sub(elapsed);
+        gated
+    }
+}
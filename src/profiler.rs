@@ -0,0 +1,83 @@
+//! Counting how often instructions are executed, for finding hot loops.
+
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+    vec::Vec,
+};
+
+use crate::{isa, StepInfo};
+
+/// How many addresses `Report` displays.
+const TOP_ADDRESSES: usize = 20;
+
+/// Counts the instructions executed per opcode and per address, recorded from what
+/// `Chip8::fetch_execute_cycle` returns.
+#[derive(Clone, Debug, Default)]
+pub struct Profiler {
+    by_opcode: HashMap<&'static str, u64>,
+    by_address: HashMap<usize, u64>,
+    total: u64,
+}
+
+impl Profiler {
+    /// Returns a profiler that has counted nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts the instruction executed by a step, if any.
+    pub fn record(&mut self, step: &StepInfo) {
+        let Some(instruction) = step.instruction else {
+            return;
+        };
+        if let Some(opcode) = isa::lookup(instruction.encode()) {
+            *self.by_opcode.entry(opcode.pattern).or_default() += 1;
+        }
+        *self.by_address.entry(step.pc).or_default() += 1;
+        self.total += 1;
+    }
+
+    /// Returns how many instructions have been counted.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Returns the counts, the most executed first.
+    pub fn report(&self) -> Report {
+        let mut opcodes: Vec<_> =
+            self.by_opcode.iter().map(|(&opcode, &count)| (opcode, count)).collect();
+        opcodes.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        let mut addresses: Vec<_> =
+            self.by_address.iter().map(|(&address, &count)| (address, count)).collect();
+        addresses.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        Report { total: self.total, opcodes, addresses }
+    }
+}
+
+/// The counts of a profiler, the most executed first.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Report {
+    pub total: u64,
+    /// The opcodes, as patterns such as `8xy4`, and how many times they were executed.
+    pub opcodes: Vec<(&'static str, u64)>,
+    /// The addresses of instructions and how many times they were executed.
+    pub addresses: Vec<(usize, u64)>,
+}
+
+impl Display for Report {
+    /// Formats the counts of every opcode and of the 20 most executed addresses as a table.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let percent = |count: u64| 100.0 * count as f64 / self.total.max(1) as f64;
+        writeln!(f, "Instructions executed: {}", self.total)?;
+        writeln!(f, "By opcode:")?;
+        for &(opcode, count) in &self.opcodes {
+            writeln!(f, "  {opcode}  {count:>12}  {:>5.1}%", percent(count))?;
+        }
+        writeln!(f, "By address:")?;
+        for &(address, count) in self.addresses.iter().take(TOP_ADDRESSES) {
+            writeln!(f, "  {address:#05X}  {count:>11}  {:>5.1}%", percent(count))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,236 @@
+//! Rewinding an interpreter to earlier states.
+
+use alloc::{collections::VecDeque, vec::Vec};
+use core::mem;
+
+use crate::{instruction::Instruction, Chip8, KeyWait, Result, StepInfo};
+
+/// A ring buffer of snapshots (in the `save_state` format) taken every few frames, which lets a
+/// frontend step an interpreter back in time.
+///
+/// ```
+/// # fn main() -> Result<(), chip8::Error> {
+/// let mut chip8 = chip8::Chip8::from_bytes(&[0x70, 0x01, 0x12, 0x00], Default::default())?;
+/// let mut rewind = chip8::rewind::RewindBuffer::new(1, 60);
+/// for _ in 0..10 {
+///     chip8.fetch_execute_cycle()?;
+///     rewind.record(&chip8)?;
+/// }
+/// rewind.step_back(&mut chip8)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct RewindBuffer {
+    interval: u32,
+    capacity: usize,
+    frames_until_snapshot: u32,
+    snapshots: VecDeque<Vec<u8>>,
+}
+
+impl RewindBuffer {
+    /// Returns an empty buffer that keeps a snapshot every `interval` frames, discarding the
+    /// oldest ones beyond `capacity`.
+    pub fn new(interval: u32, capacity: usize) -> Self {
+        Self {
+            interval: interval.max(1),
+            capacity,
+            frames_until_snapshot: 0,
+            snapshots: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Counts a frame, taking a snapshot of `chip8` if it is time to. Call this once per frame.
+    pub fn record(&mut self, chip8: &Chip8) -> Result<()> {
+        if self.frames_until_snapshot > 0 {
+            self.frames_until_snapshot -= 1;
+            return Ok(());
+        }
+        self.frames_until_snapshot = self.interval - 1;
+        if self.capacity == 0 {
+            return Ok(());
+        }
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(chip8.save_state_bytes());
+        Ok(())
+    }
+
+    /// Restores `chip8` to the latest snapshot and discards it, so that repeated calls go further
+    /// back. Returns false, leaving `chip8` untouched, if there are no snapshots left.
+    pub fn step_back(&mut self, chip8: &mut Chip8) -> Result<bool> {
+        let Some(snapshot) = self.snapshots.pop_back() else {
+            return Ok(false);
+        };
+        chip8.load_state_bytes(&snapshot)?;
+        self.frames_until_snapshot = self.interval - 1;
+        Ok(true)
+    }
+
+    /// Returns the number of snapshots that can be stepped back to.
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    /// Returns true if there are no snapshots to step back to.
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    /// Discards every snapshot, such as after loading another save state.
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+        self.frames_until_snapshot = 0;
+    }
+}
+
+/// Executes an interpreter one instruction at a time while keeping what it takes to undo the steps,
+/// for debuggers that step backwards, such as to find out how VF became 1.
+///
+/// A snapshot is taken every `interval` steps. Stepping back restores the latest snapshot and
+/// replays the steps after it but the last, with the keys as they were at each step and the
+/// numbers that Cxkk drew, and without calling the hooks or checking the breakpoints. Running the
+/// interpreter other than with `step`, such as with `Chip8::run_frame`, or loading a state makes
+/// the history wrong, so call `clear` afterwards.
+///
+/// ```
+/// # fn main() -> Result<(), chip8::Error> {
+/// let rom = [0xC0, 0xFF, 0x70, 0x01, 0x12, 0x02]; // RND V0, 0xFF; ADD V0, 0x01; JP 0x202
+/// let mut chip8 = chip8::Chip8::from_bytes(&rom, Default::default())?;
+/// let mut stepper = chip8::rewind::ReverseStepper::new(4, 16);
+/// stepper.step(&mut chip8)?;
+/// let random = chip8.v()[0];
+/// for _ in 0..8 {
+///     stepper.step(&mut chip8)?;
+/// }
+/// for _ in 0..7 {
+///     assert!(stepper.step_back(&mut chip8)?);
+/// }
+/// assert_eq!((chip8.pc(), chip8.v()[0]), (0x204, random.wrapping_add(1)));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ReverseStepper {
+    interval: usize,
+    capacity: usize,
+    /// The snapshots, the oldest first, each with the steps taken after it.
+    history: VecDeque<(Vec<u8>, Vec<RecordedStep>)>,
+}
+
+/// What replaying a step needs besides the state before it.
+#[derive(Clone, Copy, Debug)]
+struct RecordedStep {
+    keys: KeyState,
+    /// The register that Cxkk set and the value it set, if the step executed Cxkk.
+    random: Option<(usize, u8)>,
+}
+
+/// The part of the state of an interpreter that pressing and releasing keys changes.
+#[derive(Clone, Copy, Debug)]
+struct KeyState {
+    is_key_pressed: [bool; 16],
+    key_latch_remaining: [u32; 16],
+    is_key_release_pending: [bool; 16],
+    key_wait: KeyWait,
+}
+
+impl ReverseStepper {
+    /// Returns a stepper that takes a snapshot every `interval` steps, discarding the oldest ones
+    /// beyond `capacity`. Stepping back replays up to `interval - 1` steps.
+    pub fn new(interval: usize, capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self { interval: interval.max(1), capacity, history: VecDeque::with_capacity(capacity) }
+    }
+
+    /// Executes the next instruction with `Chip8::fetch_execute_cycle`, recording the step.
+    pub fn step(&mut self, chip8: &mut Chip8) -> Result<StepInfo> {
+        if !matches!(self.history.back(), Some((_, steps)) if steps.len() < self.interval) {
+            if self.history.len() == self.capacity {
+                self.history.pop_front();
+            }
+            self.history.push_back((chip8.save_state_bytes(), Vec::new()));
+        }
+        let keys = KeyState::of(chip8);
+        let step = chip8.fetch_execute_cycle()?;
+        // A step paused before its instruction changes nothing to undo.
+        if step.paused && step.instruction.is_none() {
+            return Ok(step);
+        }
+        let random = match step.instruction {
+            Some(Instruction::Random { x, .. }) => Some((usize::from(x), chip8.v[usize::from(x)])),
+            _ => None,
+        };
+        if let Some((_, steps)) = self.history.back_mut() {
+            steps.push(RecordedStep { keys, random });
+        }
+        Ok(step)
+    }
+
+    /// Restores `chip8` to the state before the last step and forgets the step, so that repeated
+    /// calls go further back. Returns false, leaving `chip8` untouched, if there are no steps left.
+    pub fn step_back(&mut self, chip8: &mut Chip8) -> Result<bool> {
+        loop {
+            let Some((_, steps)) = self.history.back_mut() else {
+                return Ok(false);
+            };
+            if steps.pop().is_some() {
+                break;
+            }
+            self.history.pop_back();
+        }
+        let Some((snapshot, steps)) = self.history.back() else {
+            return Ok(false);
+        };
+        chip8.load_state_bytes(snapshot)?;
+        // The hooks and the trace are left as they are now, less the step undone.
+        let hooks = mem::take(&mut chip8.hooks);
+        let mut trace = mem::take(&mut chip8.trace);
+        let replayed = steps.iter().try_for_each(|step| {
+            step.keys.restore(chip8);
+            chip8.fetch_execute_cycle()?;
+            if let Some((x, value)) = step.random {
+                chip8.v[x] = value;
+            }
+            Ok(())
+        });
+        chip8.hooks = hooks;
+        trace.pop_back();
+        chip8.trace = trace;
+        replayed.map(|()| true)
+    }
+
+    /// Returns the number of steps that can be undone.
+    pub fn len(&self) -> usize {
+        self.history.iter().map(|(_, steps)| steps.len()).sum()
+    }
+
+    /// Returns true if there are no steps to undo.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forgets every step, such as after running the interpreter otherwise.
+    pub fn clear(&mut self) {
+        self.history.clear();
+    }
+}
+
+impl KeyState {
+    fn of(chip8: &Chip8) -> Self {
+        Self {
+            is_key_pressed: chip8.is_key_pressed,
+            key_latch_remaining: chip8.key_latch_remaining,
+            is_key_release_pending: chip8.is_key_release_pending,
+            key_wait: chip8.key_wait,
+        }
+    }
+
+    fn restore(&self, chip8: &mut Chip8) {
+        chip8.is_key_pressed = self.is_key_pressed;
+        chip8.key_latch_remaining = self.key_latch_remaining;
+        chip8.is_key_release_pending = self.is_key_release_pending;
+        chip8.key_wait = self.key_wait;
+    }
+}
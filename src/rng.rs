@@ -0,0 +1,61 @@
+//! Random number generators for Cxkk.
+
+use alloc::boxed::Box;
+use core::fmt::Debug;
+
+/// A source of random bytes for Cxkk.
+pub trait RngSource: Debug + Send {
+    /// Returns a random byte.
+    fn random_byte(&mut self) -> u8;
+}
+
+/// A non-deterministic generator backed by the thread-local generator of the `rand` crate, which is
+/// used by default with the `std` feature.
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+pub struct ThreadRng;
+
+#[cfg(feature = "std")]
+impl RngSource for ThreadRng {
+    fn random_byte(&mut self) -> u8 {
+        rand::random()
+    }
+}
+
+/// A deterministic generator that produces the same sequence for the same seed on every platform
+/// and with every version of this crate, which makes runs reproducible for replays and tests.
+///
+/// It is an implementation of SplitMix64.
+#[derive(Clone, Debug)]
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+}
+
+impl RngSource for SeededRng {
+    fn random_byte(&mut self) -> u8 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        (z ^ (z >> 31)) as u8
+    }
+}
+
+/// Returns the generator that interpreters use unless told otherwise.
+#[cfg(feature = "std")]
+pub(crate) fn default() -> Box<dyn RngSource> {
+    Box::new(ThreadRng)
+}
+
+/// Returns the generator that interpreters use unless told otherwise, which is seeded with 0 for
+/// lack of a source of entropy without the `std` feature.
+#[cfg(not(feature = "std"))]
+pub(crate) fn default() -> Box<dyn RngSource> {
+    Box::new(SeededRng::new(0))
+}
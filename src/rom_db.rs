@@ -0,0 +1,71 @@
+//! A small database of known ROMs and the settings they run best with, modeled on the
+//! [CHIP-8 database](https://github.com/chip-8/chip-8-database) of the CHIP-8 community.
+//!
+//! ROMs are identified by `save_state::rom_hash` rather than by the SHA-1 hashes of the community
+//! database, so that no hashing crate is needed.
+
+use crate::{save_state, Quirks};
+
+/// What is known about a ROM.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RomInfo {
+    /// The hash of the ROM (see `save_state::rom_hash`).
+    pub hash: u64,
+    pub title: &'static str,
+    /// The quirks the ROM was written for.
+    pub quirks: Quirks,
+    /// How many instructions per second the ROM runs best at, if it matters.
+    pub cpu_speed: Option<u32>,
+    /// How the ROM is played with the hex keyboard, or an empty string if unknown.
+    pub keys: &'static str,
+}
+
+const ROMS: &[RomInfo] = &[
+    RomInfo {
+        hash: 0x19FA_1EDF_40FA_D0AF,
+        title: "BC_Chip8Test (2011) [BestCoder]",
+        quirks: Quirks::DEFAULT,
+        cpu_speed: None,
+        keys: "",
+    },
+    RomInfo {
+        hash: 0x258F_2C95_D6AD_ADC2,
+        title: "Worm V4 (2007) [RB, Revival Studios]",
+        quirks: Quirks::DEFAULT,
+        cpu_speed: None,
+        keys: "",
+    },
+    RomInfo {
+        hash: 0x4A23_7025_C527_A4D0,
+        title: "Astro Dodge (2008) [Revival Studios]",
+        quirks: Quirks::COSMAC_VIP,
+        cpu_speed: None,
+        keys: "2, 4, 6, and 8 move the ship; 5 starts the game",
+    },
+    RomInfo {
+        hash: 0xF23F_0301_3DC7_DF4F,
+        title: "Trip8 Demo (2008) [Revival Studios]",
+        quirks: Quirks::COSMAC_VIP,
+        cpu_speed: None,
+        keys: "",
+    },
+];
+
+impl RomInfo {
+    /// Returns what is known about a ROM, or `None` if it is not in the database.
+    ///
+    /// ```no_run
+    /// use chip8::{rom_db::RomInfo, Quirks};
+    ///
+    /// let rom = std::fs::read(
+    ///     "resources/RS-C8003 - Astro Dodge (2008)/Astro Dodge (2008) [Revival Studios].ch8",
+    /// )?;
+    /// let info = RomInfo::lookup(&rom).unwrap();
+    /// assert_eq!(info.quirks, Quirks::COSMAC_VIP);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn lookup(program: &[u8]) -> Option<&'static RomInfo> {
+        let hash = save_state::rom_hash(program);
+        ROMS.iter().find(|info| info.hash == hash)
+    }
+}
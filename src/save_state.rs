@@ -0,0 +1,278 @@
+//! A compact binary format for the state of an interpreter, which stays loadable across versions of
+//! this crate.
+//!
+//! A save state starts with the magic number `C8ST`, the version of the format, and a hash of the
+//! ROM that the interpreter was created with, followed by the state. All numbers are big-endian.
+//! Settings that are not part of the state, such as the quirks and the random number generator,
+//! are neither saved nor loaded.
+//!
+//! `Chip8::state_hash` hashes the part of the state that programs can observe, for comparing
+//! states without saving them.
+
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io::{Read, Write};
+
+#[cfg(feature = "std")]
+use snafu::ResultExt;
+use snafu::{ensure, OptionExt};
+
+#[cfg(feature = "std")]
+use crate::IoSnafu;
+use crate::{
+    Chip8, Color, InvalidSaveStateSnafu, KeyWait, PixelLayout, Result, SaveStateRomMismatchSnafu,
+    Screen, UnsupportedSaveStateVersionSnafu,
+};
+
+const MAGIC: [u8; 4] = *b"C8ST";
+
+/// The version of the format, which changes whenever the layout of the state changes.
+///
+/// Version 2 added the size of the screen before its pixels. Version 1 states are still loaded,
+/// with the screen of the size that it has when they are.
+const VERSION: u16 = 2;
+
+/// Returns the hash of a ROM that save states record, which is the 64-bit FNV-1a hash of its bytes.
+pub fn rom_hash(program: &[u8]) -> u64 {
+    let mut hasher = Fnv1a::new();
+    hasher.write(program);
+    hasher.0
+}
+
+/// The 64-bit FNV-1a hash, which is stable across platforms and versions of Rust, unlike
+/// `core::hash::Hasher` implementations.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Self {
+        Self(0xCBF2_9CE4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01B3);
+        }
+    }
+}
+
+impl Chip8 {
+    /// Writes the state of this interpreter in the save state format.
+    #[cfg(feature = "std")]
+    pub fn save_state<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_all(&self.save_state_bytes()).context(IoSnafu)
+    }
+
+    /// Replaces the state of this interpreter with one written by `save_state`, failing without
+    /// changing anything if it is malformed or was saved with another ROM.
+    #[cfg(feature = "std")]
+    pub fn load_state<R: Read>(&mut self, reader: &mut R) -> Result<()> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).context(IoSnafu)?;
+        self.load_state_bytes(&bytes)
+    }
+
+    /// Returns a 64-bit hash of the state that programs can observe: the memory, the program
+    /// counter, the registers, the call stack, the timers, and the screen. The hash does not
+    /// change across runs, platforms, or versions of this crate, so that states can be compared
+    /// cheaply for netplay, replays, and testing against other emulators. Keys, cycle counts, and
+    /// settings are not hashed.
+    ///
+    /// ```
+    /// # fn main() -> Result<(), chip8::Error> {
+    /// let rom = [0xC0, 0xFF, 0x70, 0x01, 0x12, 0x00]; // RND V0, 0xFF; ADD V0, 0x01; JP 0x200
+    /// let mut a = chip8::Chip8::builder().rom(&rom).seed(7).build()?;
+    /// let mut b = chip8::Chip8::builder().rom(&rom).seed(7).build()?;
+    /// a.run_frame(10)?;
+    /// b.run_frame(10)?;
+    /// assert_eq!(a.state_hash(), b.state_hash());
+    /// b.run_frame(1)?;
+    /// assert_ne!(a.state_hash(), b.state_hash());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = Fnv1a::new();
+        hasher.write(&self.ram);
+        hasher.write(&(self.pc as u32).to_be_bytes());
+        hasher.write(&self.v);
+        hasher.write(&self.i.to_be_bytes());
+        hasher.write(&(self.call_stack.len() as u32).to_be_bytes());
+        for &address in &self.call_stack {
+            hasher.write(&(address as u32).to_be_bytes());
+        }
+        hasher.write(&[self.timers.delay_timer, self.timers.sound_timer]);
+        hasher.write(&(self.screen.width() as u32).to_be_bytes());
+        hasher.write(&(self.screen.height() as u32).to_be_bytes());
+        hasher.write(&self.screen.to_packed_1bpp());
+        hasher.0
+    }
+
+    /// Returns the state of this interpreter in the save state format, which records the size of
+    /// the screen:
+    ///
+    /// ```
+    /// # fn main() -> Result<(), chip8::Error> {
+    /// let mut chip8 = chip8::Chip8::from_bytes(&[], Default::default())?;
+    /// chip8.screen = chip8::Screen::with_size(128, 64);
+    /// let mut other = chip8::Chip8::from_bytes(&[], Default::default())?;
+    /// other.load_state_bytes(&chip8.save_state_bytes())?;
+    /// assert_eq!((other.screen.width(), other.screen.height()), (128, 64));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn save_state_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.ram.len() + 512);
+        bytes.extend_from_slice(&MAGIC);
+        bytes.extend_from_slice(&VERSION.to_be_bytes());
+        bytes.extend_from_slice(&self.rom_hash.to_be_bytes());
+
+        bytes.extend_from_slice(&(self.ram.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&self.ram);
+        bytes.extend_from_slice(&(self.pc as u32).to_be_bytes());
+        bytes.extend_from_slice(&self.v);
+        bytes.extend_from_slice(&self.i.to_be_bytes());
+        bytes.extend_from_slice(&(self.call_stack.len() as u32).to_be_bytes());
+        for &address in &self.call_stack {
+            bytes.extend_from_slice(&(address as u32).to_be_bytes());
+        }
+        bytes.extend_from_slice(&(self.call_stack_high_water_mark as u32).to_be_bytes());
+        bytes.extend_from_slice(&[self.timers.delay_timer, self.timers.sound_timer]);
+        for key in 0..16 {
+            bytes.push(u8::from(self.is_key_pressed[key]));
+            bytes.extend_from_slice(&self.key_latch_remaining[key].to_be_bytes());
+            bytes.push(u8::from(self.is_key_release_pending[key]));
+        }
+        bytes.extend_from_slice(&match self.key_wait {
+            KeyWait::Idle => [0, 0],
+            KeyWait::Press => [1, 0],
+            KeyWait::Release(key) => [2, key],
+            KeyWait::Released(key) => [3, key],
+        });
+        bytes.extend_from_slice(&(self.screen.width() as u32).to_be_bytes());
+        bytes.extend_from_slice(&(self.screen.height() as u32).to_be_bytes());
+        bytes.extend_from_slice(&self.screen.to_packed_1bpp());
+        bytes.push(u8::from(self.waiting_for_vblank));
+        bytes.extend_from_slice(&self.cycles_executed.to_be_bytes());
+        bytes
+    }
+
+    /// Replaces the state of this interpreter with one returned by `save_state_bytes`, failing
+    /// without changing anything if it is malformed or was saved with another ROM.
+    pub fn load_state_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        let mut input = Input { bytes };
+
+        ensure!(input.take(4)? == MAGIC, InvalidSaveStateSnafu { reason: "not a save state" });
+        let version = input.u16()?;
+        ensure!((1..=VERSION).contains(&version), UnsupportedSaveStateVersionSnafu { version });
+        let hash = input.u64()?;
+        ensure!(
+            hash == self.rom_hash,
+            SaveStateRomMismatchSnafu { expected: self.rom_hash, actual: hash }
+        );
+
+        let ram_len = input.u32()? as usize;
+        ensure!(
+            ram_len == self.ram.len(),
+            InvalidSaveStateSnafu { reason: "the memory size does not match" }
+        );
+        let ram = input.take(ram_len)?.to_vec();
+        let pc = input.u32()? as usize;
+        let mut v = [0; 16];
+        v.copy_from_slice(input.take(16)?);
+        let i = input.u16()?;
+        let depth = input.u32()? as usize;
+        ensure!(
+            depth <= self.quirks.max_call_stack_depth,
+            InvalidSaveStateSnafu { reason: "the call stack is too deep" }
+        );
+        let call_stack = (0..depth).map(|_| Ok(input.u32()? as usize)).collect::<Result<_>>()?;
+        let call_stack_high_water_mark = input.u32()? as usize;
+        let delay_timer = input.u8()?;
+        let sound_timer = input.u8()?;
+        let mut is_key_pressed = [false; 16];
+        let mut key_latch_remaining = [0; 16];
+        let mut is_key_release_pending = [false; 16];
+        for key in 0..16 {
+            is_key_pressed[key] = input.bool()?;
+            key_latch_remaining[key] = input.u32()?;
+            is_key_release_pending[key] = input.bool()?;
+        }
+        let key_wait = match (input.u8()?, input.u8()?) {
+            (0, _) => KeyWait::Idle,
+            (1, _) => KeyWait::Press,
+            (2, key) if key < 16 => KeyWait::Release(key),
+            (3, key) if key < 16 => KeyWait::Released(key),
+            _ => return InvalidSaveStateSnafu { reason: "the Fx0A state is invalid" }.fail(),
+        };
+        let (width, height) = if version >= 2 {
+            (input.u32()? as usize, input.u32()? as usize)
+        } else {
+            (self.screen.width(), self.screen.height())
+        };
+        let mut screen = Screen::try_with_size(width, height)
+            .context(InvalidSaveStateSnafu { reason: "the screen size is invalid" })?;
+        let packed = input.take(screen.len_in(PixelLayout::Packed1bpp))?;
+        for (index, pixel) in screen.pixels_mut().iter_mut().enumerate() {
+            if packed[index / 8] & (0x80 >> (index % 8)) != 0 {
+                *pixel = Color::White;
+            }
+        }
+        let waiting_for_vblank = input.bool()?;
+        let cycles_executed = input.u64()?;
+        ensure!(input.bytes.is_empty(), InvalidSaveStateSnafu { reason: "trailing bytes" });
+
+        self.ram = ram;
+        self.pc = pc;
+        self.v = v;
+        self.i = i;
+        self.call_stack = call_stack;
+        self.call_stack_high_water_mark = call_stack_high_water_mark;
+        self.timers.delay_timer = delay_timer;
+        self.timers.sound_timer = sound_timer;
+        self.is_key_pressed = is_key_pressed;
+        self.key_latch_remaining = key_latch_remaining;
+        self.is_key_release_pending = is_key_release_pending;
+        self.key_wait = key_wait;
+        self.screen = screen;
+        self.waiting_for_vblank = waiting_for_vblank;
+        self.cycles_executed = cycles_executed;
+        Ok(())
+    }
+}
+
+struct Input<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Input<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        ensure!(len <= self.bytes.len(), InvalidSaveStateSnafu { reason: "unexpected end" });
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn bool(&mut self) -> Result<bool> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => InvalidSaveStateSnafu { reason: "a flag is neither 0 nor 1" }.fail(),
+        }
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
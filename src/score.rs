@@ -0,0 +1,67 @@
+//! Where a CHIP-8 program keeps its score in memory.
+
+use alloc::vec::Vec;
+use core::{fmt, str::FromStr};
+
+use crate::{Chip8, InvalidScoreLocationSnafu, Result};
+
+/// The location and the encoding of a score in memory, written as one of the following:
+///
+/// | Location          | Score                                                              |
+/// | ----------------- | ------------------------------------------------------------------ |
+/// | `byte ADDRESS`    | The byte at `ADDRESS`                                              |
+/// | `word ADDRESS`    | The big-endian 16-bit word at `ADDRESS`                            |
+/// | `bcd ADDRESS N`   | `N` decimal digits at `ADDRESS..(ADDRESS + N)`, one per byte (Fx33) |
+///
+/// Addresses can be written in decimal or, with the `0x` prefix, in hexadecimal.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ScoreLocation {
+    Byte(u16),
+    Word(u16),
+    Bcd(u16, u8),
+}
+
+impl ScoreLocation {
+    /// Reads the score from the memory of `chip8`, treating bytes out of bounds as zeros.
+    pub fn read(&self, chip8: &Chip8) -> u32 {
+        let byte =
+            |address: u16| u32::from(chip8.ram.get(usize::from(address)).copied().unwrap_or(0));
+        match *self {
+            ScoreLocation::Byte(address) => byte(address),
+            ScoreLocation::Word(address) => byte(address) << 8 | byte(address.wrapping_add(1)),
+            ScoreLocation::Bcd(address, digits) => (0..u16::from(digits))
+                .fold(0, |score, digit| score * 10 + byte(address.wrapping_add(digit)) % 10),
+        }
+    }
+}
+
+impl FromStr for ScoreLocation {
+    type Err = crate::Error;
+
+    fn from_str(location: &str) -> Result<Self> {
+        let number = |word: &str| match word.strip_prefix("0x") {
+            Some(hex) => u16::from_str_radix(hex, 16).ok(),
+            None => word.parse().ok(),
+        };
+        let words: Vec<_> = location.split_whitespace().collect();
+        let parsed = match words.as_slice() {
+            ["byte", address] => number(address).map(ScoreLocation::Byte),
+            ["word", address] => number(address).map(ScoreLocation::Word),
+            ["bcd", address, digits] => number(address)
+                .zip(digits.parse().ok().filter(|digits| (1..=9).contains(digits)))
+                .map(|(address, digits)| ScoreLocation::Bcd(address, digits)),
+            _ => None,
+        };
+        parsed.map_or_else(|| InvalidScoreLocationSnafu { location }.fail(), Ok)
+    }
+}
+
+impl fmt::Display for ScoreLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScoreLocation::Byte(address) => write!(f, "byte {address:#05X}"),
+            ScoreLocation::Word(address) => write!(f, "word {address:#05X}"),
+            ScoreLocation::Bcd(address, digits) => write!(f, "bcd {address:#05X} {digits}"),
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! Searching memory for the addresses of values such as lives and scores, as cheat finders do:
+//! start with every address, then keep narrowing the candidates down by how their values changed
+//! between searches while playing.
+//!
+//! ```
+//! # fn main() -> Result<(), chip8::Error> {
+//! use chip8::search::{Filter, MemorySearch, Width};
+//!
+//! let rom = [0x60, 0x03, 0xA3, 0x00, 0xF0, 0x55, 0x70, 0xFF, 0x12, 0x04];
+//! let mut chip8 = chip8::Chip8::builder().rom(&rom).build()?;
+//! let mut search = MemorySearch::new(&chip8, Width::Byte);
+//! chip8.run_frame(3)?; // The program stores 3 lives at 0x300.
+//! search.filter(&chip8, Filter::Equal(3));
+//! chip8.run_frame(3)?; // It loses a life.
+//! assert_eq!(search.filter(&chip8, Filter::Decreased), [0x300]);
+//! # Ok(())
+//! # }
+//! ```
+
+use alloc::vec::Vec;
+
+use crate::Chip8;
+
+/// The size of the values searched for.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Width {
+    /// 8-bit values.
+    Byte,
+    /// 16-bit values, with the most significant byte first as CHIP-8 programs usually store them.
+    Word,
+}
+
+/// How the value at a candidate address must compare for it to remain a candidate.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Filter {
+    /// The value is the given one.
+    Equal(u16),
+    /// The value differs from the one at the last search.
+    Changed,
+    /// The value is the same as at the last search.
+    Unchanged,
+    /// The value is greater than at the last search.
+    Increased,
+    /// The value is less than at the last search.
+    Decreased,
+}
+
+/// A search that narrows down the addresses of a value over successive filters.
+#[derive(Clone, Debug)]
+pub struct MemorySearch {
+    width: Width,
+    candidates: Vec<usize>,
+    /// The memory at the last search.
+    snapshot: Vec<u8>,
+}
+
+impl MemorySearch {
+    /// Starts a search in which every address of the memory of `chip8` is a candidate.
+    pub fn new(chip8: &Chip8, width: Width) -> Self {
+        let memory = chip8.memory();
+        let len = match width {
+            Width::Byte => memory.len(),
+            Width::Word => memory.len().saturating_sub(1),
+        };
+        Self { width, candidates: (0..len).collect(), snapshot: memory.to_vec() }
+    }
+
+    /// Keeps the candidates whose values in the memory of `chip8` pass `filter`, returning them.
+    pub fn filter(&mut self, chip8: &Chip8, filter: Filter) -> &[usize] {
+        let memory = chip8.memory();
+        let width = self.width;
+        let snapshot = &self.snapshot;
+        self.candidates.retain(|&address| {
+            let Some(value) = read_value(memory, address, width) else {
+                return false;
+            };
+            let last = read_value(snapshot, address, width).unwrap_or_default();
+            match filter {
+                Filter::Equal(expected) => value == expected,
+                Filter::Changed => value != last,
+                Filter::Unchanged => value == last,
+                Filter::Increased => value > last,
+                Filter::Decreased => value < last,
+            }
+        });
+        self.snapshot = memory.to_vec();
+        &self.candidates
+    }
+
+    /// Returns the addresses that are still candidates, in ascending order.
+    pub fn candidates(&self) -> &[usize] {
+        &self.candidates
+    }
+}
+
+/// Returns the addresses at which `pattern` occurs in `memory`, where `None` matches any byte.
+///
+/// ```
+/// let memory = [0xF0, 0x90, 0xF0, 0x10, 0xF0, 0x90];
+/// assert_eq!(chip8::search::find_bytes(&memory, &[Some(0xF0), None, Some(0xF0)]), [0, 2]);
+/// ```
+pub fn find_bytes(memory: &[u8], pattern: &[Option<u8>]) -> Vec<usize> {
+    if pattern.is_empty() {
+        return Vec::new();
+    }
+    memory
+        .windows(pattern.len())
+        .enumerate()
+        .filter(|(_, window)| {
+            window
+                .iter()
+                .zip(pattern)
+                .all(|(&byte, &expected)| expected.is_none() || expected == Some(byte))
+        })
+        .map(|(address, _)| address)
+        .collect()
+}
+
+/// Returns the value at `address`, or `None` if it is not all in `memory`.
+fn read_value(memory: &[u8], address: usize, width: Width) -> Option<u16> {
+    match width {
+        Width::Byte => memory.get(address).copied().map(u16::from),
+        Width::Word => {
+            let bytes = memory.get(address..(address + 2))?;
+            Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+        }
+    }
+}
@@ -0,0 +1,90 @@
+//! Numbered save-state slots, saved with F5 and loaded with F7.
+//!
+//! Slot N of a ROM is a save state (see `chip8::save_state`) in a file named after the hash of the
+//! ROM, so that states of other versions of the ROM are not loaded by mistake: next to the ROM
+//! file, with the `HASH.N.c8s` extension, or, if the directory of the ROM file is not writable, in
+//! `chip8/states` under the XDG data directory, as `HASH.N.c8s`. F6 selects the next slot, and
+//! Shift+F6 the previous one.
+
+use std::{
+    env,
+    fs::{self, File},
+    path::{Path, PathBuf},
+};
+
+use snafu::ResultExt;
+
+use chip8::Chip8;
+
+use crate::{Chip8Snafu, IoSnafu, Result};
+
+/// The number of slots, numbered from 0.
+const SLOT_COUNT: u8 = 10;
+
+pub struct SaveSlots {
+    rom_file: PathBuf,
+    slot: u8,
+}
+
+impl SaveSlots {
+    /// Returns the slots of `rom_file`, with slot 0 selected.
+    pub fn new(rom_file: &Path) -> Self {
+        Self { rom_file: rom_file.to_path_buf(), slot: 0 }
+    }
+
+    /// Returns the selected slot.
+    pub fn slot(&self) -> u8 {
+        self.slot
+    }
+
+    /// Selects the next slot, or the previous one if `forward` is false, wrapping around.
+    pub fn select_next(&mut self, forward: bool) {
+        let step = if forward { 1 } else { SLOT_COUNT - 1 };
+        self.slot = (self.slot + step) % SLOT_COUNT;
+    }
+
+    /// Saves the state of `chip8` in the selected slot, returning the path of the file.
+    pub fn save(&self, chip8: &Chip8) -> Result<PathBuf> {
+        let (beside_rom, in_data_dir) = self.paths(chip8.rom_hash());
+        let path = match File::create(&beside_rom) {
+            Ok(mut file) => {
+                chip8.save_state(&mut file).context(Chip8Snafu)?;
+                return Ok(beside_rom);
+            }
+            Err(err) => in_data_dir.ok_or(err).context(IoSnafu)?,
+        };
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory).context(IoSnafu)?;
+        }
+        chip8.save_state(&mut File::create(&path).context(IoSnafu)?).context(Chip8Snafu)?;
+        Ok(path)
+    }
+
+    /// Replaces the state of `chip8` with the one in the selected slot, returning the path of the
+    /// file, or `None` if the slot is empty.
+    pub fn load(&self, chip8: &mut Chip8) -> Result<Option<PathBuf>> {
+        let (beside_rom, in_data_dir) = self.paths(chip8.rom_hash());
+        let candidates = [Some(beside_rom), in_data_dir];
+        let Some(path) = candidates.into_iter().flatten().find(|path| path.is_file()) else {
+            return Ok(None);
+        };
+        chip8.load_state(&mut File::open(&path).context(IoSnafu)?).context(Chip8Snafu)?;
+        Ok(Some(path))
+    }
+
+    /// Returns the paths of the selected slot next to the ROM file and under the data directory, if
+    /// it is known.
+    fn paths(&self, rom_hash: u64) -> (PathBuf, Option<PathBuf>) {
+        let name = format!("{rom_hash:016X}.{}.c8s", self.slot);
+        (self.rom_file.with_extension(&name), data_dir().map(|dir| dir.join(name)))
+    }
+}
+
+/// Returns `$XDG_DATA_HOME/chip8/states`, or `~/.local/share/chip8/states` if it is not set.
+fn data_dir() -> Option<PathBuf> {
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| Some(PathBuf::from(env::var_os("HOME")?).join(".local/share")))?;
+    Some(data_home.join("chip8/states"))
+}
@@ -0,0 +1,135 @@
+//! Snapshots of the state that programs can observe, and what changed between two of them, for
+//! debugger views such as "what changed since the last break" and for delta-compressed rewinding.
+
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::{Chip8, Screen};
+
+/// A copy of the state of an interpreter that programs can observe, the same state that
+/// `Chip8::state_hash` hashes.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Chip8State {
+    pub memory: Vec<u8>,
+    pub pc: usize,
+    pub v: [u8; 16],
+    pub i: u16,
+    /// The return addresses of the subroutine calls that have not returned yet, the innermost last.
+    pub call_stack: Vec<usize>,
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+    pub screen: Screen,
+}
+
+/// A register, or a register-like part of the state, that differs between two states.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Register {
+    /// One of V0, ..., VF.
+    V(u8),
+    I,
+    Pc,
+    CallStack,
+    DelayTimer,
+    SoundTimer,
+}
+
+/// What differs between two states (see `Chip8State::diff`).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct StateDiff {
+    /// The registers that differ, V0, ..., VF first.
+    pub registers: Vec<Register>,
+    /// The ranges of addresses whose bytes differ, in ascending order. Addresses beyond the end
+    /// of the smaller memory differ.
+    pub memory: Vec<Range<usize>>,
+    /// The rows of the screen that differ, from the top, or every row if the screens differ in
+    /// size.
+    pub screen_rows: Vec<usize>,
+}
+
+impl StateDiff {
+    /// Returns true if nothing differs.
+    pub fn is_empty(&self) -> bool {
+        self.registers.is_empty() && self.memory.is_empty() && self.screen_rows.is_empty()
+    }
+}
+
+impl Chip8State {
+    /// Returns what differs between this state and `other`.
+    ///
+    /// ```
+    /// # fn main() -> Result<(), chip8::Error> {
+    /// use chip8::state::Register;
+    ///
+    /// let mut chip8 = chip8::Chip8::from_bytes(&[0x63, 0x2A, 0xA3, 0x00], Default::default())?;
+    /// let before = chip8.state();
+    /// chip8.fetch_execute_cycle()?;
+    /// chip8.fetch_execute_cycle()?;
+    /// let diff = before.diff(&chip8.state());
+    /// assert_eq!(diff.registers, [Register::V(3), Register::I, Register::Pc]);
+    /// assert!(diff.memory.is_empty() && diff.screen_rows.is_empty());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn diff(&self, other: &Self) -> StateDiff {
+        let mut registers: Vec<Register> = (0..16)
+            .filter(|&x| self.v[usize::from(x)] != other.v[usize::from(x)])
+            .map(Register::V)
+            .collect();
+        for (register, differs) in [
+            (Register::I, self.i != other.i),
+            (Register::Pc, self.pc != other.pc),
+            (Register::CallStack, self.call_stack != other.call_stack),
+            (Register::DelayTimer, self.delay_timer != other.delay_timer),
+            (Register::SoundTimer, self.sound_timer != other.sound_timer),
+        ] {
+            if differs {
+                registers.push(register);
+            }
+        }
+
+        let mut memory = Vec::new();
+        let mut start = None;
+        for address in 0..self.memory.len().max(other.memory.len()) {
+            let differs = self.memory.get(address) != other.memory.get(address);
+            match (start, differs) {
+                (None, true) => start = Some(address),
+                (Some(first), false) => {
+                    memory.push(first..address);
+                    start = None;
+                }
+                _ => (),
+            }
+        }
+        if let Some(first) = start {
+            memory.push(first..self.memory.len().max(other.memory.len()));
+        }
+
+        let screen_rows = if (self.screen.width(), self.screen.height())
+            == (other.screen.width(), other.screen.height())
+        {
+            let rows = self.screen.rows().zip(other.screen.rows());
+            rows.enumerate().filter(|(_, (a, b))| a != b).map(|(y, _)| y).collect()
+        } else {
+            (0..self.screen.height().max(other.screen.height())).collect()
+        };
+
+        StateDiff { registers, memory, screen_rows }
+    }
+}
+
+impl Chip8 {
+    /// Returns a copy of the state that programs can observe, for comparing with later states
+    /// (see `Chip8State::diff`).
+    pub fn state(&self) -> Chip8State {
+        Chip8State {
+            memory: self.ram.clone(),
+            pc: self.pc,
+            v: self.v,
+            i: self.i,
+            call_stack: self.call_stack.clone(),
+            delay_timer: self.timers.delay_timer,
+            sound_timer: self.timers.sound_timer,
+            screen: self.screen,
+        }
+    }
+}
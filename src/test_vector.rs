@@ -0,0 +1,285 @@
+//! Single-step test vectors, each of which sets up a state, executes one instruction, and gives the
+//! state expected afterwards, in the JSON format of the single-step tests for other CPUs:
+//!
+//! ``` json
+//! [
+//!   {
+//!     "name": "8124 0",
+//!     "opcode": 33060,
+//!     "initial": {
+//!       "pc": 1024, "i": 768, "v": [0, 200, 100, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+//!       "stack": [], "delay_timer": 0, "sound_timer": 0, "keys": [], "ram": []
+//!     },
+//!     "final": {
+//!       "pc": 1026, "i": 768, "v": [0, 44, 100, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
+//!       "stack": [], "delay_timer": 0, "sound_timer": 0, "keys": [], "ram": []
+//!     }
+//!   }
+//! ]
+//! ```
+//!
+//! The memory of the initial state has the font at 0x000 and zeros elsewhere except for the
+//! `ram` pairs of addresses and bytes. The `ram` of the final state gives the final bytes at those
+//! addresses and at every address that the instruction changed. `screen`, given for instructions
+//! that draw, is the rows of the screen as `Screen::to_ascii` formats them.
+//!
+//! Cxkk, whose result depends on the random number generator, and 0nnn other than 00E0 and 00EE
+//! are not generated.
+
+use std::{
+    collections::BTreeSet,
+    format,
+    io::{Read, Write},
+    string::String,
+    vec::Vec,
+};
+
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+
+use crate::{
+    rng::{RngSource, SeededRng},
+    Chip8, Color, InvalidTestVectorsSnafu, Quirks, Result, Screen,
+};
+
+/// A test vector for one instruction.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TestVector {
+    pub name: String,
+    pub opcode: u16,
+    pub initial: VectorState,
+    #[serde(rename = "final")]
+    pub final_state: VectorState,
+}
+
+/// The state of an interpreter before or after a test vector.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct VectorState {
+    pub pc: u16,
+    pub i: u16,
+    pub v: [u8; 16],
+    /// The return addresses of the subroutine calls, the innermost last.
+    pub stack: Vec<u16>,
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+    /// The hex keys being pressed.
+    #[serde(default)]
+    pub keys: Vec<u8>,
+    /// Pairs of addresses and bytes (see the module documentation).
+    pub ram: Vec<(u16, u8)>,
+    /// The rows of the screen, if the instruction draws.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub screen: Option<Vec<String>>,
+}
+
+/// The opcodes that `generate` makes vectors for, with the bits of their operands.
+const TEMPLATES: [(u16, u16); 33] = [
+    (0x00E0, 0x0000),
+    (0x00EE, 0x0000),
+    (0x1000, 0x0FFF),
+    (0x2000, 0x0FFF),
+    (0x3000, 0x0FFF),
+    (0x4000, 0x0FFF),
+    (0x5000, 0x0FF0),
+    (0x6000, 0x0FFF),
+    (0x7000, 0x0FFF),
+    (0x8000, 0x0FF0),
+    (0x8001, 0x0FF0),
+    (0x8002, 0x0FF0),
+    (0x8003, 0x0FF0),
+    (0x8004, 0x0FF0),
+    (0x8005, 0x0FF0),
+    (0x8006, 0x0FF0),
+    (0x8007, 0x0FF0),
+    (0x800E, 0x0FF0),
+    (0x9000, 0x0FF0),
+    (0xA000, 0x0FFF),
+    (0xB000, 0x0FFF),
+    (0xD000, 0x0FFF),
+    (0xE09E, 0x0F00),
+    (0xE0A1, 0x0F00),
+    (0xF007, 0x0F00),
+    (0xF00A, 0x0F00),
+    (0xF015, 0x0F00),
+    (0xF018, 0x0F00),
+    (0xF01E, 0x0F00),
+    (0xF029, 0x0F00),
+    (0xF033, 0x0F00),
+    (0xF055, 0x0F00),
+    (0xF065, 0x0F00),
+];
+
+impl TestVector {
+    /// Sets up the initial state, executes the opcode with `quirks`, and returns the final state,
+    /// failing if the opcode fails.
+    pub fn run(&self, quirks: Quirks) -> Result<VectorState> {
+        let (chip8, memory) = self.execute(quirks)?;
+        let addresses = self.initial.ram.iter().map(|&(address, _)| address);
+        let with_screen = self.initial.screen.is_some() || self.final_state.screen.is_some();
+        Ok(VectorState::capture(&chip8, &memory, addresses, with_screen))
+    }
+
+    /// Returns the names of the parts of the final state that differ from those after executing
+    /// the opcode with `quirks`, failing if the opcode fails.
+    pub fn check(&self, quirks: Quirks) -> Result<Vec<&'static str>> {
+        let (chip8, mut memory) = self.execute(quirks)?;
+        for &(address, byte) in &self.final_state.ram {
+            if let Some(expected) = memory.get_mut(usize::from(address)) {
+                *expected = byte;
+            }
+        }
+        let actual = VectorState::capture(&chip8, &memory, [].into_iter(), true);
+        let expected = &self.final_state;
+        let mut mismatches = Vec::new();
+        for (name, differs) in [
+            ("pc", actual.pc != expected.pc),
+            ("i", actual.i != expected.i),
+            ("v", actual.v != expected.v),
+            ("stack", actual.stack != expected.stack),
+            ("delay_timer", actual.delay_timer != expected.delay_timer),
+            ("sound_timer", actual.sound_timer != expected.sound_timer),
+            ("ram", !actual.ram.is_empty()),
+            ("screen", expected.screen.is_some() && actual.screen != expected.screen),
+        ] {
+            if differs {
+                mismatches.push(name);
+            }
+        }
+        Ok(mismatches)
+    }
+
+    /// Executes the opcode from the initial state, returning the interpreter and its memory
+    /// before the opcode.
+    fn execute(&self, quirks: Quirks) -> Result<(Chip8, Vec<u8>)> {
+        let mut chip8 = Chip8::builder().quirks(quirks).seed(0).build()?;
+        self.initial.apply(&mut chip8)?;
+        let memory = chip8.memory().to_vec();
+        chip8.execute_opcode(self.opcode)?;
+        Ok((chip8, memory))
+    }
+}
+
+impl VectorState {
+    /// Replaces the state of `chip8` with this one.
+    fn apply(&self, chip8: &mut Chip8) -> Result<()> {
+        chip8.set_pc(usize::from(self.pc));
+        chip8.set_i(self.i);
+        *chip8.v_mut() = self.v;
+        *chip8.call_stack_mut() = self.stack.iter().map(|&address| usize::from(address)).collect();
+        chip8.timers.set_delay_timer(self.delay_timer);
+        chip8.timers.sound_timer = self.sound_timer;
+        for &key in &self.keys {
+            chip8.press_key(key)?;
+        }
+        for &(address, byte) in &self.ram {
+            chip8.write(usize::from(address), &[byte])?;
+        }
+        if let Some(rows) = &self.screen {
+            for (y, row) in rows.iter().enumerate().take(chip8.screen.height()) {
+                for (x, pixel) in row.chars().enumerate().take(chip8.screen.width()) {
+                    let color = if pixel == 'O' { Color::White } else { Color::Black };
+                    chip8.screen.set(x, y, color);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the state of `chip8`, listing the bytes at `addresses` and those that differ from
+    /// `memory`.
+    fn capture(
+        chip8: &Chip8,
+        memory: &[u8],
+        addresses: impl Iterator<Item = u16>,
+        with_screen: bool,
+    ) -> Self {
+        let mut listed: BTreeSet<u16> = addresses.collect();
+        for (address, (before, after)) in memory.iter().zip(chip8.memory()).enumerate() {
+            if before != after {
+                listed.insert(address as u16);
+            }
+        }
+        Self {
+            pc: chip8.pc() as u16,
+            i: chip8.i(),
+            v: *chip8.v(),
+            stack: chip8.call_stack().iter().map(|&address| address as u16).collect(),
+            delay_timer: chip8.timers.delay_timer(),
+            sound_timer: chip8.timers.sound_timer,
+            keys: (0..16).filter(|&key| chip8.is_key_pressed(key)).collect(),
+            ram: listed
+                .into_iter()
+                .map(|address| (address, chip8.memory()[usize::from(address)]))
+                .collect(),
+            screen: with_screen
+                .then(|| chip8.screen.to_ascii().lines().map(String::from).collect()),
+        }
+    }
+}
+
+/// Returns `count` vectors for each opcode that this interpreter executes successfully with
+/// `quirks`, from random states drawn from `seed`.
+pub fn generate(quirks: Quirks, count: usize, seed: u64) -> Result<Vec<TestVector>> {
+    let mut rng = SeededRng::new(seed);
+    let mut word = move || u16::from_be_bytes([rng.random_byte(), rng.random_byte()]);
+    let mut vectors = Vec::new();
+    for (base, operands) in TEMPLATES {
+        let mut generated = 0;
+        // Random states can make an opcode fail, such as 00EE with an empty stack.
+        for _ in 0..count * 8 {
+            if generated == count {
+                break;
+            }
+            let opcode = base | word() & operands;
+            let draws = matches!(base, 0x00E0 | 0xD000);
+            let i = 0x200 + word() % 0xDF0;
+            let initial = VectorState {
+                pc: (0x200 + word() % 0xDF0) & !1,
+                i,
+                v: core::array::from_fn(|_| word() as u8),
+                stack: (0..word() % 4).map(|_| (0x200 + word() % 0xDF0) & !1).collect(),
+                delay_timer: word() as u8,
+                sound_timer: word() as u8,
+                keys: (0..16).filter(|_| word() % 4 == 0).collect(),
+                ram: (i..i + 16).map(|address| (address, word() as u8)).collect(),
+                screen: draws.then(|| {
+                    let mut screen = Screen::default();
+                    for _ in 0..64 {
+                        let x = usize::from(word()) % screen.width();
+                        let y = usize::from(word()) % screen.height();
+                        screen.set(x, y, Color::White);
+                    }
+                    screen.to_ascii().lines().map(String::from).collect()
+                }),
+            };
+            let mut vector = TestVector {
+                name: format!("{opcode:04X} {generated}"),
+                opcode,
+                initial,
+                final_state: VectorState::default(),
+            };
+            if let Ok(final_state) = vector.run(quirks) {
+                vector.final_state = final_state;
+                vectors.push(vector);
+                generated += 1;
+            }
+        }
+    }
+    Ok(vectors)
+}
+
+/// Reads test vectors in JSON.
+pub fn read<R: Read>(reader: R) -> Result<Vec<TestVector>> {
+    serde_json::from_reader(reader).context(InvalidTestVectorsSnafu)
+}
+
+/// Writes test vectors in JSON, one per line.
+pub fn write<W: Write>(vectors: &[TestVector], writer: &mut W) -> Result<()> {
+    let mut json = String::from("[\n");
+    for (index, vector) in vectors.iter().enumerate() {
+        json += &serde_json::to_string(vector).context(InvalidTestVectorsSnafu)?;
+        json += if index + 1 < vectors.len() { ",\n" } else { "\n" };
+    }
+    json += "]\n";
+    writer.write_all(json.as_bytes()).context(crate::IoSnafu)
+}
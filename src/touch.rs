@@ -0,0 +1,102 @@
+//! An on-screen keypad for touchscreens, laid out like the CHIP-8 hex keyboard in the bottom half
+//! of the window, below the screen.
+//!
+//! The keypad is shown when a touch device is present or `--touch` is given, in which case mouse
+//! clicks also count as touches. A key is pressed while a finger that went down on it stays down,
+//! wherever the finger moves.
+
+use std::collections::HashMap;
+
+use sdl2::{event::Event, pixels::Color, rect::Rect, render::Canvas, video::Window};
+
+use crate::Result;
+
+const LAYOUT: [[u8; 4]; 4] =
+    [[0x1, 0x2, 0x3, 0xC], [0x4, 0x5, 0x6, 0xD], [0x7, 0x8, 0x9, 0xE], [0xA, 0x0, 0xB, 0xF]];
+
+const KEY_COLOR: Color = Color::RGB(0x40, 0x40, 0x40);
+const PRESSED_KEY_COLOR: Color = Color::RGB(0x80, 0x80, 0x80);
+const LABEL_COLOR: Color = Color::RGB(0xFF, 0xFF, 0xFF);
+
+pub struct TouchKeypad {
+    /// The keys pressed by the fingers down, by touch device and finger.
+    fingers: HashMap<(i64, i64), u8>,
+}
+
+impl TouchKeypad {
+    pub fn new() -> Self {
+        Self { fingers: HashMap::new() }
+    }
+
+    /// Returns the CHIP-8 key that a touch event presses or releases and whether it is pressed, if
+    /// any.
+    pub fn handle(&mut self, event: &Event) -> Option<(u8, bool)> {
+        match *event {
+            Event::FingerDown { touch_id, finger_id, x, y, .. } => {
+                let key = key_at(x, y)?;
+                self.fingers.insert((touch_id, finger_id), key);
+                Some((key, true))
+            }
+            Event::FingerUp { touch_id, finger_id, .. } => {
+                Some((self.fingers.remove(&(touch_id, finger_id))?, false))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Splits a window of `width` by `height` pixels into the areas of the screen and the keypad.
+pub fn split(width: u32, height: u32) -> (Rect, Rect) {
+    let top = height / 2;
+    (Rect::new(0, 0, width, top), Rect::new(0, top as i32, width, height - top))
+}
+
+/// Draws the keypad in `area`, highlighting the keys for which `is_pressed` returns true.
+pub fn draw(
+    canvas: &mut Canvas<Window>,
+    area: Rect,
+    is_pressed: impl Fn(u8) -> bool,
+) -> Result<()> {
+    let (key_width, key_height) = (area.width() / 4, area.height() / 4);
+    let gap = key_width.min(key_height) / 16;
+    // A label is a sprite of the font, 4 pixels wide and 5 high.
+    let pixel = (key_width.min(key_height) / 10).max(1);
+    for (row, keys) in LAYOUT.iter().enumerate() {
+        for (column, &key) in keys.iter().enumerate() {
+            let x = area.x() + (column as u32 * key_width) as i32;
+            let y = area.y() + (row as u32 * key_height) as i32;
+            canvas.set_draw_color(if is_pressed(key) { PRESSED_KEY_COLOR } else { KEY_COLOR });
+            canvas.fill_rect(Rect::new(
+                x + gap as i32,
+                y + gap as i32,
+                key_width.saturating_sub(gap * 2),
+                key_height.saturating_sub(gap * 2),
+            ))?;
+
+            let label_x = x + ((key_width - pixel * 4) / 2) as i32;
+            let label_y = y + ((key_height - pixel * 5) / 2) as i32;
+            canvas.set_draw_color(LABEL_COLOR);
+            for (dy, bits) in chip8::sprite_for_digit(key).iter().enumerate() {
+                for dx in (0..4).filter(|dx| bits & (0x80 >> dx) != 0) {
+                    canvas.fill_rect(Rect::new(
+                        label_x + (dx * pixel) as i32,
+                        label_y + (dy as u32 * pixel) as i32,
+                        pixel,
+                        pixel,
+                    ))?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns the key at `x` and `y`, normalized to the width and height of the window.
+fn key_at(x: f32, y: f32) -> Option<u8> {
+    if !(0.5..1.0).contains(&y) || !(0.0..1.0).contains(&x) {
+        return None;
+    }
+    let row = ((y - 0.5) * 8.0) as usize;
+    let column = (x * 4.0) as usize;
+    Some(LAYOUT[row.min(3)][column.min(3)])
+}
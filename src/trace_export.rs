@@ -0,0 +1,173 @@
+//! Writing a trace of the instructions executed to a file, one line per instruction, for comparing
+//! runs with other emulators when chasing quirk bugs:
+//!
+//! ``` text
+//!          3 0x204 7005 ADD V0, 0x05         V0=06
+//! ```
+//!
+//! ``` json
+//! {"cycle":3,"pc":516,"opcode":28677,"mnemonic":"ADD V0, 0x05","changed":{"V0":6}}
+//! ```
+//!
+//! Each line gives the number of cycles executed before the instruction (see
+//! `Chip8::cycles_executed`), which unlike the time is the same for every run, the address, the
+//! opcode, the mnemonic, and the registers among V0 to VF and I that the instruction changed.
+//!
+//! An exporter is fed by the hooks of the interpreter, which `TraceExporter::install` sets:
+//!
+//! ```
+//! # fn main() -> Result<(), chip8::Error> {
+//! use std::sync::{Arc, Mutex};
+//!
+//! use chip8::trace_export::{TraceExporter, TraceFormat};
+//!
+//! let rom = [0x60, 0x01, 0xA3, 0x00]; // LD V0, 0x01; LD I, 0x300
+//! let mut chip8 = chip8::Chip8::builder().rom(&rom).build()?;
+//! let exporter = Arc::new(Mutex::new(TraceExporter::new(Vec::new(), TraceFormat::Text)));
+//! TraceExporter::install(&exporter, &mut chip8);
+//! chip8.run_frame(2)?;
+//! let trace = String::from_utf8(exporter.lock().unwrap().get_ref().clone()).unwrap();
+//! assert_eq!(trace.lines().last(), Some("         1 0x202 A300 LD I, 0x300          I=0x300"));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    format,
+    io::Write,
+    ops::Range,
+    string::{String, ToString},
+    sync::{Arc, Mutex},
+    vec::Vec,
+};
+
+use snafu::ResultExt;
+
+use crate::{hook::HookAction, instruction::Instruction, Chip8, Error, IoSnafu, Result};
+
+/// The format of the lines of a trace.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TraceFormat {
+    /// Columns of text, with the changed registers as `NAME=VALUE` in hexadecimal.
+    Text,
+    /// One JSON object per line, with the numbers in decimal.
+    Jsonl,
+}
+
+/// Writes a line for each instruction executed within the address ranges to trace.
+#[derive(Debug)]
+pub struct TraceExporter<W> {
+    writer: W,
+    format: TraceFormat,
+    ranges: Vec<Range<usize>>,
+    /// The program counter, the cycles executed, and the registers before the instruction being
+    /// executed.
+    before: Option<(usize, u64, [u8; 16], u16)>,
+    /// The error that made the hook abort execution.
+    error: Option<Error>,
+}
+
+impl<W: Write> TraceExporter<W> {
+    /// Returns an exporter that traces every address.
+    pub fn new(writer: W, format: TraceFormat) -> Self {
+        Self { writer, format, ranges: Vec::new(), before: None, error: None }
+    }
+
+    /// Traces only the instructions at addresses within `ranges`, or at every address if it is
+    /// empty.
+    pub fn set_ranges(&mut self, ranges: Vec<Range<usize>>) {
+        self.ranges = ranges;
+    }
+
+    /// Notes the state of `chip8` before an instruction. Call this from the hook set by
+    /// `Chip8::set_hook`.
+    pub fn before(&mut self, chip8: &Chip8) {
+        let pc = chip8.pc();
+        let traced = self.ranges.is_empty() || self.ranges.iter().any(|range| range.contains(&pc));
+        self.before = traced.then(|| (pc, chip8.cycles_executed(), *chip8.v(), chip8.i()));
+    }
+
+    /// Writes the line for `instruction`, which `chip8` has just executed, if it is traced. Call
+    /// this from the hook set by `Chip8::set_post_hook`.
+    pub fn after(&mut self, chip8: &Chip8, instruction: Instruction) -> Result<()> {
+        let Some((pc, cycle, v, i)) = self.before.take() else {
+            return Ok(());
+        };
+        let mut changed: Vec<(String, u16)> = (0..16)
+            .filter(|&x| chip8.v()[x] != v[x])
+            .map(|x| (format!("V{x:X}"), u16::from(chip8.v()[x])))
+            .collect();
+        if chip8.i() != i {
+            changed.push((String::from("I"), chip8.i()));
+        }
+        let opcode = instruction.encode();
+        let line = match self.format {
+            TraceFormat::Text => {
+                let changed: Vec<_> = changed
+                    .iter()
+                    .map(|(name, value)| match name.as_str() {
+                        "I" => format!("I={value:#05X}"),
+                        _ => format!("{name}={value:02X}"),
+                    })
+                    .collect();
+                let mnemonic = instruction.to_string();
+                format!("{cycle:>10} {pc:#05X} {opcode:04X} {mnemonic:<20} {}", changed.join(" "))
+                    .trim_end()
+                    .to_string()
+            }
+            TraceFormat::Jsonl => {
+                let changed: Vec<_> =
+                    changed.iter().map(|(name, value)| format!("\"{name}\":{value}")).collect();
+                format!(
+                    "{{\"cycle\":{cycle},\"pc\":{pc},\"opcode\":{opcode},\
+                     \"mnemonic\":\"{instruction}\",\"changed\":{{{}}}}}",
+                    changed.join(",")
+                )
+            }
+        };
+        writeln!(self.writer, "{line}").context(IoSnafu)
+    }
+
+    /// Returns the error that made the hook set by `install` abort execution, if one did.
+    pub fn take_error(&mut self) -> Option<Error> {
+        self.error.take()
+    }
+
+    /// Flushes the writer.
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush().context(IoSnafu)
+    }
+
+    /// Returns the writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+}
+
+impl<W: Write + Send + 'static> TraceExporter<W> {
+    /// Sets the hooks of `chip8` to call `before` and `after` on `exporter`, replacing those set
+    /// before. A failure to write aborts execution with `Error::HookAborted`, and `take_error`
+    /// returns it.
+    pub fn install(exporter: &Arc<Mutex<Self>>, chip8: &mut Chip8) {
+        let before = Arc::clone(exporter);
+        chip8.set_hook(move |chip8, _| {
+            if let Ok(mut exporter) = before.lock() {
+                exporter.before(chip8);
+            }
+            HookAction::Continue
+        });
+        let after = Arc::clone(exporter);
+        chip8.set_post_hook(move |chip8, instruction| {
+            let Ok(mut exporter) = after.lock() else {
+                return HookAction::Abort;
+            };
+            match exporter.after(chip8, instruction) {
+                Ok(()) => HookAction::Continue,
+                Err(err) => {
+                    exporter.error = Some(err);
+                    HookAction::Abort
+                }
+            }
+        });
+    }
+}
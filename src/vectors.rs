@@ -0,0 +1,70 @@
+//! Generates single-step test vectors (see `chip8::test_vector`) and checks this interpreter
+//! against them, such as those published for other interpreters.
+
+use std::{fs::File, io::BufReader, path::PathBuf};
+
+use clap::Args;
+
+use snafu::{ensure, ResultExt};
+
+use crate::{Chip8Snafu, IoSnafu, QuirksOpt, Result, TestVectorsFailedSnafu};
+
+#[derive(Debug, Args)]
+pub struct GenerateOpt {
+    /// Sets how many vectors will be generated for each opcode
+    #[arg(long, value_name = "COUNT", default_value = "100")]
+    count: usize,
+
+    #[command(flatten)]
+    quirks: QuirksOpt,
+
+    /// Seeds the random initial states
+    #[arg(long, value_name = "SEED", default_value = "0")]
+    seed: u64,
+
+    /// Sets a JSON file to write
+    #[arg(name = "VECTORS-FILE")]
+    vectors_file: PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub struct CheckOpt {
+    #[command(flatten)]
+    quirks: QuirksOpt,
+
+    /// Sets a JSON file to read
+    #[arg(name = "VECTORS-FILE")]
+    vectors_file: PathBuf,
+}
+
+pub fn generate(opt: &GenerateOpt) -> Result<()> {
+    let quirks = chip8::Quirks::from(&opt.quirks);
+    let vectors = chip8::test_vector::generate(quirks, opt.count, opt.seed).context(Chip8Snafu)?;
+    let mut file = File::create(&opt.vectors_file).context(IoSnafu)?;
+    chip8::test_vector::write(&vectors, &mut file).context(Chip8Snafu)?;
+    println!("Wrote {} test vectors", vectors.len());
+    Ok(())
+}
+
+pub fn check(opt: &CheckOpt) -> Result<()> {
+    let quirks = chip8::Quirks::from(&opt.quirks);
+    let file = File::open(&opt.vectors_file).context(IoSnafu)?;
+    let vectors = chip8::test_vector::read(BufReader::new(file)).context(Chip8Snafu)?;
+    let mut failed: usize = 0;
+    for vector in &vectors {
+        match vector.check(quirks) {
+            Ok(mismatches) if mismatches.is_empty() => (),
+            Ok(mismatches) => {
+                println!("{}: {} differ", vector.name, mismatches.join(", "));
+                failed += 1;
+            }
+            Err(err) => {
+                println!("{}: {err}", vector.name);
+                failed += 1;
+            }
+        }
+    }
+    ensure!(failed == 0, TestVectorsFailedSnafu { failed, total: vectors.len() });
+    println!("All {} test vectors passed", vectors.len());
+    Ok(())
+}
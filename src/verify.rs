@@ -0,0 +1,218 @@
+//! Invariants of the instructions whose semantics interpreters most often get wrong, for
+//! property-based tests with proptest or quickcheck:
+//!
+//! ``` ignore
+//! proptest! {
+//!     #[test]
+//!     fn add_sets_the_carry(x in 0..16u8, y in 0..16u8, v: [u8; 16]) {
+//!         let mut chip8 = chip8::Chip8::from_bytes(&[], Default::default()).unwrap();
+//!         *chip8.v_mut() = v;
+//!         chip8::verify::check_add(&mut chip8, x, y).unwrap();
+//!     }
+//! }
+//! ```
+//!
+//! The `check_` functions execute an instruction on the current state of an interpreter and fail
+//! with `Error::InvariantViolated` if the result differs from what the plain functions, such as
+//! `add` and `bcd`, give for that state. `x` and `y` must be register numbers, 0 to F. Other
+//! interpreters can use the plain functions as oracles for their own tests.
+
+use alloc::string::ToString;
+use core::fmt::Display;
+
+use crate::{instruction::Instruction, Chip8, InvariantViolatedSnafu, Result};
+
+const F: usize = 0xF;
+
+/// Returns the result of 8xy4 for `vx` and `vy` and the value of VF afterwards, the carry.
+pub fn add(vx: u8, vy: u8) -> (u8, u8) {
+    let sum = u16::from(vx) + u16::from(vy);
+    (sum as u8, (sum > 0xFF) as u8)
+}
+
+/// Returns the result of 8xy5 for `vx` and `vy` and the value of VF afterwards, 1 if there is no
+/// borrow. 8xy7 gives `sub(vy, vx)`.
+pub fn sub(vx: u8, vy: u8) -> (u8, u8) {
+    (vx.wrapping_sub(vy), (vx >= vy) as u8)
+}
+
+/// Returns the result of 8xy6 for the shifted `value` and the value of VF afterwards, the bit
+/// shifted out.
+pub fn shift_right(value: u8) -> (u8, u8) {
+    (value >> 1, value & 0x01)
+}
+
+/// Returns the result of 8xyE for the shifted `value` and the value of VF afterwards, the bit
+/// shifted out.
+pub fn shift_left(value: u8) -> (u8, u8) {
+    (value << 1, value >> 7)
+}
+
+/// Returns the digits that Fx33 stores for `value`, the hundreds first.
+///
+/// ```
+/// assert_eq!(chip8::verify::bcd(207), [2, 0, 7]);
+/// ```
+pub fn bcd(value: u8) -> [u8; 3] {
+    [value / 100, value / 10 % 10, value % 10]
+}
+
+/// Checks that 8xy4 sets Vx to the sum of Vx and Vy and VF to the carry, and leaves the other
+/// registers alone.
+///
+/// ```
+/// # fn main() -> Result<(), chip8::Error> {
+/// let mut chip8 = chip8::Chip8::from_bytes(&[], Default::default())?;
+/// for (vx, vy) in [(0x00, 0x00), (0x80, 0x80), (0xFF, 0x01), (0x12, 0x34)] {
+///     chip8.v_mut()[3] = vx;
+///     chip8.v_mut()[0xA] = vy;
+///     chip8::verify::check_add(&mut chip8, 3, 0xA)?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn check_add(chip8: &mut Chip8, x: u8, y: u8) -> Result<()> {
+    let v = *chip8.v();
+    check_arithmetic(
+        chip8,
+        "8xy4 sets Vx to Vx + Vy and VF to the carry",
+        Instruction::AddReg { x, y },
+        x,
+        add(v[usize::from(x)], v[usize::from(y)]),
+    )
+}
+
+/// Checks that 8xy5 sets Vx to Vx - Vy and VF to 1 if there is no borrow, and leaves the other
+/// registers alone.
+pub fn check_sub(chip8: &mut Chip8, x: u8, y: u8) -> Result<()> {
+    let v = *chip8.v();
+    check_arithmetic(
+        chip8,
+        "8xy5 sets Vx to Vx - Vy and VF to no borrow",
+        Instruction::SubReg { x, y },
+        x,
+        sub(v[usize::from(x)], v[usize::from(y)]),
+    )
+}
+
+/// Checks that 8xy7 sets Vx to Vy - Vx and VF to 1 if there is no borrow, and leaves the other
+/// registers alone.
+pub fn check_subn(chip8: &mut Chip8, x: u8, y: u8) -> Result<()> {
+    let v = *chip8.v();
+    check_arithmetic(
+        chip8,
+        "8xy7 sets Vx to Vy - Vx and VF to no borrow",
+        Instruction::SubN { x, y },
+        x,
+        sub(v[usize::from(y)], v[usize::from(x)]),
+    )
+}
+
+/// Checks that 8xy6 sets Vx to the shifted register (Vx with the shift quirk, Vy without)
+/// shifted right and VF to the bit shifted out, and leaves the other registers alone.
+pub fn check_shift_right(chip8: &mut Chip8, x: u8, y: u8) -> Result<()> {
+    let shifted = chip8.v()[usize::from(if chip8.quirks.shift { x } else { y })];
+    check_arithmetic(
+        chip8,
+        "8xy6 shifts right into Vx and the bit out into VF",
+        Instruction::ShiftRight { x, y },
+        x,
+        shift_right(shifted),
+    )
+}
+
+/// Checks that 8xyE sets Vx to the shifted register (Vx with the shift quirk, Vy without)
+/// shifted left and VF to the bit shifted out, and leaves the other registers alone.
+pub fn check_shift_left(chip8: &mut Chip8, x: u8, y: u8) -> Result<()> {
+    let shifted = chip8.v()[usize::from(if chip8.quirks.shift { x } else { y })];
+    check_arithmetic(
+        chip8,
+        "8xyE shifts left into Vx and the bit out into VF",
+        Instruction::ShiftLeft { x, y },
+        x,
+        shift_left(shifted),
+    )
+}
+
+/// Checks that Fx33 stores the digits of Vx at I..=(I + 2), failing if they are not in memory.
+pub fn check_bcd(chip8: &mut Chip8, x: u8) -> Result<()> {
+    let (digits, i) = (bcd(chip8.v()[usize::from(x)]), usize::from(chip8.i()));
+    chip8.execute(Instruction::StoreBcd { x })?;
+    for (offset, &digit) in digits.iter().enumerate() {
+        let actual = chip8.memory().get(i + offset).copied().unwrap_or_default();
+        ensure_equal("Fx33 stores the digits", format_args!("{:#06X}", i + offset), digit, actual)?;
+    }
+    Ok(())
+}
+
+/// Checks that Fx55 stores V0..=Vx at I..=(I + x) and Fx65 then loads the same values back, and
+/// that both leave I at I + x + 1 without the load/store quirk and alone with it. The values are
+/// scrambled between the two, and I is set back to where Fx55 stored them. Fails if I..=(I + x)
+/// is not in memory.
+pub fn check_store_restore(chip8: &mut Chip8, x: u8) -> Result<()> {
+    let (v, i) = (*chip8.v(), chip8.i());
+    let expected_i = if chip8.quirks.load_store {
+        i
+    } else {
+        i.wrapping_add(u16::from(x) + 1) & chip8.quirks.index_mask
+    };
+    let registers = 0..=usize::from(x);
+
+    chip8.execute(Instruction::Store { x })?;
+    for register in registers.clone() {
+        let address = usize::from(i) + register;
+        let actual = chip8.memory().get(address).copied().unwrap_or_default();
+        ensure_equal("Fx55 stores V0..=Vx", format_args!("{address:#06X}"), v[register], actual)?;
+    }
+    ensure_equal("Fx55 updates I", "I", expected_i, chip8.i())?;
+
+    for register in registers.clone() {
+        chip8.v_mut()[register] = !v[register];
+    }
+    chip8.set_i(i);
+    chip8.execute(Instruction::Restore { x })?;
+    for register in registers {
+        let actual = chip8.v()[register];
+        ensure_equal(
+            "Fx65 loads what Fx55 stored",
+            format_args!("V{register:X}"),
+            v[register],
+            actual,
+        )?;
+    }
+    ensure_equal("Fx65 updates I", "I", expected_i, chip8.i())
+}
+
+/// Executes `instruction`, an 8xy_ instruction that sets Vx and then VF to `result` and `flag`,
+/// and checks the registers.
+fn check_arithmetic(
+    chip8: &mut Chip8,
+    invariant: &'static str,
+    instruction: Instruction,
+    x: u8,
+    (result, flag): (u8, u8),
+) -> Result<()> {
+    let mut expected = *chip8.v();
+    expected[usize::from(x)] = result;
+    expected[F] = flag;
+    chip8.execute(instruction)?;
+    for (register, (&expected, &actual)) in expected.iter().zip(chip8.v()).enumerate() {
+        ensure_equal(invariant, format_args!("V{register:X}"), expected, actual)?;
+    }
+    Ok(())
+}
+
+/// Fails with `Error::InvariantViolated` if `expected` and `actual`, the values at `location`,
+/// differ.
+fn ensure_equal<T: Into<u16> + PartialEq>(
+    invariant: &'static str,
+    location: impl Display,
+    expected: T,
+    actual: T,
+) -> Result<()> {
+    if expected != actual {
+        let (location, expected, actual) = (location.to_string(), expected.into(), actual.into());
+        return InvariantViolatedSnafu { invariant, location, expected, actual }.fail();
+    }
+    Ok(())
+}
@@ -0,0 +1,48 @@
+//! Noticing when the ROM file changes, for `--watch`.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+/// How often the modification time of the file is checked.
+const CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+pub struct Watcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    next_check: Instant,
+}
+
+impl Watcher {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            modified: modified(path),
+            next_check: Instant::now() + CHECK_INTERVAL,
+        }
+    }
+
+    /// Returns whether the file has been modified since the last time this returned true,
+    /// checking at most every `CHECK_INTERVAL`. A file that is missing, as while some editors save
+    /// it, is not considered modified until it is back.
+    pub fn poll(&mut self) -> bool {
+        let now = Instant::now();
+        if now < self.next_check {
+            return false;
+        }
+        self.next_check = now + CHECK_INTERVAL;
+        match modified(&self.path) {
+            Some(modified) if self.modified != Some(modified) => {
+                self.modified = Some(modified);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
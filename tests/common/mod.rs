@@ -0,0 +1,72 @@
+//! Helpers shared by the integration tests, which run ROMs headlessly.
+
+// Each test crate uses only some of the helpers.
+#![allow(dead_code)]
+
+use std::{env, fs, path::Path};
+
+use chip8::{Chip8, Quirks, Screen};
+
+/// How many instructions are executed per frame, which is about the default 700 per second.
+pub const INSTRUCTIONS_PER_FRAME: u32 = 12;
+
+/// Reads a ROM from the `resources` directory.
+pub fn resource(path: &str) -> Vec<u8> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("resources").join(path);
+    fs::read(&path).unwrap_or_else(|err| panic!("cannot read {}: {err}", path.display()))
+}
+
+/// Runs `rom` with `quirks` for `frames` frames, with the random number generator seeded with 0
+/// so that runs are reproducible.
+pub fn run_headless(rom: &[u8], quirks: Quirks, frames: u32) -> Chip8 {
+    run_scripted(rom, quirks, frames, &[])
+}
+
+/// A key press or release at the start of a frame: the frame, the hex key, and true if pressed.
+pub type Input = (u32, u8, bool);
+
+/// Runs `rom` like `run_headless`, pressing and releasing keys as `script` says, in order.
+pub fn run_scripted(rom: &[u8], quirks: Quirks, frames: u32, script: &[Input]) -> Chip8 {
+    let mut chip8 = Chip8::builder().rom(rom).quirks(quirks).seed(0).build().unwrap();
+    let mut script = script.iter().peekable();
+    for frame in 0..frames {
+        while let Some(&(_, key, pressed)) = script.next_if(|&&(at, _, _)| at == frame) {
+            if pressed {
+                chip8.press_key(key).unwrap();
+            } else {
+                chip8.release_key(key).unwrap();
+            }
+        }
+        chip8
+            .run_frame(INSTRUCTIONS_PER_FRAME)
+            .unwrap_or_else(|err| panic!("frame {frame}: {err}"));
+    }
+    chip8
+}
+
+/// Returns the rows `rows` of `screen` as `Screen::to_ascii` formats them, without newlines.
+pub fn ascii_rows(screen: &Screen, rows: std::ops::Range<usize>) -> Vec<String> {
+    screen.to_ascii().lines().skip(rows.start).take(rows.len()).map(String::from).collect()
+}
+
+/// Compares `screen` with the golden image `tests/golden/{name}.txt`, in the format of
+/// `Screen::to_ascii`. If the `BLESS` environment variable is set, writes the image instead, so
+/// that intended changes are accepted with `BLESS=1 cargo test`.
+pub fn assert_golden(name: &str, screen: &Screen) {
+    let path =
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(format!("{name}.txt"));
+    let actual = screen.to_ascii();
+    if env::var_os("BLESS").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, &actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap_or_else(|err| {
+        panic!("cannot read {} ({err}); run with BLESS=1 to create it", path.display())
+    });
+    assert!(
+        actual == expected,
+        "the screen differs from {} (rerun with BLESS=1 if the change is intended):\n{actual}",
+        path.display()
+    );
+}
@@ -0,0 +1,41 @@
+//! Compares the screens of ROMs run deterministically, with scripted input, against the golden
+//! images in `tests/golden`, to pin the rendering.
+//!
+//! After an intended change to the rendering, update the images with
+//! `BLESS=1 cargo test --test golden` and review their diff.
+
+mod common;
+
+use chip8::Quirks;
+
+use common::{assert_golden, resource, run_headless, run_scripted};
+
+#[test]
+fn bc_test() {
+    let rom = resource("BC_Chip8Test/BC_test.ch8");
+    assert_golden("bc_test", &run_headless(&rom, Quirks::default(), 300).screen);
+}
+
+#[test]
+fn trip8_demo() {
+    let rom = resource("RS-C8004 - Trip8 Demo (2008)/Trip8 Demo (2008) [Revival Studios].ch8");
+    for frames in [100, 600] {
+        let chip8 = run_headless(&rom, Quirks::COSMAC_VIP, frames);
+        assert_golden(&format!("trip8_demo_{frames}"), &chip8.screen);
+    }
+}
+
+#[test]
+fn astro_dodge_title() {
+    let rom = resource("RS-C8003 - Astro Dodge (2008)/Astro Dodge (2008) [Revival Studios].ch8");
+    assert_golden("astro_dodge_title", &run_headless(&rom, Quirks::COSMAC_VIP, 300).screen);
+}
+
+#[test]
+fn astro_dodge_playing() {
+    let rom = resource("RS-C8003 - Astro Dodge (2008)/Astro Dodge (2008) [Revival Studios].ch8");
+    // Starts the game with 5 once the title has been shown, and then moves the ship with 4.
+    let script = [(520, 5, true), (525, 5, false), (600, 4, true), (640, 4, false)];
+    let chip8 = run_scripted(&rom, Quirks::COSMAC_VIP, 760, &script);
+    assert_golden("astro_dodge_playing", &chip8.screen);
+}
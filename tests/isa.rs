@@ -0,0 +1,59 @@
+//! Checks `chip8::isa` against the decoder.
+
+use chip8::{
+    instruction::Instruction,
+    isa::{self, Operand, OPCODES},
+};
+
+#[test]
+fn lookup_and_decode_agree_on_every_instruction() {
+    for instruction in 0..=u16::MAX {
+        let opcode = isa::lookup(instruction);
+        let decoded = Instruction::decode(instruction);
+        assert_eq!(opcode.is_some(), decoded.is_some(), "{instruction:04X}");
+        let (Some(opcode), Some(decoded)) = (opcode, decoded) else {
+            continue;
+        };
+        let encoded = decoded.encode();
+        assert!(
+            isa::lookup(encoded).is_some_and(|other| other.pattern == opcode.pattern),
+            "{instruction:04X} decodes to {decoded:?}, which is not a {}",
+            opcode.pattern
+        );
+        for &operand in opcode.operands {
+            assert_eq!(operand.extract(encoded), operand.extract(instruction), "{instruction:04X}");
+        }
+        let mut mnemonic = String::new();
+        opcode.write(instruction, &mut mnemonic).unwrap();
+        assert_eq!(decoded.to_string(), mnemonic, "{instruction:04X}");
+    }
+}
+
+#[test]
+fn operands_are_the_placeholders_of_the_mnemonics() {
+    for opcode in OPCODES {
+        let placeholders: Vec<_> = opcode
+            .mnemonic
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter_map(Operand::from_placeholder)
+            .collect();
+        assert_eq!(placeholders, opcode.operands, "{}", opcode.pattern);
+    }
+}
+
+#[test]
+fn mnemonics_match_cowgods_reference() {
+    let cases = [
+        (0x00E0, "CLS"),
+        (0x0123, "SYS 0x123"),
+        (0x3A0F, "SE VA, 0x0F"),
+        (0x8126, "SHR V1, V2"),
+        (0xB300, "JP V0, 0x300"),
+        (0xD015, "DRW V0, V1, 5"),
+        (0xF355, "LD [I], V3"),
+        (0xF365, "LD V3, [I]"),
+    ];
+    for (instruction, mnemonic) in cases {
+        assert_eq!(Instruction::decode(instruction).unwrap().to_string(), mnemonic);
+    }
+}
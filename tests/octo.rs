@@ -0,0 +1,36 @@
+//! Decompiles the ROMs in the `resources` directory and assembles them back.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chip8::octo;
+
+/// Returns the paths of the ROMs under `directory`, recursively, in order.
+fn find_roms(directory: &Path) -> Vec<PathBuf> {
+    let mut roms = Vec::new();
+    for entry in fs::read_dir(directory).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            roms.extend(find_roms(&path));
+        } else if path.extension().is_some_and(|extension| extension == "ch8") {
+            roms.push(path);
+        }
+    }
+    roms.sort();
+    roms
+}
+
+#[test]
+fn decompiled_roms_assemble_to_the_same_roms() {
+    let roms = find_roms(&Path::new(env!("CARGO_MANIFEST_DIR")).join("resources"));
+    assert!(!roms.is_empty());
+    for path in roms {
+        let rom = fs::read(&path).unwrap();
+        let source = octo::decompile(&rom);
+        let assembled = octo::assemble(&source)
+            .unwrap_or_else(|err| panic!("{}: {err}\n{source}", path.display()));
+        assert!(assembled == rom, "{} does not round-trip:\n{source}", path.display());
+    }
+}
@@ -0,0 +1,32 @@
+//! Runs test ROMs headlessly and checks what they show, such as the verdict of a test ROM. Whole
+//! screens are pinned by the golden images of `tests/golden.rs` instead.
+//!
+//! The suite of Timendus (<https://github.com/Timendus/chip8-test-suite>) is not included, since
+//! it is licensed under the GPL, but its ROMs can be checked the same way.
+
+mod common;
+
+use chip8::Quirks;
+
+use common::{ascii_rows, resource, run_headless};
+
+#[test]
+fn bc_test_shows_bon() {
+    let rom = resource("BC_Chip8Test/BC_test.ch8");
+    let chip8 = run_headless(&rom, Quirks::default(), 300);
+    assert!(chip8.is_halted());
+    // "BON" (good) rather than one of the error numbers.
+    assert_eq!(
+        ascii_rows(&chip8.screen, 11..19),
+        [
+            ".....................OOOO.....OOOO...O....O.....................",
+            ".....................O...O...O....O..OO...O.....................",
+            ".....................O...O...O....O..O.O..O.....................",
+            ".....................OOOO....O....O..O..O.O.....................",
+            ".....................O...O...O....O..O...OO.....................",
+            ".....................O...O...O....O..O....O.....................",
+            ".....................O...O...O....O..O....O.....................",
+            ".....................OOOO.....OOOO...O....O.....................",
+        ]
+    );
+}